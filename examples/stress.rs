@@ -1,15 +1,32 @@
 use bevy::prelude::*;
 use bevy_infinite_grid::{InfiniteGridBundle, InfiniteGridPlugin, InfiniteGridSettings};
-use bevy_marching_cubes::{MarchingCubesPlugin, chunk::Chunk};
+use bevy_marching_cubes::{ChunkRequest, MarchingCubesPlugin, chunk::Chunk};
 
 use bevy_panorbit_camera::{PanOrbitCamera, PanOrbitCameraPlugin};
 use noiz::prelude::*;
 
+type TerrainNoise = Noise<
+    LayeredNoise<
+        Normed<f32>,
+        Persistence,
+        Octave<MixCellGradients<OrthoGrid, Smoothstep, QuickGradients>>,
+    >,
+>;
+
 fn main() {
+    let mut noise = TerrainNoise::default();
+    noise.set_frequency(0.06);
+
     App::new()
         .add_plugins((
             DefaultPlugins,
-            MarchingCubesPlugin::default(),
+            MarchingCubesPlugin::default().with_generator(
+                move |_coord: IVec3, origin: Vec3, chunk: &mut Chunk| {
+                    chunk.for_each_corner_offset(origin, |x, y, z, value| {
+                        *value = noise.sample_for(Vec3::new(x, y, z));
+                    });
+                },
+            ),
             PanOrbitCameraPlugin,
             InfiniteGridPlugin,
             #[cfg(not(target_arch = "wasm32"))]
@@ -54,39 +71,26 @@ fn spawn_chunks(
     keyboard: Res<ButtonInput<KeyCode>>,
 ) {
     if keyboard.just_pressed(KeyCode::Space) {
-        const CHUNK_SIZE: i32 = 32;
+        const CHUNK_SIZE: u32 = 32;
         const CHUNK_DIM: i32 = 16;
 
-        let mut noise = Noise::<
-            LayeredNoise<
-                Normed<f32>,
-                Persistence,
-                Octave<MixCellGradients<OrthoGrid, Smoothstep, QuickGradients>>,
-            >,
-        >::default();
-        noise.set_frequency(0.06);
+        let material = materials.add(StandardMaterial {
+            base_color: Color::srgb(1., 0., 0.),
+            ..Default::default()
+        });
 
         for x in -CHUNK_DIM..CHUNK_DIM {
             for z in -CHUNK_DIM..CHUNK_DIM {
-                let translation: Vec3 = Vec3::new(
-                    x as f32 * CHUNK_SIZE as f32,
-                    0.,
-                    z as f32 * CHUNK_SIZE as f32,
+                // Filled and meshed together on the async pool.
+                let request = ChunkRequest::new(
+                    IVec3::new(x, 0, z),
+                    UVec3::new(CHUNK_SIZE, 128, CHUNK_SIZE),
+                    1.,
                 );
 
-                let mut chunk =
-                    Chunk::new(CHUNK_SIZE as usize, 128, CHUNK_SIZE as usize).with_threshold(0.);
-                chunk.for_each_corner_offset(translation, |x, y, z, value| {
-                    *value = noise.sample_for(Vec3::new(x, y, z));
-                });
-
                 commands.spawn((
-                    chunk,
-                    Transform::from_translation(translation),
-                    MeshMaterial3d(materials.add(StandardMaterial {
-                        base_color: Color::srgb(1., 0., 0.),
-                        ..Default::default()
-                    })),
+                    request.bundle(),
+                    MeshMaterial3d(material.clone()),
                     // Wireframe,
                 ));
             }
@@ -1,12 +1,11 @@
-use bevy::{
-    prelude::*,
-    tasks::{AsyncComputeTaskPool, Task, block_on, futures_lite::future},
-};
+use bevy::prelude::*;
 use bevy_infinite_grid::{InfiniteGridBundle, InfiniteGridPlugin, InfiniteGridSettings};
-use bevy_marching_cubes::{MarchingCubesPlugin, chunk::Chunk};
+use bevy_marching_cubes::{
+    ChunkStreamer, ChunkStreamingPlugin, MarchingCubesPlugin, chunk::Chunk,
+    streaming::StreamedChunk,
+};
 use bevy_panorbit_camera::{PanOrbitCamera, PanOrbitCameraPlugin};
 use noiz::prelude::*;
-use std::collections::HashSet;
 
 type TerrainNoise = Noise<
     LayeredNoise<
@@ -16,25 +15,45 @@ type TerrainNoise = Noise<
     >,
 >;
 
-#[derive(Component)]
-struct ChunkFillTask(Task<Chunk>);
+const CHUNK_SIZE: u32 = 64;
+const CHUNK_HEIGHT: u32 = 128;
+
+#[derive(Resource)]
+struct TerrainMaterial(Handle<StandardMaterial>);
 
 fn main() {
+    let mut noise = TerrainNoise::default();
+    noise.set_frequency(0.06);
+
     App::new()
         .add_plugins((
             DefaultPlugins,
-            MarchingCubesPlugin::default(),
+            MarchingCubesPlugin::default().with_generator(
+                move |_coord: IVec3, origin: Vec3, chunk: &mut Chunk| {
+                    chunk.for_each_corner_offset(origin, |x, y, z, value| {
+                        *value = noise.sample_for(Vec3::new(x, y, z));
+                    });
+                },
+            ),
+            ChunkStreamingPlugin::default()
+                .with_chunk_size(UVec3::new(CHUNK_SIZE, CHUNK_HEIGHT, CHUNK_SIZE))
+                .with_y_range(0..=0),
             PanOrbitCameraPlugin,
             InfiniteGridPlugin,
             #[cfg(not(target_arch = "wasm32"))]
             bevy::pbr::wireframe::WireframePlugin::default(),
         ))
         .add_systems(Startup, setup)
-        .add_systems(Update, (spawn_chunk_tasks, poll_chunk_tasks, debug))
+        .add_systems(Update, (follow_focus, add_material, debug))
         .run();
 }
 
-fn setup(mut commands: Commands) {
+fn setup(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
+    commands.insert_resource(TerrainMaterial(materials.add(StandardMaterial {
+        base_color: Color::srgb(1., 0., 0.),
+        ..Default::default()
+    })));
+
     commands.spawn(InfiniteGridBundle {
         settings: InfiniteGridSettings {
             fadeout_distance: 1000.0,
@@ -53,6 +72,10 @@ fn setup(mut commands: Commands) {
         Transform::from_xyz(50., 200., 50.).looking_at(Vec3::ZERO, Vec3::Y),
     ));
 
+    // Streams chunks around the camera's focus point rather than the camera itself, which
+    // orbits high above the terrain.
+    commands.spawn(ChunkStreamer::new(2.5, 3.5));
+
     commands.spawn((
         DirectionalLight {
             illuminance: light_consts::lux::FULL_DAYLIGHT,
@@ -62,82 +85,28 @@ fn setup(mut commands: Commands) {
     ));
 }
 
-fn spawn_chunk_tasks(
-    mut commands: Commands,
+fn follow_focus(
     pan_orbit: Query<&PanOrbitCamera>,
-    mut noise: Local<TerrainNoise>,
-    mut spawned: Local<HashSet<IVec3>>,
+    mut streamer: Query<&mut Transform, With<ChunkStreamer>>,
 ) {
-    const CHUNK_SIZE: i32 = 64;
-    const CHUNK_HEIGHT: usize = 128;
-    const CHUNK_RADIUS: i32 = 2;
-
-    noise.set_frequency(0.06);
-
-    let origin = pan_orbit
+    let focus = pan_orbit
         .single()
         .expect("No PanOrbitCamera found")
         .target_focus;
-
-    let origin_chunk = IVec3::new(
-        (origin.x / CHUNK_SIZE as f32).floor() as i32,
-        0,
-        (origin.z / CHUNK_SIZE as f32).floor() as i32,
-    );
-
-    let task_pool = AsyncComputeTaskPool::get();
-
-    for dx in -CHUNK_RADIUS..=CHUNK_RADIUS {
-        for dz in -CHUNK_RADIUS..=CHUNK_RADIUS {
-            if dx * dx + dz * dz > CHUNK_RADIUS * CHUNK_RADIUS {
-                continue;
-            }
-
-            let coord = IVec3::new(origin_chunk.x + dx, 0, origin_chunk.z + dz);
-            if !spawned.insert(coord) {
-                continue;
-            }
-
-            let translation = Vec3::new(
-                coord.x as f32 * CHUNK_SIZE as f32,
-                0.,
-                coord.z as f32 * CHUNK_SIZE as f32,
-            );
-
-            let noise_copy = *noise;
-
-            let task = task_pool.spawn(async move {
-                let mut chunk = Chunk::new(CHUNK_SIZE as usize, CHUNK_HEIGHT, CHUNK_SIZE as usize)
-                    .with_threshold(0.);
-                chunk.for_each_corner_offset(translation, |x, y, z, value| {
-                    *value = noise_copy.sample_for(Vec3::new(x, y, z));
-                });
-                chunk
-            });
-
-            commands.spawn((
-                ChunkFillTask(task),
-                Transform::from_translation(translation),
-            ));
-        }
+    for mut transform in &mut streamer {
+        transform.translation = Vec3::new(focus.x, CHUNK_HEIGHT as f32 / 2., focus.z);
     }
 }
 
-fn poll_chunk_tasks(
+fn add_material(
     mut commands: Commands,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut tasks: Query<(Entity, &mut ChunkFillTask)>,
+    material: Res<TerrainMaterial>,
+    added: Query<Entity, Added<StreamedChunk>>,
 ) {
-    for (entity, mut fill_task) in &mut tasks {
-        if let Some(chunk) = block_on(future::poll_once(&mut fill_task.0)) {
-            commands.entity(entity).remove::<ChunkFillTask>().insert((
-                chunk,
-                MeshMaterial3d(materials.add(StandardMaterial {
-                    base_color: Color::srgb(1., 0., 0.),
-                    ..Default::default()
-                })),
-            ));
-        }
+    for entity in &added {
+        commands
+            .entity(entity)
+            .insert(MeshMaterial3d(material.0.clone()));
     }
 }
 
@@ -0,0 +1,221 @@
+use crate::{
+    transition_tables::{CUBE_CORNER_OFFSETS, CUBE_FACE_CORNERS},
+    types::Value,
+};
+
+/// Pairs of corners at opposite ends of a body diagonal, using the corner numbering of
+/// [`CUBE_CORNER_OFFSETS`].
+const BODY_DIAGONALS: [[usize; 2]; 4] = [[0, 6], [1, 7], [2, 4], [3, 5]];
+
+/// Returns `true` if the inside corners of a face whose inside corners lie on one diagonal
+/// are joined across the face. This is the asymptotic decider.
+///
+/// `inside` and `outside` hold the values at the two ends of each diagonal. The bilinear
+/// interpolant over the face has a saddle where the two diagonals' regions meet; the inside
+/// corners are joined when the saddle is inside, otherwise the outside corners are.
+///
+/// Both voxels sharing the face make the same choice, which is what keeps the surface
+/// closed across it.
+#[inline]
+pub fn face_joins_inside(inside: [Value; 2], outside: [Value; 2], threshold: Value) -> bool {
+    let [a, c] = inside.map(|v| v - threshold);
+    let [b, d] = outside.map(|v| v - threshold);
+    // The saddle value is (ac - bd) / (a + c - b - d), whose denominator is negative.
+    a * c >= b * d
+}
+
+/// Returns `true` if the classic marching cubes triangulation of a voxel is ambiguous.
+///
+/// `corners` holds the voxel's corner values in [`CUBE_CORNER_OFFSETS`] order. A voxel is
+/// ambiguous when any face has its inside corners on one diagonal, or when its only inside
+/// (or only outside) corners are the two ends of a body diagonal, which may or may not be
+/// joined by a tunnel through the voxel.
+pub fn is_ambiguous(corners: &[Value; 8], threshold: Value) -> bool {
+    let inside = corners.map(|v| v <= threshold);
+    let diagonal_face = CUBE_FACE_CORNERS.iter().any(|&[a, b, c, d]| {
+        inside[a] == inside[c] && inside[b] == inside[d] && inside[a] != inside[b]
+    });
+    if diagonal_face {
+        return true;
+    }
+    let count = inside.iter().filter(|&&i| i).count();
+    (count == 2 || count == 6)
+        && BODY_DIAGONALS
+            .iter()
+            .any(|&[a, b]| inside[a] == inside[b] && inside[a] == (count == 2))
+}
+
+/// Labels the corners of a voxel by which part of the voxel's boundary they belong to.
+///
+/// Corners on the same side of the threshold share a label when a path along the voxel's
+/// faces joins them without crossing the surface: along an edge, or across a face whose
+/// diagonal is decided by [`face_joins_inside`].
+pub(crate) fn boundary_components(corners: &[Value; 8], threshold: Value) -> [usize; 8] {
+    let inside = corners.map(|v| v <= threshold);
+    let mut parent = [0, 1, 2, 3, 4, 5, 6, 7];
+    for face in CUBE_FACE_CORNERS {
+        for i in 0..4 {
+            let (a, b) = (face[i], face[(i + 1) % 4]);
+            if inside[a] == inside[b] {
+                union(&mut parent, a, b);
+            }
+        }
+        let [a, b, c, d] = face;
+        if inside[a] == inside[c] && inside[b] == inside[d] && inside[a] != inside[b] {
+            let (ins, outs) = if inside[a] {
+                ([a, c], [b, d])
+            } else {
+                ([b, d], [a, c])
+            };
+            let values = |pair: [usize; 2]| pair.map(|i| corners[i]);
+            let [p, q] = if face_joins_inside(values(ins), values(outs), threshold) {
+                ins
+            } else {
+                outs
+            };
+            union(&mut parent, p, q);
+        }
+    }
+    [0, 1, 2, 3, 4, 5, 6, 7].map(|i| find(&mut parent, i))
+}
+
+/// Labels the corners of a voxel by which connected part of their side of the surface they
+/// belong to, within the voxel's trilinear interpolant.
+///
+/// Unlike [`boundary_components`], paths may pass through the voxel's interior, so corners
+/// joined by a tunnel share a label. Inside and outside corners never share one.
+///
+/// Every part of either side touches one of the four edges along Z, since the interpolant
+/// has no extremum inside the voxel and each Z slice is bilinear. Sweeping along Z, two
+/// edges are joined while both are on that side at the same height and the slice joins
+/// them: always for neighbouring edges, and by the asymptotic decider for opposite ones.
+pub(crate) fn trilinear_components(corners: &[Value; 8], threshold: Value) -> [usize; 8] {
+    let inside = corners.map(|v| v - threshold);
+    let outside = corners.map(|v| threshold - v);
+    let inside_labels = sweep_components(&inside);
+    let outside_labels = sweep_components(&outside);
+    [0, 1, 2, 3, 4, 5, 6, 7].map(|i| {
+        if corners[i] <= threshold {
+            inside_labels[i]
+        } else {
+            outside_labels[i] + 8
+        }
+    })
+}
+
+/// Returns the pair of loops to join with a tube, if the voxel's interior joins two parts of
+/// one side that its boundary separates.
+///
+/// `loops` holds one `(inside corner, outside corner)` pair for each surface loop traced on
+/// the voxel's faces, from any of its crossings. A tube between two loops replaces the two
+/// disks that would cap them, so it is only chosen when exactly one pair of loops qualifies:
+/// their parts of one side are separate on the boundary but joined through the interior,
+/// and they share the part of the other side between them.
+pub(crate) fn tunnel(
+    corners: &[Value; 8],
+    threshold: Value,
+    loops: &[(usize, usize)],
+) -> Option<(usize, usize)> {
+    if loops.len() < 2 {
+        return None;
+    }
+    let boundary = boundary_components(corners, threshold);
+    let interior = trilinear_components(corners, threshold);
+    let mut found = None;
+    for i in 0..loops.len() {
+        for j in i + 1..loops.len() {
+            let (inside_i, outside_i) = loops[i];
+            let (inside_j, outside_j) = loops[j];
+            let joins = |(side_i, side_j): (usize, usize), (other_i, other_j): (usize, usize)| {
+                boundary[side_i] != boundary[side_j]
+                    && boundary[other_i] == boundary[other_j]
+                    && interior[side_i] == interior[side_j]
+            };
+            if joins((inside_i, inside_j), (outside_i, outside_j))
+                || joins((outside_i, outside_j), (inside_i, inside_j))
+            {
+                if found.is_some() {
+                    return None;
+                }
+                found = Some((i, j));
+            }
+        }
+    }
+    found
+}
+
+/// Labels the corners where `w ≤ 0` by which part of that region they belong to; see
+/// [`trilinear_components`].
+///
+/// The four edges along Z run from corner `k` to corner `k + 4`, with `k` going around the
+/// bottom face.
+fn sweep_components(w: &[Value; 8]) -> [usize; 8] {
+    // Heights along each edge where it's in the region; values are linear along the edge.
+    let spans: [Option<(Value, Value)>; 4] = [0, 1, 2, 3].map(|k| {
+        let (bottom, top) = (w[k], w[k + 4]);
+        match (bottom <= 0.0, top <= 0.0) {
+            (true, true) => Some((0.0, 1.0)),
+            (true, false) => Some((0.0, bottom / (bottom - top))),
+            (false, true) => Some((bottom / (bottom - top), 1.0)),
+            (false, false) => None,
+        }
+    });
+    let overlap = |a: usize, b: usize| match (spans[a], spans[b]) {
+        (Some((lo_a, hi_a)), Some((lo_b, hi_b))) if lo_a.max(lo_b) <= hi_a.min(hi_b) => {
+            Some((lo_a.max(lo_b), hi_a.min(hi_b)))
+        }
+        _ => None,
+    };
+    let at = |k: usize, z: Value| w[k] + (w[k + 4] - w[k]) * z;
+
+    let mut parent = [0, 1, 2, 3];
+    for k in 0..4 {
+        if overlap(k, (k + 1) % 4).is_some() {
+            union(&mut parent, k, (k + 1) % 4);
+        }
+    }
+    for [a, b] in [[0, 2], [1, 3]] {
+        let Some((lo, hi)) = overlap(a, b) else {
+            continue;
+        };
+        let (c, d) = ((a + 1) % 4, (a + 3) % 4);
+        // The slice joins `a` and `b` where their product outweighs the other diagonal's;
+        // the difference is quadratic in z, so check its maximum over the overlap.
+        let q = |z: Value| at(a, z) * at(b, z) - at(c, z) * at(d, z);
+        let (q0, q1, q2) = (q(0.0), q(0.5), q(1.0));
+        let (a2, a1) = (2.0 * (q0 - 2.0 * q1 + q2), 4.0 * q1 - 3.0 * q0 - q2);
+        let peak = if a2 < 0.0 {
+            (-a1 / (2.0 * a2)).clamp(lo, hi)
+        } else {
+            lo
+        };
+        if [lo, hi, peak].into_iter().any(|z| q(z) >= 0.0) {
+            union(&mut parent, a, b);
+        }
+    }
+    [0, 1, 2, 3, 4, 5, 6, 7].map(|i| find(&mut parent, i % 4))
+}
+
+/// Returns the root of `i` in a union-find forest.
+fn find<const N: usize>(parent: &mut [usize; N], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Joins the sets of `a` and `b` in a union-find forest.
+fn union<const N: usize>(parent: &mut [usize; N], a: usize, b: usize) {
+    let (a, b) = (find(parent, a), find(parent, b));
+    parent[a] = b;
+}
+
+/// Returns the corner index of `offset` from a voxel's minimum corner.
+#[inline]
+pub(crate) fn corner_index(offset: [usize; 3]) -> usize {
+    CUBE_CORNER_OFFSETS
+        .iter()
+        .position(|&o| o == offset)
+        .expect("not a voxel corner")
+}
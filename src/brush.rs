@@ -0,0 +1,462 @@
+use bevy::prelude::*;
+
+use crate::{
+    chunk::Chunk,
+    history::{ChunkSnapshot, Edit, EditHistory},
+    sdf::{self, smooth_min},
+    types::{MaterialId, Value},
+};
+
+/// Shape of a [`Brush`], centred on [`Brush::centre`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BrushShape {
+    /// Ball of the given radius.
+    Sphere { radius: f32 },
+    /// Axis-aligned box reaching `half_extents` from the centre along each axis.
+    Box { half_extents: Vec3 },
+    /// Segment from `centre - half_axis` to `centre + half_axis`, swept by a ball of
+    /// `radius`.
+    Capsule { half_axis: Vec3, radius: f32 },
+}
+
+impl BrushShape {
+    /// Returns the signed distance from `p`, relative to the shape's centre, to its surface.
+    /// Negative inside.
+    pub fn distance(&self, p: Vec3) -> f32 {
+        match *self {
+            BrushShape::Sphere { radius } => sdf::sphere(radius)(p.x, p.y, p.z),
+            BrushShape::Box { half_extents } => sdf::cuboid(half_extents)(p.x, p.y, p.z),
+            BrushShape::Capsule { half_axis, radius } => {
+                sdf::capsule(-half_axis, half_axis, radius)(p.x, p.y, p.z)
+            }
+        }
+    }
+
+    /// Returns how far the shape reaches from its centre along each axis.
+    pub fn half_size(&self) -> Vec3 {
+        match *self {
+            BrushShape::Sphere { radius } => Vec3::splat(radius),
+            BrushShape::Box { half_extents } => half_extents,
+            BrushShape::Capsule { half_axis, radius } => half_axis.abs() + radius,
+        }
+    }
+}
+
+/// What a [`Brush`] does to the field values it covers.
+///
+/// Values are treated as signed distances: below the chunk's threshold is solid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BrushOp {
+    /// Unions the shape with the terrain, blending over [`Brush::falloff`].
+    Add,
+    /// Carves the shape out of the terrain, blending over [`Brush::falloff`].
+    Subtract,
+    /// Blurs values with a 3×3×3 Gaussian kernel.
+    Smooth,
+    /// Pulls values towards the plane through [`Brush::centre`] with the given normal,
+    /// which points out of the terrain.
+    Flatten { normal: Vec3 },
+    /// Sets the material of every corner inside the shape. [`Brush::strength`] and
+    /// [`Brush::falloff`] are ignored.
+    Paint(MaterialId),
+}
+
+/// A world-space terrain edit.
+///
+/// Write it as a message and [`MarchingCubesPlugin`](crate::MarchingCubesPlugin) applies it
+/// to every [`Chunk`] the brush overlaps before the next meshing pass, using each chunk's
+/// [`Transform`] translation as its minimum corner. Edited chunks are marked as changed, so
+/// with the `auto_queue` feature they are re-meshed automatically:
+///
+/// ```rust,ignore
+/// fn dig(mut brushes: MessageWriter<Brush>, target: Res<CursorHit>) {
+///     let shape = BrushShape::Sphere { radius: 3.0 };
+///     brushes.write(Brush::new(target.0, shape, BrushOp::Subtract).with_falloff(1.0));
+/// }
+/// ```
+///
+/// Because every chunk is edited at the same world positions, the faces shared by
+/// neighbours stay identical, except that [`BrushOp::Smooth`] clamps its kernel at chunk
+/// boundaries.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct Brush {
+    /// World-space centre of the shape.
+    pub centre: Vec3,
+    /// Shape of the brush.
+    pub shape: BrushShape,
+    /// Operation applied inside the shape.
+    pub op: BrushOp,
+    /// How strongly the brush applies, from `0.0` (no effect) to `1.0`. Default: `1.0`.
+    pub strength: f32,
+    /// World-space width of the blend at the shape's edge. For [`BrushOp::Add`] and
+    /// [`BrushOp::Subtract`] this is the smooth union radius; for the other operations the
+    /// effect fades in over this distance inside the shape. Default: `0.0`.
+    pub falloff: f32,
+}
+
+impl Brush {
+    /// Creates a brush at `centre` with full strength and no falloff.
+    pub fn new(centre: Vec3, shape: BrushShape, op: BrushOp) -> Self {
+        Self {
+            centre,
+            shape,
+            op,
+            strength: 1.,
+            falloff: 0.,
+        }
+    }
+
+    /// Sets how strongly the brush applies, from `0.0` to `1.0`.
+    pub fn with_strength(mut self, strength: f32) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    /// Sets the world-space width of the blend at the shape's edge.
+    pub fn with_falloff(mut self, falloff: f32) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /// Returns the world-space minimum and maximum corners of the region the brush can
+    /// change.
+    pub fn bounds(&self) -> (Vec3, Vec3) {
+        let mut half_size = self.shape.half_size();
+        if matches!(self.op, BrushOp::Add | BrushOp::Subtract) {
+            half_size += self.falloff.max(0.0);
+        }
+        (self.centre - half_size, self.centre + half_size)
+    }
+
+    /// Applies the brush to `chunk`, whose minimum corner sits at `origin` in world space.
+    ///
+    /// Returns whether any value or material changed. The chunk's grids are only copied out
+    /// of a shared [`Arc`](std::sync::Arc) if they change.
+    pub fn apply(&self, chunk: &mut Chunk, origin: Vec3) -> bool {
+        let Some((lo, hi)) = self.corner_range(chunk, origin) else {
+            return false;
+        };
+        let corners = || {
+            (lo[2]..=hi[2]).flat_map(move |z| {
+                (lo[1]..=hi[1]).flat_map(move |y| (lo[0]..=hi[0]).map(move |x| [x, y, z]))
+            })
+        };
+        let world =
+            |[x, y, z]: [usize; 3]| origin + Vec3::new(x as f32, y as f32, z as f32) * chunk.scale;
+
+        if let BrushOp::Paint(material) = self.op {
+            let inside: Vec<[usize; 3]> = corners()
+                .filter(|&c| self.shape.distance(world(c) - self.centre) <= 0.0)
+                .filter(|&[x, y, z]| chunk.get_material(x, y, z) != material)
+                .collect();
+            for &[x, y, z] in &inside {
+                chunk.set_material(x, y, z, material);
+            }
+            return !inside.is_empty();
+        }
+
+        let threshold = chunk.threshold;
+        let updates: Vec<([usize; 3], Value)> = corners()
+            .filter_map(|c @ [x, y, z]| {
+                let p = world(c);
+                let d = self.shape.distance(p - self.centre);
+                let old = chunk.get(x, y, z);
+                let new = match self.op {
+                    BrushOp::Add => {
+                        let target = threshold + smooth_min(old - threshold, d, self.falloff);
+                        old + (target - old) * self.strength
+                    }
+                    BrushOp::Subtract => {
+                        let target = threshold - smooth_min(threshold - old, d, self.falloff);
+                        old + (target - old) * self.strength
+                    }
+                    BrushOp::Smooth => old + (gaussian(chunk, c) - old) * self.fade(d),
+                    BrushOp::Flatten { normal } => {
+                        let target = threshold + (p - self.centre).dot(normal.normalize_or_zero());
+                        old + (target - old) * self.fade(d)
+                    }
+                    BrushOp::Paint(_) => unreachable!("handled above"),
+                };
+                (new != old).then_some((c, new))
+            })
+            .collect();
+
+        if updates.is_empty() {
+            return false;
+        }
+        let values = chunk.values_mut();
+        for ([x, y, z], value) in updates {
+            values.set(x, y, z, value);
+        }
+        chunk.update_value_range();
+        true
+    }
+
+    /// Returns the inclusive range of `chunk`'s corners inside [`bounds`](Brush::bounds), or
+    /// `None` if the brush misses the chunk.
+    fn corner_range(&self, chunk: &Chunk, origin: Vec3) -> Option<([usize; 3], [usize; 3])> {
+        let (min, max) = self.bounds();
+        let [dx, dy, dz] = chunk.values().dims();
+        let last = Vec3::new(dx as f32, dy as f32, dz as f32) - 1.0;
+        let lo = ((min - origin) / chunk.scale).ceil().max(Vec3::ZERO);
+        let hi = ((max - origin) / chunk.scale).floor().min(last);
+        if lo.cmpgt(hi).any() {
+            return None;
+        }
+        Some((
+            lo.as_uvec3().to_array().map(|c| c as usize),
+            hi.as_uvec3().to_array().map(|c| c as usize),
+        ))
+    }
+
+    /// Returns how much of a fading operation applies at distance `d` from the shape's
+    /// surface: `0` outside, easing up to [`strength`](Brush::strength) `falloff` inside.
+    fn fade(&self, d: f32) -> f32 {
+        let t = if self.falloff > 0.0 {
+            (-d / self.falloff).clamp(0.0, 1.0)
+        } else if d <= 0.0 {
+            1.0
+        } else {
+            0.0
+        };
+        self.strength * t * t * (3.0 - 2.0 * t)
+    }
+}
+
+/// Returns the 3×3×3 Gaussian-weighted average of the values around corner `c`, clamping
+/// samples to the chunk.
+fn gaussian(chunk: &Chunk, [x, y, z]: [usize; 3]) -> Value {
+    const WEIGHTS: [f32; 3] = [0.25, 0.5, 0.25];
+    let [dx, dy, dz] = chunk.values().dims();
+    let offset = |c: usize, o: usize, dim: usize| (c + o).saturating_sub(1).min(dim - 1);
+    let mut sum = 0.0;
+    for (oz, wz) in WEIGHTS.iter().enumerate() {
+        for (oy, wy) in WEIGHTS.iter().enumerate() {
+            for (ox, wx) in WEIGHTS.iter().enumerate() {
+                let value = chunk.get(offset(x, ox, dx), offset(y, oy, dy), offset(z, oz, dz));
+                sum += value * wx * wy * wz;
+            }
+        }
+    }
+    sum
+}
+
+/// Applies every [`Brush`] written this frame to the chunks it overlaps.
+///
+/// Only chunks whose values or materials actually change are marked as changed. If an
+/// [`EditHistory`] exists, each brush is recorded as one [`Edit`].
+pub(crate) fn apply_brushes(
+    mut brushes: MessageReader<Brush>,
+    mut chunks: Query<(Entity, &mut Chunk, &Transform)>,
+    mut history: Option<ResMut<EditHistory>>,
+) {
+    for brush in brushes.read() {
+        let mut edit = Edit::default();
+        for (entity, mut chunk, transform) in chunks.iter_mut() {
+            let before = history.is_some().then(|| ChunkSnapshot::of(&chunk));
+            if !brush.apply(chunk.bypass_change_detection(), transform.translation) {
+                continue;
+            }
+            chunk.set_changed();
+            if let Some(before) = before {
+                edit.record(entity, &before, &chunk);
+            }
+        }
+        if let Some(history) = history.as_mut() {
+            history.push(edit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CENTRE: Vec3 = Vec3::splat(4.0);
+
+    /// Returns an 8³ chunk with every value set to `value`.
+    fn uniform(value: Value) -> Chunk {
+        let mut chunk = Chunk::new(8, 8, 8);
+        chunk.fill(&|_, _, _| value);
+        chunk
+    }
+
+    fn sphere(radius: f32, op: BrushOp) -> Brush {
+        Brush::new(CENTRE, BrushShape::Sphere { radius }, op)
+    }
+
+    #[test]
+    fn add_and_subtract_take_the_union_and_difference() {
+        let mut air = uniform(1.0);
+        assert!(sphere(2.0, BrushOp::Add).apply(&mut air, Vec3::ZERO));
+        assert_eq!(air.get(4, 4, 4), -2.0);
+        assert_eq!(air.get(6, 4, 4), 0.0);
+        assert_eq!(air.get(7, 4, 4), 1.0);
+        assert_eq!(air.value_range(), Some((-2.0, 1.0)));
+
+        let mut solid = uniform(-1.0);
+        assert!(sphere(2.0, BrushOp::Subtract).apply(&mut solid, Vec3::ZERO));
+        assert_eq!(solid.get(4, 4, 4), 2.0);
+        assert_eq!(solid.get(5, 4, 4), 1.0);
+        assert_eq!(solid.get(7, 4, 4), -1.0);
+
+        // Half strength goes halfway.
+        let mut air = uniform(1.0);
+        sphere(2.0, BrushOp::Add)
+            .with_strength(0.5)
+            .apply(&mut air, Vec3::ZERO);
+        assert_eq!(air.get(4, 4, 4), -0.5);
+
+        // Adding what's already there changes nothing.
+        assert!(!sphere(2.0, BrushOp::Add).apply(&mut uniform(-3.0), Vec3::ZERO));
+    }
+
+    #[test]
+    fn falloff_blends_the_edge() {
+        // One unit past the sphere, as far from it as the surface of the air is.
+        let mut sharp = uniform(1.0);
+        sphere(2.0, BrushOp::Add).apply(&mut sharp, Vec3::ZERO);
+        assert_eq!(sharp.get(7, 4, 4), 1.0);
+        let mut blended = uniform(1.0);
+        sphere(2.0, BrushOp::Add)
+            .with_falloff(1.0)
+            .apply(&mut blended, Vec3::ZERO);
+        assert_eq!(blended.get(7, 4, 4), 0.75);
+
+        // Fading operations apply fully `falloff` inside the shape and not at all on its
+        // surface.
+        let flatten = Brush::new(
+            CENTRE,
+            BrushShape::Box {
+                half_extents: Vec3::splat(2.0),
+            },
+            BrushOp::Flatten { normal: Vec3::Y },
+        );
+        let mut chunk = uniform(1.0);
+        flatten.with_falloff(1.0).apply(&mut chunk, Vec3::ZERO);
+        assert_eq!(chunk.get(4, 4, 4), 0.0);
+        assert_eq!(chunk.get(4, 5, 4), 1.0);
+        assert_eq!(chunk.get(4, 3, 4), -1.0);
+        assert_eq!(chunk.get(6, 4, 4), 1.0);
+        let partial = {
+            let mut chunk = uniform(1.0);
+            flatten.with_falloff(2.0).apply(&mut chunk, Vec3::ZERO);
+            chunk.get(5, 4, 4)
+        };
+        // `d = -1` is halfway through a falloff of 2, where smoothstep is 0.5.
+        assert_eq!(partial, 0.5);
+    }
+
+    #[test]
+    fn flatten_pulls_values_onto_the_plane() {
+        let mut chunk = uniform(1.0);
+        let brush = Brush::new(
+            CENTRE,
+            BrushShape::Box {
+                half_extents: Vec3::splat(2.0),
+            },
+            BrushOp::Flatten {
+                normal: Vec3::new(0.0, 2.0, 0.0),
+            },
+        );
+        assert!(brush.apply(&mut chunk, Vec3::ZERO));
+        for y in 2..=6 {
+            assert_eq!(chunk.get(3, y, 5), y as f32 - 4.0);
+        }
+        assert_eq!(chunk.get(3, 7, 5), 1.0);
+        assert_eq!(chunk.get(1, 2, 5), 1.0);
+    }
+
+    #[test]
+    fn smooth_blurs_inside_the_shape() {
+        let mut chunk = uniform(1.0);
+        chunk.set(4, 4, 4, -1.0);
+        assert!(sphere(1.5, BrushOp::Smooth).apply(&mut chunk, Vec3::ZERO));
+        // The spike has 1/8 of the kernel's weight at its own corner and 1/16 at a face
+        // neighbour's.
+        assert_eq!(chunk.get(4, 4, 4), 0.75);
+        assert_eq!(chunk.get(5, 4, 4), 0.875);
+        // A diagonal neighbour would blur too, but lies outside the sphere.
+        assert_eq!(chunk.get(5, 5, 5), 1.0);
+    }
+
+    #[test]
+    fn paint_sets_materials_inside_the_shape() {
+        let mut chunk = uniform(1.0);
+        let brush = sphere(1.5, BrushOp::Paint(3)).with_falloff(5.0);
+        assert!(brush.apply(&mut chunk, Vec3::ZERO));
+        assert_eq!(chunk.get_material(4, 4, 4), 3);
+        assert_eq!(chunk.get_material(5, 5, 4), 3);
+        assert_eq!(chunk.get_material(5, 5, 5), 0);
+        assert_eq!(chunk.get_material(6, 4, 4), 0);
+        assert_eq!(chunk.value_range(), Some((1.0, 1.0)));
+        // Painting again changes nothing.
+        assert!(!brush.apply(&mut chunk, Vec3::ZERO));
+    }
+
+    #[test]
+    fn corner_range_covers_the_bounds_in_chunk_space() {
+        let chunk = Chunk::new(8, 8, 8).with_scale(0.5);
+        let origin = Vec3::new(2.0, 0.0, 0.0);
+        let at =
+            |centre: Vec3| Brush::new(centre, BrushShape::Sphere { radius: 1.0 }, BrushOp::Smooth);
+        assert_eq!(
+            at(Vec3::new(3.2, 1.0, 1.0)).corner_range(&chunk, origin),
+            Some(([1, 0, 0], [4, 4, 4]))
+        );
+        // Add and Subtract also reach over their falloff.
+        let add = Brush {
+            op: BrushOp::Add,
+            ..at(Vec3::new(3.2, 1.0, 1.0))
+        };
+        assert_eq!(
+            add.with_falloff(0.5).corner_range(&chunk, origin),
+            Some(([0, 0, 0], [5, 5, 5]))
+        );
+        // Clamped to the chunk's corners.
+        assert_eq!(
+            at(Vec3::new(6.0, 4.0, -0.5)).corner_range(&chunk, origin),
+            Some(([6, 6, 0], [8, 8, 1]))
+        );
+        assert_eq!(
+            at(Vec3::new(0.5, 1.0, 1.0)).corner_range(&chunk, origin),
+            None
+        );
+        assert_eq!(
+            at(Vec3::new(7.5, 1.0, 1.0)).corner_range(&chunk, origin),
+            None
+        );
+    }
+
+    #[test]
+    fn brushes_straddling_chunks_edit_both_sides_alike() {
+        let mut left = uniform(1.0);
+        let mut right = uniform(1.0);
+        let mut far = uniform(1.0);
+        for op in [
+            BrushOp::Add,
+            BrushOp::Subtract,
+            BrushOp::Flatten { normal: Vec3::Y },
+            BrushOp::Paint(2),
+        ] {
+            let brush = Brush::new(
+                Vec3::new(8.2, 3.6, 4.4),
+                BrushShape::Capsule {
+                    half_axis: Vec3::new(0.0, 1.0, 0.5),
+                    radius: 2.0,
+                },
+                op,
+            )
+            .with_falloff(0.5);
+            assert!(brush.apply(&mut left, Vec3::ZERO), "{op:?}");
+            assert!(brush.apply(&mut right, Vec3::new(8.0, 0.0, 0.0)), "{op:?}");
+            assert!(!brush.apply(&mut far, Vec3::new(16.0, 0.0, 0.0)), "{op:?}");
+            assert_eq!(left.values().layer(0, 8), right.values().layer(0, 0));
+            assert_eq!(
+                left.materials.as_ref().map(|m| m.layer(0, 8)),
+                right.materials.as_ref().map(|m| m.layer(0, 0))
+            );
+        }
+        assert_eq!(far.values(), uniform(1.0).values());
+    }
+}
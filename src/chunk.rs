@@ -1,15 +1,26 @@
 use std::sync::Arc;
 
-use bevy::prelude::*;
+use bevy::{asset::RenderAssetUsages, prelude::*};
+use rayon::{
+    iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator},
+    slice::ParallelSliceMut,
+};
 
-use crate::types::{CompiledFunction, Value};
+use crate::{
+    grid::VoxelGrid,
+    mesh::{MeshingAlgorithm, NormalMode},
+    plugin::EmptyChunk,
+    transvoxel::TransitionFaces,
+    types::{MaterialId, Value},
+    utils::normalize_or_zero,
+};
 
 /// A voxel grid that holds scalar field values and produces a marching cubes mesh.
 ///
 /// The grid has `(size_x + 1) × (size_y + 1) × (size_z + 1)` corner points
 /// and `size_x × size_y × size_z` voxels.
 ///
-/// Values are stored as `values[z][y][x]`.
+/// Values are stored in a single contiguous [`VoxelGrid`] in `[z][y][x]` order.
 ///
 /// `values` is wrapped in an [`Arc`] so the async mesh-generation task can hold a reference
 /// to the grid without copying it.
@@ -26,8 +37,37 @@ pub struct Chunk {
     pub scale: Value,
     /// Iso-surface threshold — corners ≤ threshold are "inside".
     pub threshold: Value,
-    /// Scalar field values, indexed `[z][y][x]`.
-    pub values: Arc<Vec<Vec<Vec<Value>>>>,
+    /// Scalar field values, one per corner; see [`values`](Chunk::values).
+    values: Arc<VoxelGrid>,
+    /// Optional material per corner, with the same dimensions as `values`.
+    ///
+    /// When set, generated meshes carry per-vertex material IDs and blend weights; see
+    /// [`GeneratedMesh::material_ids`](crate::GeneratedMesh::material_ids).
+    pub materials: Option<Arc<VoxelGrid<MaterialId>>>,
+    /// Optional linear RGBA colour per corner, with the same dimensions as `values`.
+    ///
+    /// When set, generated meshes carry interpolated per-vertex colours; see
+    /// [`GeneratedMesh::colors`](crate::GeneratedMesh::colors).
+    pub colors: Option<Arc<VoxelGrid<[f32; 4]>>>,
+    /// Optional field gradient per corner, with the same dimensions as `values`.
+    ///
+    /// Used as Hermite data by [`MeshingAlgorithm::DualContouring`]; exact gradients keep
+    /// sharp edges and corners that estimated ones round off. Zero vectors fall back to the
+    /// gradient estimated from `values`. Not updated by [`set`](Chunk::set) or brushes, so
+    /// refill or clear them after editing.
+    pub normals: Option<Arc<VoxelGrid<[f32; 3]>>>,
+    /// Normal mode for this chunk. `None` uses [`MarchingCubesConfig::normal_mode`](crate::MarchingCubesConfig::normal_mode).
+    pub normal_mode: Option<NormalMode>,
+    /// Meshing algorithm for this chunk. `None` uses [`MarchingCubesConfig::meshing_algorithm`](crate::MarchingCubesConfig::meshing_algorithm).
+    pub meshing_algorithm: Option<MeshingAlgorithm>,
+    /// Worlds that keep this chunk's mesh assets. `None` uses [`MarchingCubesConfig::asset_usage`](crate::MarchingCubesConfig::asset_usage).
+    pub asset_usage: Option<RenderAssetUsages>,
+    /// Faces bordering a neighbour at twice this chunk's resolution, meshed with transition
+    /// cells. Only used for chunks with a [`ChunkCoord`](crate::neighbours::ChunkCoord).
+    pub transition_faces: TransitionFaces,
+    /// Lower and upper bound of `values`, `None` when unknown; see
+    /// [`value_range`](Chunk::value_range).
+    value_range: Option<(Value, Value)>,
 }
 
 impl Default for Chunk {
@@ -38,7 +78,15 @@ impl Default for Chunk {
             size_z: 0,
             scale: 1.,
             threshold: 0.,
-            values: Arc::new(vec![]),
+            values: Arc::new(VoxelGrid::new([1, 1, 1], 0.)),
+            materials: None,
+            colors: None,
+            normals: None,
+            normal_mode: None,
+            meshing_algorithm: None,
+            asset_usage: None,
+            transition_faces: TransitionFaces::NONE,
+            value_range: Some((0., 0.)),
         }
     }
 }
@@ -49,7 +97,7 @@ impl Chunk {
     /// All values are initialised to `0.0`. The grid has `(size + 1)` corners
     /// per axis so that every voxel has a full set of 8 corners.
     pub fn new(size_x: usize, size_y: usize, size_z: usize) -> Self {
-        let values = vec![vec![vec![0.; size_x + 1]; size_y + 1]; size_z + 1];
+        let values = VoxelGrid::new([size_x + 1, size_y + 1, size_z + 1], 0.);
         Self {
             size_x,
             size_y,
@@ -67,11 +115,14 @@ impl Chunk {
 
     /// Replaces the scalar field values with a previously saved [`Arc`].
     ///
+    /// Also accepts a bare [`VoxelGrid`], so nested `values[z][y][x]` data can be passed as
+    /// `VoxelGrid::from(nested)`.
+    ///
     /// Use this to respawn a chunk with data retained from a prior despawn:
     ///
     /// ```rust,ignore
     /// // Before despawning — store the Arc, not a deep copy:
-    /// let saved = Arc::clone(&chunk.values);
+    /// let saved = Arc::clone(chunk.values());
     /// commands.entity(entity).despawn();
     ///
     /// // Later, respawn with zero allocation:
@@ -83,12 +134,48 @@ impl Chunk {
     /// ```
     ///
     /// # Panics
-    /// Panics (in debug) if the Arc's grid dimensions don't match `size_x/y/z + 1`.
-    pub fn with_values(mut self, values: Arc<Vec<Vec<Vec<Value>>>>) -> Self {
-        debug_assert_eq!(values.len(), self.size_z + 1);
-        debug_assert_eq!(values[0].len(), self.size_y + 1);
-        debug_assert_eq!(values[0][0].len(), self.size_x + 1);
+    /// Panics (in debug) if the grid dimensions don't match `size_x/y/z + 1`.
+    pub fn with_values(mut self, values: impl Into<Arc<VoxelGrid>>) -> Self {
+        let values = values.into();
+        debug_assert_eq!(
+            values.dims(),
+            [self.size_x + 1, self.size_y + 1, self.size_z + 1]
+        );
         self.values = values;
+        self.update_value_range();
+        self
+    }
+
+    /// Sets the material of every corner.
+    ///
+    /// # Panics
+    /// Panics (in debug) if the grid dimensions don't match `size_x/y/z + 1`.
+    pub fn with_materials(mut self, materials: impl Into<Arc<VoxelGrid<MaterialId>>>) -> Self {
+        let materials = materials.into();
+        debug_assert_eq!(materials.dims(), self.values.dims());
+        self.materials = Some(materials);
+        self
+    }
+
+    /// Sets the colour of every corner.
+    ///
+    /// # Panics
+    /// Panics (in debug) if the grid dimensions don't match `size_x/y/z + 1`.
+    pub fn with_colors(mut self, colors: impl Into<Arc<VoxelGrid<[f32; 4]>>>) -> Self {
+        let colors = colors.into();
+        debug_assert_eq!(colors.dims(), self.values.dims());
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Sets the field gradient of every corner; see [`normals`](Chunk::normals).
+    ///
+    /// # Panics
+    /// Panics (in debug) if the grid dimensions don't match `size_x/y/z + 1`.
+    pub fn with_normals(mut self, normals: impl Into<Arc<VoxelGrid<[f32; 3]>>>) -> Self {
+        let normals = normals.into();
+        debug_assert_eq!(normals.dims(), self.values.dims());
+        self.normals = Some(normals);
         self
     }
 
@@ -98,29 +185,100 @@ impl Chunk {
         self
     }
 
+    /// Overrides [`MarchingCubesConfig::normal_mode`](crate::MarchingCubesConfig::normal_mode) for this chunk.
+    pub fn with_normal_mode(mut self, normal_mode: NormalMode) -> Self {
+        self.normal_mode = Some(normal_mode);
+        self
+    }
+
+    /// Overrides [`MarchingCubesConfig::meshing_algorithm`](crate::MarchingCubesConfig::meshing_algorithm) for this chunk.
+    pub fn with_meshing_algorithm(mut self, algorithm: MeshingAlgorithm) -> Self {
+        self.meshing_algorithm = Some(algorithm);
+        self
+    }
+
+    /// Overrides [`MarchingCubesConfig::asset_usage`](crate::MarchingCubesConfig::asset_usage) for this chunk.
+    pub fn with_asset_usage(mut self, asset_usage: RenderAssetUsages) -> Self {
+        self.asset_usage = Some(asset_usage);
+        self
+    }
+
+    /// Marks the faces that border a neighbour at twice this chunk's resolution.
+    ///
+    /// See [`TransitionFaces`] for how the two chunks must line up.
+    pub fn with_transition_faces(mut self, faces: TransitionFaces) -> Self {
+        self.transition_faces = faces;
+        self
+    }
+
+    /// Returns a lower and upper bound of every value in the chunk, or `None` after
+    /// [`values_mut`](Chunk::values_mut) until [`update_value_range`](Chunk::update_value_range)
+    /// is called.
+    ///
+    /// Kept exact by [`fill`](Chunk::fill) and the other bulk writes, and widened by
+    /// [`set`](Chunk::set). [`MarchingCubesPlugin`](crate::MarchingCubesPlugin) uses it to
+    /// skip meshing chunks that lie entirely on one side of the threshold; see
+    /// [`empty_kind`](Chunk::empty_kind).
+    pub fn value_range(&self) -> Option<(Value, Value)> {
+        self.value_range
+    }
+
+    /// Recomputes [`value_range`](Chunk::value_range) from every value in the chunk.
+    ///
+    /// Only needed after writing through [`values_mut`](Chunk::values_mut).
+    pub fn update_value_range(&mut self) {
+        self.value_range = Some(
+            self.values
+                .as_slice()
+                .iter()
+                .fold((Value::INFINITY, Value::NEG_INFINITY), |(low, high), &v| {
+                    (low.min(v), high.max(v))
+                }),
+        );
+    }
+
+    /// Returns which side of the threshold the whole chunk is on, or `None` if the surface
+    /// may pass through it or the [`value_range`](Chunk::value_range) is unknown.
+    ///
+    /// Such chunks produce no triangles, so they are never meshed.
+    pub fn empty_kind(&self) -> Option<EmptyChunk> {
+        let (low, high) = self.value_range?;
+        if low > self.threshold {
+            Some(EmptyChunk::Outside)
+        } else if high <= self.threshold {
+            Some(EmptyChunk::Inside)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the scalar field values, one per corner.
+    ///
+    /// Clone the [`Arc`] to keep the values past a despawn without copying them; see
+    /// [`with_values`](Chunk::with_values).
+    pub fn values(&self) -> &Arc<VoxelGrid> {
+        &self.values
+    }
+
     /// Returns a mutable reference to the inner values grid.
     ///
-    /// If the Arc is shared this will clone the data first (copy-on-write).
-    fn values_mut(&mut self) -> &mut Vec<Vec<Vec<Value>>> {
+    /// If the Arc is shared this will clone the data first (copy-on-write). The
+    /// [`value_range`](Chunk::value_range) becomes unknown, so the chunk is meshed even if it
+    /// is empty until [`update_value_range`](Chunk::update_value_range) is called.
+    pub fn values_mut(&mut self) -> &mut VoxelGrid {
+        self.value_range = None;
         Arc::make_mut(&mut self.values)
     }
 
     /// Calls `f(x, y, z, &mut value)` for every corner in the grid.
     ///
-    /// Coordinates are integer voxel indices, not world-space positions.
-    pub fn for_each_corner<F>(&mut self, mut f: F)
+    /// Coordinates are integer voxel indices, not world-space positions. Corners are
+    /// visited in memory order (X fastest, then Y, then Z).
+    pub fn for_each_corner<F>(&mut self, f: F)
     where
         F: FnMut(f32, f32, f32, &mut Value),
     {
-        let (size_x, size_y, size_z) = (self.size_x, self.size_y, self.size_z);
-        let values = self.values_mut();
-        for x in 0..=size_x {
-            for y in 0..=size_y {
-                for z in 0..=size_z {
-                    f(x as f32, y as f32, z as f32, &mut values[z][y][x]);
-                }
-            }
-        }
+        self.for_each_corner_scaled(Vec3::ZERO, 1., f);
     }
 
     /// Like [`for_each_corner`](Chunk::for_each_corner), but scales each index by
@@ -128,35 +286,103 @@ impl Chunk {
     ///
     /// Coordinates passed to `f` are true world-space positions, so the closure
     /// can sample a noise function or SDF directly without needing to know the scale.
-    pub fn for_each_corner_offset<F>(&mut self, min_point: Vec3, mut f: F)
+    pub fn for_each_corner_offset<F>(&mut self, min_point: Vec3, f: F)
     where
         F: FnMut(f32, f32, f32, &mut Value),
     {
-        let (size_x, size_y, size_z) = (self.size_x, self.size_y, self.size_z);
         let scale = self.scale;
+        self.for_each_corner_scaled(min_point, scale, f);
+    }
+
+    /// Visits every corner in memory order, passing `min_point + index * scale` to `f`.
+    fn for_each_corner_scaled<F>(&mut self, min_point: Vec3, scale: Value, mut f: F)
+    where
+        F: FnMut(f32, f32, f32, &mut Value),
+    {
         let values = self.values_mut();
-        for x in 0..=size_x {
-            for y in 0..=size_y {
-                for z in 0..=size_z {
-                    f(
-                        min_point.x + x as f32 * scale,
-                        min_point.y + y as f32 * scale,
-                        min_point.z + z as f32 * scale,
-                        &mut values[z][y][x],
-                    );
-                }
-            }
+        let [dims_x, dims_y, _] = values.dims();
+        for (i, value) in values.as_mut_slice().iter_mut().enumerate() {
+            let x = i % dims_x;
+            let y = (i / dims_x) % dims_y;
+            let z = i / (dims_x * dims_y);
+            f(
+                min_point.x + x as f32 * scale,
+                min_point.y + y as f32 * scale,
+                min_point.z + z as f32 * scale,
+                value,
+            );
         }
+        self.update_value_range();
     }
 
     /// Returns the scalar field value at corner `(x, y, z)`.
     pub fn get(&self, x: usize, y: usize, z: usize) -> Value {
-        self.values[z][y][x]
+        self.values.get(x, y, z)
     }
 
     /// Sets the scalar field value at corner `(x, y, z)`.
     pub fn set(&mut self, x: usize, y: usize, z: usize, v: Value) {
-        self.values_mut()[z][y][x] = v
+        Arc::make_mut(&mut self.values).set(x, y, z, v);
+        if let Some((low, high)) = &mut self.value_range {
+            (*low, *high) = (low.min(v), high.max(v));
+        }
+    }
+
+    /// Returns the material at corner `(x, y, z)`, or `0` if the chunk has no materials.
+    pub fn get_material(&self, x: usize, y: usize, z: usize) -> MaterialId {
+        self.materials
+            .as_ref()
+            .map_or(0, |materials| materials.get(x, y, z))
+    }
+
+    /// Sets the material at corner `(x, y, z)`.
+    ///
+    /// The first call on a chunk without materials creates a grid with every other corner
+    /// set to `0`.
+    pub fn set_material(&mut self, x: usize, y: usize, z: usize, material: MaterialId) {
+        let dims = self.values.dims();
+        let materials = self
+            .materials
+            .get_or_insert_with(|| Arc::new(VoxelGrid::new(dims, 0)));
+        Arc::make_mut(materials).set(x, y, z, material);
+    }
+
+    /// Returns the colour at corner `(x, y, z)`, or opaque white if the chunk has no colours.
+    pub fn get_color(&self, x: usize, y: usize, z: usize) -> [f32; 4] {
+        self.colors
+            .as_ref()
+            .map_or([1.0; 4], |colors| colors.get(x, y, z))
+    }
+
+    /// Sets the colour at corner `(x, y, z)`.
+    ///
+    /// The first call on a chunk without colours creates a grid with every other corner
+    /// set to opaque white.
+    pub fn set_color(&mut self, x: usize, y: usize, z: usize, color: [f32; 4]) {
+        let dims = self.values.dims();
+        let colors = self
+            .colors
+            .get_or_insert_with(|| Arc::new(VoxelGrid::new(dims, [1.0; 4])));
+        Arc::make_mut(colors).set(x, y, z, color);
+    }
+
+    /// Returns the field gradient at corner `(x, y, z)`, or zero if the chunk has no normals.
+    pub fn get_normal(&self, x: usize, y: usize, z: usize) -> [f32; 3] {
+        self.normals
+            .as_ref()
+            .map_or([0.0; 3], |normals| normals.get(x, y, z))
+    }
+
+    /// Sets the field gradient at corner `(x, y, z)`.
+    ///
+    /// The first call on a chunk without normals creates a grid with every other corner set
+    /// to zero, which falls back to the estimated gradient.
+    pub fn set_normal(&mut self, x: usize, y: usize, z: usize, normal: [f32; 3]) {
+        let dims = self.values.dims();
+        let normals = self
+            .normals
+            .get_or_insert_with(|| Arc::new(VoxelGrid::new(dims, [0.0; 3])));
+        Arc::make_mut(normals).set(x, y, z, normal);
     }
 
     /// Returns the 8 corner indices `[x, y, z]` of the voxel at `(x, y, z)`.
@@ -193,19 +419,332 @@ impl Chunk {
     /// Fills the chunk by evaluating `function` at every corner.
     ///
     /// Coordinates passed to `function` are scaled by [`scale`](Chunk::scale).
-    pub fn fill(&mut self, function: &CompiledFunction) {
-        let (size_x, size_y, size_z) = (self.size_x, self.size_y, self.size_z);
+    /// Z slabs of the grid are filled in parallel using Rayon. Takes a
+    /// [`CompiledFunction`](crate::types::CompiledFunction) or any closure, including ones
+    /// that borrow.
+    pub fn fill<F>(&mut self, function: &F)
+    where
+        F: Fn(f32, f32, f32) -> Value + Sync + ?Sized,
+    {
         let scale = self.scale;
         let values = self.values_mut();
-        (0..=size_x).for_each(|x| {
-            (0..=size_y).for_each(|y| {
-                (0..=size_z).for_each(|z| {
-                    let xf = x as Value * scale;
+        let [dims_x, dims_y, _] = values.dims();
+        values
+            .as_mut_slice()
+            .par_chunks_mut(dims_x * dims_y)
+            .enumerate()
+            .for_each(|(z, slab)| {
+                let zf = z as Value * scale;
+                for (y, row) in slab.chunks_mut(dims_x).enumerate() {
                     let yf = y as Value * scale;
-                    let zf = z as Value * scale;
-                    values[z][y][x] = function(xf, yf, zf);
-                })
+                    for (x, value) in row.iter_mut().enumerate() {
+                        *value = function(x as Value * scale, yf, zf);
+                    }
+                }
+            });
+        self.update_value_range();
+    }
+
+    /// Fills [`normals`](Chunk::normals) with the gradient of `function` at every corner,
+    /// estimated by central differences a thousandth of a voxel wide.
+    ///
+    /// Pass the same function as to [`fill`](Chunk::fill). Z slabs of the grid are filled in
+    /// parallel using Rayon.
+    pub fn fill_normals<F>(&mut self, function: &F)
+    where
+        F: Fn(f32, f32, f32) -> Value + Sync + ?Sized,
+    {
+        let scale = self.scale;
+        let h = scale * 1e-3;
+        let mut normals = VoxelGrid::new(self.values.dims(), [0.0; 3]);
+        let [dims_x, dims_y, _] = normals.dims();
+        normals
+            .as_mut_slice()
+            .par_chunks_mut(dims_x * dims_y)
+            .enumerate()
+            .for_each(|(z, slab)| {
+                let zf = z as Value * scale;
+                for (y, row) in slab.chunks_mut(dims_x).enumerate() {
+                    let yf = y as Value * scale;
+                    for (x, normal) in row.iter_mut().enumerate() {
+                        let xf = x as Value * scale;
+                        *normal = normalize_or_zero([
+                            function(xf + h, yf, zf) - function(xf - h, yf, zf),
+                            function(xf, yf + h, zf) - function(xf, yf - h, zf),
+                            function(xf, yf, zf + h) - function(xf, yf, zf - h),
+                        ]);
+                    }
+                }
+            });
+        self.normals = Some(Arc::new(normals));
+    }
+
+    /// Fills the chunk like [`fill`](Chunk::fill), but only samples `function` near the
+    /// surface.
+    ///
+    /// `bounds(min, max)` must return a lower and upper bound of `function` over the box from
+    /// `min` to `max`, in the same coordinates as `function`; see
+    /// [`SdfNode::bounds`](crate::SdfNode::bounds) and
+    /// [`sdf::lipschitz_bounds`](crate::sdf::lipschitz_bounds). The grid is split
+    /// recursively, and regions whose bounds stay above or below
+    /// [`threshold`](Chunk::threshold) are skipped: their corners are set to the bound nearest
+    /// the threshold, which puts them on the correct side of the surface without sampling
+    /// them.
+    ///
+    /// Bounds are taken over each region widened by two voxels, so every corner of a cell
+    /// the surface crosses, and every neighbour used for its gradient, is sampled exactly.
+    /// The mesh is the same as after a full fill. Small regions near the surface are sampled
+    /// in parallel using Rayon.
+    ///
+    /// Corners beyond that margin are placeholders rather than samples of `function`. Edits
+    /// that read existing values, like a [`Brush`](crate::Brush) that smooths or carves into
+    /// them, give different results than after a full fill.
+    ///
+    /// ```rust,ignore
+    /// let shape = sdf::translate(sdf::sphere(4.0), Vec3::splat(16.0));
+    /// chunk.fill_pruned(&shape, |min, max| sdf::lipschitz_bounds(&shape, min, max, 1.0));
+    /// ```
+    pub fn fill_pruned<F, B>(&mut self, function: F, bounds: B)
+    where
+        F: Fn(f32, f32, f32) -> Value + Sync,
+        B: Fn(Vec3, Vec3) -> (Value, Value),
+    {
+        let (scale, threshold) = (self.scale, self.threshold);
+        let values = self.values_mut();
+        if values.is_empty() {
+            return;
+        }
+        let position = |[x, y, z]: [usize; 3]| Vec3::new(x as f32, y as f32, z as f32) * scale;
+        let margin = Vec3::splat(PRUNE_MARGIN * scale.abs());
+
+        let mut leaves = Vec::new();
+        let mut regions = vec![([0; 3], values.dims().map(|d| d - 1))];
+        while let Some((lo, hi)) = regions.pop() {
+            let (low, high) = bounds(position(lo) - margin, position(hi) + margin);
+            if low > threshold || high < threshold {
+                let value = if low > threshold { low } else { high };
+                for [x, y, z] in corners_between(lo, hi) {
+                    values.set(x, y, z, value);
+                }
+                continue;
+            }
+
+            let axis = (0..3).max_by_key(|&axis| hi[axis] - lo[axis]).unwrap();
+            let span = hi[axis] - lo[axis];
+            if span < PRUNE_LEAF_SPAN {
+                leaves.push((lo, hi));
+                continue;
+            }
+            let (mut lower_hi, mut upper_lo) = (hi, lo);
+            lower_hi[axis] = lo[axis] + span / 2;
+            upper_lo[axis] = lower_hi[axis] + 1;
+            regions.push((lo, lower_hi));
+            regions.push((upper_lo, hi));
+        }
+
+        let samples: Vec<Vec<Value>> = leaves
+            .par_iter()
+            .map(|&(lo, hi)| {
+                corners_between(lo, hi)
+                    .map(|c| {
+                        let p = position(c);
+                        function(p.x, p.y, p.z)
+                    })
+                    .collect()
             })
-        });
+            .collect();
+        for (&(lo, hi), samples) in leaves.iter().zip(samples) {
+            for ([x, y, z], value) in corners_between(lo, hi).zip(samples) {
+                values.set(x, y, z, value);
+            }
+        }
+        self.update_value_range();
+    }
+}
+
+/// Voxels of margin around each region when [`Chunk::fill_pruned`] bounds it.
+const PRUNE_MARGIN: f32 = 2.;
+
+/// Regions narrower than this many voxels on every axis are sampled without splitting
+/// further.
+const PRUNE_LEAF_SPAN: usize = 4;
+
+/// Iterates the corners from `lo` to `hi` inclusive, in memory order.
+fn corners_between(lo: [usize; 3], hi: [usize; 3]) -> impl Iterator<Item = [usize; 3]> {
+    (lo[2]..=hi[2]).flat_map(move |z| {
+        (lo[1]..=hi[1]).flat_map(move |y| (lo[0]..=hi[0]).map(move |x| [x, y, z]))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SdfNode, sdf, test_support::mesh_standalone};
+
+    /// Asserts that every algorithm meshes `full` and `pruned` identically.
+    fn assert_meshes_match(full: &Chunk, pruned: &Chunk, context: &str) {
+        for algorithm in [
+            MeshingAlgorithm::MarchingCubes,
+            MeshingAlgorithm::SurfaceNets,
+            MeshingAlgorithm::DualContouring,
+            MeshingAlgorithm::MarchingTetrahedra,
+        ] {
+            let expected = mesh_standalone(full, algorithm, NormalMode::Gradient);
+            let actual = mesh_standalone(pruned, algorithm, NormalMode::Gradient);
+            assert!(!expected.indices.is_empty());
+            assert!(
+                expected.vertices == actual.vertices
+                    && expected.indices == actual.indices
+                    && expected.normals == actual.normals,
+                "{algorithm:?} differs {context}"
+            );
+        }
+    }
+
+    #[test]
+    fn fill_pruned_meshes_like_fill() {
+        let shape = sdf::translate(sdf::torus(2.5, 0.8), Vec3::new(4.0, 3.9, 4.2));
+        let bounds = |min, max| sdf::lipschitz_bounds(&shape, min, max, 1.0);
+        for scale in [1.0, 0.5, 0.25] {
+            for threshold in [0.4, -0.4] {
+                let size = (8.0 / scale) as usize;
+                let chunk = || {
+                    Chunk::new(size, size, size)
+                        .with_scale(scale)
+                        .with_threshold(threshold)
+                };
+                let mut full = chunk();
+                full.fill(&shape);
+                let mut pruned = chunk();
+                pruned.fill_pruned(&shape, bounds);
+                assert_meshes_match(
+                    &full,
+                    &pruned,
+                    &format!("at scale {scale}, threshold {threshold}"),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fill_pruned_with_node_bounds_meshes_like_fill() {
+        use SdfNode::*;
+        let translate = |offset: Vec3, child| Translate {
+            offset,
+            children: vec![child],
+        };
+        let node = Union {
+            children: vec![
+                translate(
+                    Vec3::new(8.0, 8.0, 8.0),
+                    Repeat {
+                        period: Vec3::new(16.0, 0.0, 16.0),
+                        children: vec![Twist {
+                            rate: 0.3,
+                            children: vec![SmoothUnion {
+                                k: 0.8,
+                                children: vec![
+                                    Rotate {
+                                        rotation: Quat::from_euler(EulerRot::XYZ, 0.4, 0.3, 0.2),
+                                        children: vec![Cuboid {
+                                            half_extents: Vec3::new(1.2, 2.0, 1.0),
+                                        }],
+                                    },
+                                    translate(
+                                        Vec3::new(0.0, 1.5, 0.0),
+                                        Scale {
+                                            factor: 1.5,
+                                            children: vec![Sphere { radius: 0.8 }],
+                                        },
+                                    ),
+                                ],
+                            }],
+                        }],
+                    },
+                ),
+                translate(
+                    Vec3::new(8.0, 36.0, 12.0),
+                    Mirror {
+                        normal: Vec3::X,
+                        children: vec![translate(
+                            Vec3::new(1.5, 0.0, 0.0),
+                            SmoothDifference {
+                                k: 0.5,
+                                children: vec![
+                                    Sphere { radius: 1.5 },
+                                    translate(Vec3::new(0.8, 0.0, 0.0), Sphere { radius: 0.7 }),
+                                ],
+                            },
+                        )],
+                    },
+                ),
+                translate(
+                    Vec3::new(36.0, 24.0, 36.0),
+                    Bend {
+                        rate: 0.2,
+                        children: vec![SmoothIntersection {
+                            k: 0.5,
+                            children: vec![
+                                Cuboid {
+                                    half_extents: Vec3::new(3.0, 1.0, 1.0),
+                                },
+                                Cylinder {
+                                    radius: 1.2,
+                                    half_height: 2.0,
+                                },
+                            ],
+                        }],
+                    },
+                ),
+                translate(
+                    Vec3::new(24.0, 40.0, 28.0),
+                    Noise {
+                        amplitude: 0.3,
+                        frequency: 0.8,
+                        seed: 7,
+                        children: vec![Sphere { radius: 2.0 }],
+                    },
+                ),
+            ],
+        };
+
+        let distance = |x, y, z| node.distance(Vec3::new(x, y, z));
+        let chunk = || Chunk::new(48, 48, 48);
+        let mut full = chunk();
+        full.fill(&distance);
+        let mut pruned = chunk();
+        pruned.fill_pruned(distance, |min, max| node.bounds(min, max));
+        // Some regions were skipped rather than sampled.
+        assert_ne!(full.values(), pruned.values());
+        assert_meshes_match(&full, &pruned, "for the composite node");
+    }
+
+    #[test]
+    fn writes_keep_value_range_sound() {
+        let mut chunk = Chunk::new(4, 4, 4);
+        chunk.fill(&|_, _, _| 1.0);
+        assert_eq!(chunk.value_range(), Some((1.0, 1.0)));
+        assert_eq!(chunk.empty_kind(), Some(EmptyChunk::Outside));
+
+        // `set` widens the range, so the chunk is no longer empty.
+        chunk.set(2, 2, 2, -0.5);
+        assert_eq!(chunk.value_range(), Some((-0.5, 1.0)));
+        assert_eq!(chunk.empty_kind(), None);
+
+        // Writes through `values_mut` can't be tracked until the range is recomputed.
+        chunk.values_mut().set(2, 2, 2, 2.0);
+        assert_eq!(chunk.value_range(), None);
+        assert_eq!(chunk.empty_kind(), None);
+        chunk.update_value_range();
+        assert_eq!(chunk.value_range(), Some((1.0, 2.0)));
+        assert_eq!(chunk.empty_kind(), Some(EmptyChunk::Outside));
+
+        chunk.fill(&|_, _, _| -1.0);
+        assert_eq!(chunk.empty_kind(), Some(EmptyChunk::Inside));
+        // Values exactly at the threshold are inside.
+        assert_eq!(
+            chunk.with_threshold(-1.0).empty_kind(),
+            Some(EmptyChunk::Inside)
+        );
     }
 }
@@ -0,0 +1,236 @@
+use bevy::math::{Mat3, Vec3};
+
+use crate::{
+    grid::VoxelGrid,
+    mesh::{GeneratedMesh, NormalMode},
+    neighbours::ChunkPadding,
+    surface_nets::{VertexPlacement, run_dual},
+    types::{MaterialId, Value},
+};
+
+/// Eigenvalues of `AᵀA` below this fraction of the largest are treated as zero when solving
+/// a [`Qef`], i.e. singular values below roughly a tenth of the largest.
+const QEF_TOLERANCE: f32 = 0.01;
+
+/// Jacobi sweeps when diagonalising `AᵀA`; a 3×3 matrix converges well within this.
+const JACOBI_SWEEPS: usize = 8;
+
+/// Runs dual contouring over the given voxel grid.
+///
+/// Lays out cells and quads like surface nets, but places each cell's vertex using Hermite
+/// data: the exact point where the surface crosses each of the cell's edges, and the field
+/// gradient there. The vertex goes where the tangent planes through those points meet, so
+/// it lands on edges and corners of the surface instead of rounding them off.
+///
+/// ```text
+///   surface nets       dual contouring
+///   ──────•            ───────•
+///          ╲                  │
+///           •                 │
+///           │                 │
+/// ```
+///
+/// Gradients are interpolated from `normals` when given (see
+/// [`Chunk::normals`](crate::chunk::Chunk::normals)), and otherwise from the trilinear
+/// interpolation of the cell's corners, which sharpens features less. Vertices are kept
+/// inside their cell. Gradient vertex normals average the Hermite normals; use
+/// [`NormalMode::Flat`] to shade sharp features crisply.
+pub(crate) fn run_dual_contouring(
+    size_x: usize,
+    size_y: usize,
+    size_z: usize,
+    scale: Value,
+    threshold: Value,
+    normal_mode: NormalMode,
+    values: &VoxelGrid,
+    materials: Option<&VoxelGrid<MaterialId>>,
+    colors: Option<&VoxelGrid<[f32; 4]>>,
+    normals: Option<&VoxelGrid<[f32; 3]>>,
+    padding: &ChunkPadding,
+) -> GeneratedMesh {
+    run_dual(
+        [size_x, size_y, size_z],
+        scale,
+        threshold,
+        normal_mode,
+        values,
+        materials,
+        colors,
+        padding,
+        VertexPlacement::Qef { normals },
+    )
+}
+
+/// Quadratic error function of a cell: the sum of squared distances from a point to the
+/// tangent planes through its edge crossings.
+///
+/// Stored as the normal equations `AᵀA x = Aᵀb`, where each row of `A` is a plane normal
+/// and `b` holds the planes' offsets, plus the mass point of the crossings.
+pub(crate) struct Qef {
+    ata: Mat3,
+    atb: Vec3,
+    point_sum: Vec3,
+    count: u32,
+}
+
+impl Default for Qef {
+    // `Mat3::default()` is the identity.
+    fn default() -> Self {
+        Self {
+            ata: Mat3::ZERO,
+            atb: Vec3::ZERO,
+            point_sum: Vec3::ZERO,
+            count: 0,
+        }
+    }
+}
+
+impl Qef {
+    /// Adds the plane through `point` with unit `normal`.
+    pub(crate) fn add(&mut self, point: Vec3, normal: Vec3) {
+        self.ata += Mat3::from_cols(normal * normal.x, normal * normal.y, normal * normal.z);
+        self.atb += normal * normal.dot(point);
+        self.point_sum += point;
+        self.count += 1;
+    }
+
+    /// Returns the point minimising the error, closest to the mass point where the
+    /// minimum isn't unique.
+    ///
+    /// Solves relative to the mass point with the pseudo-inverse of `AᵀA`, dropping
+    /// directions the planes barely constrain: flat regions stay at the mass point, and
+    /// only planes that meet at an angle pull the vertex onto an edge or corner.
+    ///
+    /// # Panics
+    /// Panics (in debug) if no planes were added.
+    pub(crate) fn solve(&self) -> Vec3 {
+        debug_assert!(self.count > 0, "solving an empty QEF");
+        let mass_point = self.point_sum / self.count as f32;
+        let (eigenvalues, eigenvectors) = symmetric_eigen(self.ata);
+        let largest = eigenvalues.max_element();
+        if largest <= 0.0 {
+            return mass_point;
+        }
+        let inverse = eigenvalues.map(|e| {
+            if e > largest * QEF_TOLERANCE {
+                1.0 / e
+            } else {
+                0.0
+            }
+        });
+        let pseudo_inverse = eigenvectors * Mat3::from_diagonal(inverse) * eigenvectors.transpose();
+        mass_point + pseudo_inverse * (self.atb - self.ata * mass_point)
+    }
+}
+
+/// Diagonalises the symmetric matrix `m` with cyclic Jacobi rotations.
+///
+/// Returns the eigenvalues and a matrix with the matching eigenvectors as columns.
+fn symmetric_eigen(m: Mat3) -> (Vec3, Mat3) {
+    let mut a = m;
+    let mut v = Mat3::IDENTITY;
+    for _ in 0..JACOBI_SWEEPS {
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            let apq = a.col(q)[p];
+            if apq.abs() < 1e-12 {
+                continue;
+            }
+            // Rotation in the p-q plane that zeroes the off-diagonal entry `apq`.
+            let theta = (a.col(q)[q] - a.col(p)[p]) / (2.0 * apq);
+            let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+            let c = 1.0 / (t * t + 1.0).sqrt();
+            let s = t * c;
+            let mut rotation = Mat3::IDENTITY;
+            rotation.col_mut(p)[p] = c;
+            rotation.col_mut(q)[q] = c;
+            rotation.col_mut(q)[p] = s;
+            rotation.col_mut(p)[q] = -s;
+            a = rotation.transpose() * a * rotation;
+            v *= rotation;
+        }
+    }
+    (Vec3::new(a.x_axis.x, a.y_axis.y, a.z_axis.z), v)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::{BVec3, EulerRot, Quat};
+
+    use super::*;
+    use crate::{chunk::Chunk, mesh::MeshingAlgorithm, sdf, test_support::mesh_standalone};
+
+    #[test]
+    fn qef_solves_three_orthogonal_planes() {
+        let corner = Vec3::new(1.2, -0.4, 2.5);
+        for rotation in [
+            Quat::IDENTITY,
+            Quat::from_euler(EulerRot::XYZ, 0.7, -0.3, 1.1),
+        ] {
+            let mut qef = Qef::default();
+            for (axis, offset) in [(Vec3::X, Vec3::Y), (Vec3::Y, Vec3::Z), (Vec3::Z, Vec3::X)] {
+                // Two points on each plane, away from the corner.
+                for step in [0.3, -0.8] {
+                    qef.add(corner + rotation * (offset * step), rotation * axis);
+                }
+            }
+            assert!(qef.solve().distance(corner) < 1e-4, "{rotation:?}");
+        }
+    }
+
+    #[test]
+    fn qef_keeps_flat_regions_at_the_mass_point() {
+        let mut qef = Qef::default();
+        let points = [
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(0.5, 1.0, 1.0),
+        ];
+        for point in points {
+            qef.add(point, Vec3::Y);
+        }
+        assert!(qef.solve().distance(Vec3::new(0.5, 1.0, 1.0 / 3.0)) < 1e-5);
+    }
+
+    /// Returns the distance from `point` to the nearest vertex of `mesh`.
+    fn nearest_vertex(mesh: &GeneratedMesh, point: Vec3) -> f32 {
+        mesh.vertices
+            .iter()
+            .map(|&v| Vec3::from(v).distance(point))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    #[test]
+    fn dual_contouring_keeps_cuboid_corners() {
+        // Faces just past a row of corners, so the Hermite normals interpolated along each
+        // crossed edge come mostly from the outside corner, which sees a single face.
+        let (min, max) = (Vec3::new(2.15, 3.1, 2.2), Vec3::new(9.85, 8.9, 9.8));
+        let shape = sdf::translate(sdf::cuboid((max - min) * 0.5), (min + max) * 0.5);
+        let mut estimated = Chunk::new(12, 12, 12);
+        estimated.fill(&shape);
+        let mut exact = Chunk::new(12, 12, 12);
+        exact.fill(&shape);
+        exact.fill_normals(&shape);
+
+        let rounded = mesh_standalone(
+            &exact,
+            MeshingAlgorithm::SurfaceNets,
+            NormalMode::AreaWeighted,
+        );
+        for (chunk, tolerance) in [(&exact, 0.25), (&estimated, 0.5)] {
+            let mesh = mesh_standalone(
+                chunk,
+                MeshingAlgorithm::DualContouring,
+                NormalMode::AreaWeighted,
+            );
+            for i in 0..8 {
+                let corner = Vec3::select(BVec3::new(i & 1 != 0, i & 2 != 0, i & 4 != 0), max, min);
+                let nearest = nearest_vertex(&mesh, corner);
+                assert!(
+                    nearest < tolerance,
+                    "corner {corner} is {nearest} from the mesh"
+                );
+                assert!(nearest_vertex(&rounded, corner) > 0.9);
+            }
+        }
+    }
+}
@@ -2,7 +2,7 @@ use derive_more::{Display, From};
 
 pub type Result<T> = core::result::Result<T, MarchingCubesError>;
 
-/// Errors produced during marching cubes mesh generation.
+/// Errors produced during marching cubes mesh generation and asset loading.
 #[derive(Debug, Display, From)]
 #[display("{self:?}")]
 pub enum MarchingCubesError {
@@ -10,6 +10,12 @@ pub enum MarchingCubesError {
     InvalidCorners,
     /// A triangle was added referencing a vertex index that doesn't exist.
     InvalidIndex,
+    /// An asset file couldn't be read.
+    #[cfg(feature = "serialize")]
+    Io(std::io::Error),
+    /// An asset file isn't valid RON for its type.
+    #[cfg(feature = "serialize")]
+    Ron(ron::error::SpannedError),
 }
 
 impl std::error::Error for MarchingCubesError {}
@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+
+use crate::{chunk::Chunk, neighbours::ChunkCoord, types::Value};
+
+/// Fills requested chunks with scalar field values.
+///
+/// Set on [`MarchingCubesConfig::generator`](crate::MarchingCubesConfig::generator) and run
+/// on Bevy's `AsyncComputeTaskPool` for every [`ChunkRequest`], so implementations must be
+/// `Send + Sync`. Any `Fn(IVec3, Vec3, &mut Chunk)` closure is a generator:
+///
+/// ```rust,ignore
+/// let generator = |_coord: IVec3, origin: Vec3, chunk: &mut Chunk| {
+///     chunk.for_each_corner_offset(origin, |x, y, z, value| *value = y - 8.0);
+/// };
+/// app.add_plugins(MarchingCubesPlugin::default().with_generator(generator));
+/// ```
+pub trait ChunkGenerator: Send + Sync + 'static {
+    /// Fills `chunk`, the chunk at `coord` whose minimum corner sits at `origin` in world space.
+    ///
+    /// `chunk` already has the size, scale and threshold of its [`ChunkRequest`].
+    fn generate(&self, coord: IVec3, origin: Vec3, chunk: &mut Chunk);
+}
+
+impl<F> ChunkGenerator for F
+where
+    F: Fn(IVec3, Vec3, &mut Chunk) + Send + Sync + 'static,
+{
+    fn generate(&self, coord: IVec3, origin: Vec3, chunk: &mut Chunk) {
+        self(coord, origin, chunk)
+    }
+}
+
+/// Asks the plugin to generate and mesh a [`Chunk`] on this entity.
+///
+/// Filling the chunk with the configured [`ChunkGenerator`] and meshing it run as a single
+/// task in [`MarchingCubesSet::Spawn`](crate::MarchingCubesSet::Spawn), sharing
+/// [`MarchingCubesConfig::max_tasks_per_frame`](crate::MarchingCubesConfig::max_tasks_per_frame)
+/// with re-meshing. When the task finishes the [`Chunk`] and its
+/// [`GeneratedMesh`](crate::GeneratedMesh) are inserted together and the mesh is uploaded
+/// the same frame:
+///
+/// ```text
+/// ChunkRequest inserted
+///   → ComputeTask spawned           (MarchingCubesSet::Spawn, fill + mesh off-thread)
+///   → Chunk + GeneratedMesh inserted (MarchingCubesSet::Generate)
+///   → Mesh3d inserted               (MarchingCubesSet::Upload)
+///   → ChunkRequest removed
+/// ```
+///
+/// Use [`bundle`](ChunkRequest::bundle) to also place the chunk in the grid:
+///
+/// ```rust,ignore
+/// commands.spawn(ChunkRequest::new(IVec3::new(2, 0, -1), UVec3::splat(32), 1.0).bundle());
+/// ```
+///
+/// Requests wait until a generator is set.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct ChunkRequest {
+    /// Position of the chunk in the chunk grid.
+    pub coord: IVec3,
+    /// Number of voxels along each axis.
+    pub size: UVec3,
+    /// World-space size of each voxel edge.
+    pub scale: Value,
+    /// Iso-surface threshold of the generated chunk.
+    pub threshold: Value,
+}
+
+impl ChunkRequest {
+    /// Creates a request for a chunk of `size` voxels at `coord`, with threshold `0.0`.
+    pub fn new(coord: IVec3, size: UVec3, scale: Value) -> Self {
+        Self {
+            coord,
+            size,
+            scale,
+            threshold: 0.,
+        }
+    }
+
+    /// Sets the iso-surface threshold of the generated chunk.
+    pub fn with_threshold(mut self, threshold: Value) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Returns the world-space position of the chunk's minimum corner, assuming neighbouring
+    /// chunks have the same size and scale.
+    pub fn origin(&self) -> Vec3 {
+        self.coord.as_vec3() * self.size.as_vec3() * self.scale
+    }
+
+    /// Returns the request together with a [`ChunkCoord`] and a [`Transform`] at
+    /// [`origin`](ChunkRequest::origin).
+    pub fn bundle(self) -> impl Bundle {
+        (
+            self,
+            ChunkCoord(self.coord),
+            Transform::from_translation(self.origin()),
+        )
+    }
+
+    /// Creates the empty chunk and fills it with `generator`.
+    pub(crate) fn generate(&self, generator: &dyn ChunkGenerator) -> Chunk {
+        let mut chunk = Chunk::new(
+            self.size.x as usize,
+            self.size.y as usize,
+            self.size.z as usize,
+        )
+        .with_scale(self.scale)
+        .with_threshold(self.threshold);
+        generator.generate(self.coord, self.origin(), &mut chunk);
+        // Generators may write through `values_mut`, which leaves the range unknown.
+        chunk.update_value_range();
+        chunk
+    }
+}
@@ -0,0 +1,288 @@
+use crate::types::Value;
+
+/// A dense 3D grid of per-corner samples stored in one contiguous buffer.
+///
+/// Samples are laid out with X varying fastest, then Y, then Z, so a single X row is a
+/// contiguous slice and the whole grid is one allocation:
+///
+/// ```text
+/// index(x, y, z) = (z * dims_y + y) * dims_x + x
+///
+/// data: [ (0,0,0) (1,0,0) .. (dx-1,0,0) | (0,1,0) .. | .. | (0,0,1) .. ]
+///         \__________ row y=0 _________/  \_ y=1 _/        \_ z=1 _/
+/// ```
+///
+/// `dims` counts corners, not voxels: a [`Chunk`](crate::chunk::Chunk) of `size_x` voxels
+/// has `size_x + 1` corners along X.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct VoxelGrid<T = Value> {
+    dims: [usize; 3],
+    data: Vec<T>,
+}
+
+impl<T: Copy> VoxelGrid<T> {
+    /// Creates a grid of `dims` corners with every sample set to `value`.
+    pub fn new(dims: [usize; 3], value: T) -> Self {
+        Self {
+            dims,
+            data: vec![value; dims[0] * dims[1] * dims[2]],
+        }
+    }
+
+    /// Wraps an existing buffer laid out in `[z][y][x]` order.
+    ///
+    /// # Panics
+    /// Panics if `data.len()` doesn't equal `dims_x * dims_y * dims_z`.
+    pub fn from_vec(dims: [usize; 3], data: Vec<T>) -> Self {
+        assert_eq!(data.len(), dims[0] * dims[1] * dims[2]);
+        Self { dims, data }
+    }
+
+    /// Returns the number of corners along each axis as `[x, y, z]`.
+    #[inline]
+    pub fn dims(&self) -> [usize; 3] {
+        self.dims
+    }
+
+    /// Returns the total number of samples.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns `true` if the grid holds no samples.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the flat buffer index of corner `(x, y, z)`.
+    #[inline]
+    pub fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        debug_assert!(x < self.dims[0] && y < self.dims[1] && z < self.dims[2]);
+        (z * self.dims[1] + y) * self.dims[0] + x
+    }
+
+    /// Returns the sample at corner `(x, y, z)`.
+    #[inline]
+    pub fn get(&self, x: usize, y: usize, z: usize) -> T {
+        self.data[self.index(x, y, z)]
+    }
+
+    /// Returns a mutable reference to the sample at corner `(x, y, z)`.
+    #[inline]
+    pub fn get_mut(&mut self, x: usize, y: usize, z: usize) -> &mut T {
+        let i = self.index(x, y, z);
+        &mut self.data[i]
+    }
+
+    /// Sets the sample at corner `(x, y, z)`.
+    #[inline]
+    pub fn set(&mut self, x: usize, y: usize, z: usize, value: T) {
+        let i = self.index(x, y, z);
+        self.data[i] = value;
+    }
+
+    /// Returns the contiguous row of samples along X at `(y, z)`.
+    #[inline]
+    pub fn row(&self, y: usize, z: usize) -> &[T] {
+        let start = self.index(0, y, z);
+        &self.data[start..start + self.dims[0]]
+    }
+
+    /// Returns the whole buffer in `[z][y][x]` order.
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Returns the whole buffer in `[z][y][x]` order, mutably.
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    /// Copies the slab of samples at `index` along `axis` (`0` = X, `1` = Y, `2` = Z).
+    ///
+    /// The result is a grid with the same dimensions except `dims[axis] == 1`, so it can be
+    /// sampled with the slab coordinate set to `0`.
+    pub fn layer(&self, axis: usize, index: usize) -> Self {
+        let mut dims = self.dims;
+        dims[axis] = 1;
+        let mut data = Vec::with_capacity(dims[0] * dims[1] * dims[2]);
+        for z in 0..dims[2] {
+            for y in 0..dims[1] {
+                for x in 0..dims[0] {
+                    let mut p = [x, y, z];
+                    p[axis] = index;
+                    data.push(self.get(p[0], p[1], p[2]));
+                }
+            }
+        }
+        Self { dims, data }
+    }
+
+    /// Overwrites the slab at `index` along `axis` with `layer`, as returned by
+    /// [`layer`](VoxelGrid::layer).
+    ///
+    /// # Panics
+    /// Panics if `layer` doesn't match this grid's dimensions on the other two axes.
+    pub fn set_layer(&mut self, axis: usize, index: usize, layer: &Self) {
+        let mut expected = self.dims;
+        expected[axis] = 1;
+        assert_eq!(layer.dims, expected, "layer dimensions don't match grid");
+        for z in 0..expected[2] {
+            for y in 0..expected[1] {
+                for x in 0..expected[0] {
+                    let mut p = [x, y, z];
+                    p[axis] = index;
+                    self.set(p[0], p[1], p[2], layer.get(x, y, z));
+                }
+            }
+        }
+    }
+
+    /// Keeps every `step`th sample along each axis, starting from corner `(0, 0, 0)`.
+    ///
+    /// Axes whose corner count is `step * n + 1` shrink to `n + 1` corners, so the first and
+    /// last corners of every axis survive; an axis with a single corner is kept as-is.
+    pub fn downsample(&self, step: usize) -> Self {
+        let dims = self
+            .dims
+            .map(|d| if d == 0 { 0 } else { (d - 1) / step + 1 });
+        let mut data = Vec::with_capacity(dims[0] * dims[1] * dims[2]);
+        for z in 0..dims[2] {
+            for y in 0..dims[1] {
+                for x in 0..dims[0] {
+                    data.push(self.get(x * step, y * step, z * step));
+                }
+            }
+        }
+        Self { dims, data }
+    }
+
+    /// Converts the grid back into nested `[z][y][x]` vectors.
+    pub fn to_nested(&self) -> Vec<Vec<Vec<T>>> {
+        self.data
+            .chunks(self.dims[0] * self.dims[1])
+            .map(|slab| slab.chunks(self.dims[0]).map(<[T]>::to_vec).collect())
+            .collect()
+    }
+}
+
+/// Converts nested `values[z][y][x]` vectors, as used before [`VoxelGrid`] existed.
+///
+/// # Panics
+/// Panics if the nested vectors are ragged.
+impl<T: Copy> From<Vec<Vec<Vec<T>>>> for VoxelGrid<T> {
+    fn from(nested: Vec<Vec<Vec<T>>>) -> Self {
+        let dims_z = nested.len();
+        let dims_y = nested.first().map_or(0, Vec::len);
+        let dims_x = nested.first().and_then(|s| s.first()).map_or(0, Vec::len);
+
+        let mut data = Vec::with_capacity(dims_x * dims_y * dims_z);
+        for slab in nested {
+            assert_eq!(slab.len(), dims_y, "ragged nested grid");
+            for row in slab {
+                assert_eq!(row.len(), dims_x, "ragged nested grid");
+                data.extend(row);
+            }
+        }
+
+        Self::from_vec([dims_x, dims_y, dims_z], data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a grid of `dims` whose samples encode their own coordinates as `zyx`.
+    fn numbered(dims: [usize; 3]) -> VoxelGrid<u32> {
+        let mut grid = VoxelGrid::new(dims, 0);
+        for z in 0..dims[2] {
+            for y in 0..dims[1] {
+                for x in 0..dims[0] {
+                    grid.set(x, y, z, (z * 100 + y * 10 + x) as u32);
+                }
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn samples_are_stored_x_fastest() {
+        let grid = numbered([3, 2, 2]);
+        assert_eq!(
+            grid.as_slice(),
+            &[0, 1, 2, 10, 11, 12, 100, 101, 102, 110, 111, 112]
+        );
+        assert_eq!(grid.index(2, 1, 0), 5);
+        assert_eq!(grid.row(1, 1), &[110, 111, 112]);
+        assert_eq!(
+            VoxelGrid::from_vec([3, 2, 2], grid.as_slice().to_vec()),
+            grid
+        );
+    }
+
+    #[test]
+    fn nested_vectors_round_trip_in_z_y_x_order() {
+        let nested = vec![
+            vec![vec![0, 1, 2], vec![10, 11, 12]],
+            vec![vec![100, 101, 102], vec![110, 111, 112]],
+        ];
+        let grid = VoxelGrid::from(nested.clone());
+        assert_eq!(grid, numbered([3, 2, 2]));
+        assert_eq!(grid.get(2, 0, 1), nested[1][0][2]);
+        assert_eq!(grid.to_nested(), nested);
+    }
+
+    #[test]
+    #[should_panic(expected = "ragged nested grid")]
+    fn ragged_nested_vectors_panic() {
+        let _ = VoxelGrid::from(vec![vec![vec![0, 1], vec![2]]]);
+    }
+
+    #[test]
+    fn layers_copy_slabs_along_each_axis() {
+        let mut grid = numbered([3, 4, 5]);
+        let x = grid.layer(0, 2);
+        assert_eq!(x.dims(), [1, 4, 5]);
+        assert_eq!(x.get(0, 3, 4), 432);
+        let y = grid.layer(1, 3);
+        assert_eq!(y.dims(), [3, 1, 5]);
+        assert_eq!(y.get(1, 0, 4), 431);
+        let z = grid.layer(2, 4);
+        assert_eq!(z.dims(), [3, 4, 1]);
+        assert_eq!(z.row(2, 0), &[420, 421, 422]);
+
+        // Writing a layer back moves it to another slab and leaves the rest alone.
+        grid.set_layer(2, 0, &z);
+        assert_eq!(grid.layer(2, 0), z);
+        assert_eq!(grid.layer(2, 1), numbered([3, 4, 5]).layer(2, 1));
+        grid.set_layer(0, 0, &grid.layer(0, 2));
+        assert_eq!(grid.get(0, 3, 2), 232);
+        assert_eq!(grid.get(1, 3, 2), 231);
+    }
+
+    #[test]
+    #[should_panic(expected = "layer dimensions don't match grid")]
+    fn set_layer_rejects_mismatched_layers() {
+        let mut grid = numbered([3, 4, 5]);
+        let layer = grid.layer(1, 0);
+        grid.set_layer(0, 0, &layer);
+    }
+
+    #[test]
+    fn downsample_keeps_every_nth_corner() {
+        let grid = numbered([5, 3, 1]);
+        let half = grid.downsample(2);
+        assert_eq!(half.dims(), [3, 2, 1]);
+        assert_eq!(half.as_slice(), &[0, 2, 4, 20, 22, 24]);
+        assert_eq!(grid.downsample(1), grid);
+        assert_eq!(
+            numbered([9, 9, 9]).downsample(4).to_nested()[2][1],
+            vec![840, 844, 848]
+        );
+    }
+}
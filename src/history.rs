@@ -0,0 +1,468 @@
+use std::{collections::VecDeque, mem::size_of, sync::Arc};
+
+use bevy::{ecs::query::QueryFilter, prelude::*};
+
+use crate::{
+    chunk::Chunk,
+    grid::VoxelGrid,
+    types::{MaterialId, Value},
+};
+
+/// The grids of a [`Chunk`] before an edit.
+///
+/// Taking a snapshot only clones the [`Arc`]s, so it costs nothing until the chunk is
+/// modified; the first write then copies the grid and the snapshot keeps the original.
+#[derive(Debug, Clone)]
+pub struct ChunkSnapshot {
+    values: Arc<VoxelGrid>,
+    materials: Option<Arc<VoxelGrid<MaterialId>>>,
+    colors: Option<Arc<VoxelGrid<[f32; 4]>>>,
+    normals: Option<Arc<VoxelGrid<[f32; 3]>>>,
+}
+
+impl ChunkSnapshot {
+    /// Captures the current grids of `chunk`.
+    pub fn of(chunk: &Chunk) -> Self {
+        Self {
+            values: Arc::clone(chunk.values()),
+            materials: chunk.materials.clone(),
+            colors: chunk.colors.clone(),
+            normals: chunk.normals.clone(),
+        }
+    }
+}
+
+/// Samples of one grid changed by an edit.
+#[derive(Debug, Clone)]
+struct GridDelta<T> {
+    /// Whether the grid was created by the edit, so undoing it removes the grid.
+    created: bool,
+    /// `(index, before, after)` for every changed sample.
+    changes: Vec<(u32, T, T)>,
+}
+
+impl<T: Copy + PartialEq> GridDelta<T> {
+    /// Compares two versions of a grid, treating a missing `before` grid as filled with
+    /// `default`. Returns `None` if nothing changed.
+    ///
+    /// Grids that still share their [`Arc`] are skipped without comparing samples.
+    fn diff(
+        before: Option<&Arc<VoxelGrid<T>>>,
+        after: Option<&Arc<VoxelGrid<T>>>,
+        default: T,
+    ) -> Option<Self> {
+        let after = after?;
+        let (created, changes) = match before {
+            Some(before) if Arc::ptr_eq(before, after) || before.dims() != after.dims() => {
+                return None;
+            }
+            Some(before) => (false, changed(before.as_slice().iter().copied(), after)),
+            None => (true, changed(std::iter::repeat(default), after)),
+        };
+        (created || !changes.is_empty()).then_some(Self { created, changes })
+    }
+
+    /// Writes the `before` or `after` side of every change into `grid`.
+    fn write(&self, grid: &mut VoxelGrid<T>, redo: bool) {
+        let data = grid.as_mut_slice();
+        for &(index, before, after) in &self.changes {
+            data[index as usize] = if redo { after } else { before };
+        }
+    }
+
+    /// Undoes or redoes this delta on an optional grid.
+    fn apply(
+        &self,
+        grid: &mut Option<Arc<VoxelGrid<T>>>,
+        dims: [usize; 3],
+        default: T,
+        redo: bool,
+    ) {
+        if self.created && !redo {
+            *grid = None;
+            return;
+        }
+        let grid = grid.get_or_insert_with(|| Arc::new(VoxelGrid::new(dims, default)));
+        self.write(Arc::make_mut(grid), redo);
+    }
+
+    fn bytes(&self) -> usize {
+        self.changes.len() * size_of::<(u32, T, T)>()
+    }
+}
+
+/// Returns `(index, before, after)` for every sample of `after` that differs from `before`.
+fn changed<T: Copy + PartialEq>(
+    before: impl Iterator<Item = T>,
+    after: &VoxelGrid<T>,
+) -> Vec<(u32, T, T)> {
+    before
+        .zip(after.as_slice().iter().copied())
+        .enumerate()
+        .filter(|(_, (before, after))| before != after)
+        .map(|(index, (before, after))| (index as u32, before, after))
+        .collect()
+}
+
+/// Sparse changes made to one chunk by an [`Edit`].
+#[derive(Debug, Clone)]
+struct ChunkDelta {
+    entity: Entity,
+    /// Corner counts of the chunk when edited; the delta is skipped if they change.
+    dims: [usize; 3],
+    values: Option<GridDelta<Value>>,
+    materials: Option<GridDelta<MaterialId>>,
+    colors: Option<GridDelta<[f32; 4]>>,
+    normals: Option<GridDelta<[f32; 3]>>,
+}
+
+impl ChunkDelta {
+    fn apply(&self, chunk: &mut Mut<Chunk>, redo: bool) {
+        if chunk.values().dims() != self.dims {
+            return;
+        }
+        if let Some(values) = &self.values {
+            values.write(chunk.values_mut(), redo);
+            chunk.update_value_range();
+        }
+        if let Some(materials) = &self.materials {
+            materials.apply(&mut chunk.materials, self.dims, 0, redo);
+        }
+        if let Some(colors) = &self.colors {
+            colors.apply(&mut chunk.colors, self.dims, [1.0; 4], redo);
+        }
+        if let Some(normals) = &self.normals {
+            normals.apply(&mut chunk.normals, self.dims, [0.0; 3], redo);
+        }
+    }
+
+    fn bytes(&self) -> usize {
+        size_of::<Self>()
+            + self.values.as_ref().map_or(0, GridDelta::bytes)
+            + self.materials.as_ref().map_or(0, GridDelta::bytes)
+            + self.colors.as_ref().map_or(0, GridDelta::bytes)
+            + self.normals.as_ref().map_or(0, GridDelta::bytes)
+    }
+}
+
+/// One undoable operation, covering every chunk it touched.
+///
+/// Snapshot each chunk before changing it, then record it once the change is done:
+///
+/// ```rust,ignore
+/// let mut edit = Edit::default();
+/// for (entity, mut chunk) in &mut chunks {
+///     let before = ChunkSnapshot::of(&chunk);
+///     raise_terrain(&mut chunk);
+///     edit.record(entity, &before, &chunk);
+/// }
+/// history.push(edit);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Edit {
+    chunks: Vec<ChunkDelta>,
+}
+
+impl Edit {
+    /// Records the changes made to the chunk on `entity` since `before` was taken.
+    ///
+    /// Only samples that differ are stored. Chunks whose grids weren't modified, or whose
+    /// values were resized, are not recorded. Record each chunk at most once per edit.
+    pub fn record(&mut self, entity: Entity, before: &ChunkSnapshot, after: &Chunk) {
+        let delta = ChunkDelta {
+            entity,
+            dims: after.values().dims(),
+            values: GridDelta::diff(Some(&before.values), Some(after.values()), 0.0),
+            materials: GridDelta::diff(before.materials.as_ref(), after.materials.as_ref(), 0),
+            colors: GridDelta::diff(before.colors.as_ref(), after.colors.as_ref(), [1.0; 4]),
+            normals: GridDelta::diff(before.normals.as_ref(), after.normals.as_ref(), [0.0; 3]),
+        };
+        if delta.values.is_some()
+            || delta.materials.is_some()
+            || delta.colors.is_some()
+            || delta.normals.is_some()
+        {
+            self.chunks.push(delta);
+        }
+    }
+
+    /// Returns `true` if no chunk was changed by this edit.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Returns the approximate memory used by this edit, in bytes.
+    pub fn bytes(&self) -> usize {
+        self.chunks.iter().map(ChunkDelta::bytes).sum()
+    }
+
+    /// Undoes or redoes the edit on every chunk that still exists.
+    fn apply<F: QueryFilter>(&self, chunks: &mut Query<&mut Chunk, F>, redo: bool) {
+        for delta in &self.chunks {
+            if let Ok(mut chunk) = chunks.get_mut(delta.entity) {
+                delta.apply(&mut chunk, redo);
+            }
+        }
+    }
+}
+
+/// Undo and redo stacks of [`Edit`]s.
+///
+/// Not inserted by default; add it to record every [`Brush`](crate::Brush) automatically:
+///
+/// ```rust,ignore
+/// app.insert_resource(EditHistory::new(32 * 1024 * 1024));
+///
+/// fn undo(keys: Res<ButtonInput<KeyCode>>, mut history: ResMut<EditHistory>, mut chunks: Query<&mut Chunk>) {
+///     if keys.just_pressed(KeyCode::KeyZ) {
+///         history.undo(&mut chunks);
+///     }
+/// }
+/// ```
+///
+/// Undoing and redoing write straight into the affected chunks, which marks only those as
+/// changed so they are re-meshed. Edits refer to chunk entities, so they do nothing for
+/// chunks that have since been despawned, for example by
+/// [`ChunkStreamingPlugin`](crate::ChunkStreamingPlugin).
+#[derive(Resource, Debug, Clone)]
+pub struct EditHistory {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+    /// Memory budget shared by both stacks, in bytes.
+    max_bytes: usize,
+    bytes: usize,
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self::new(64 * 1024 * 1024)
+    }
+}
+
+impl EditHistory {
+    /// Creates an empty history that keeps at most `max_bytes` of edits. Default: 64 MiB.
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            max_bytes,
+            bytes: 0,
+        }
+    }
+
+    /// Adds `edit` to the undo stack and clears the redo stack.
+    ///
+    /// The oldest edits are dropped until the history fits in its memory budget, including
+    /// `edit` itself if it is larger than the whole budget. Empty edits are ignored.
+    pub fn push(&mut self, edit: Edit) {
+        if edit.is_empty() {
+            return;
+        }
+        self.bytes -= self.redo.drain(..).map(|edit| edit.bytes()).sum::<usize>();
+        self.bytes += edit.bytes();
+        self.undo.push_back(edit);
+        while self.bytes > self.max_bytes {
+            let Some(oldest) = self.undo.pop_front() else {
+                break;
+            };
+            self.bytes -= oldest.bytes();
+        }
+    }
+
+    /// Reverts the most recent edit. Returns `false` if there was nothing to undo.
+    pub fn undo<F: QueryFilter>(&mut self, chunks: &mut Query<&mut Chunk, F>) -> bool {
+        let Some(edit) = self.undo.pop_back() else {
+            return false;
+        };
+        edit.apply(chunks, false);
+        self.redo.push(edit);
+        true
+    }
+
+    /// Re-applies the most recently undone edit. Returns `false` if there was nothing to
+    /// redo.
+    pub fn redo<F: QueryFilter>(&mut self, chunks: &mut Query<&mut Chunk, F>) -> bool {
+        let Some(edit) = self.redo.pop() else {
+            return false;
+        };
+        edit.apply(chunks, true);
+        self.undo.push_back(edit);
+        true
+    }
+
+    /// Returns the number of edits that can be undone.
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Returns the number of edits that can be redone.
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    /// Returns the approximate memory used by both stacks, in bytes.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Drops every recorded edit.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.bytes = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+
+    use super::*;
+
+    fn sphere() -> Chunk {
+        let mut chunk = Chunk::new(4, 4, 4);
+        chunk.fill(&|x, y, z| Vec3::new(x, y, z).distance(Vec3::splat(2.0)) - 1.5);
+        chunk
+    }
+
+    /// Records the change `edit` makes to the chunk on `entity` as one [`Edit`].
+    fn record(world: &mut World, entity: Entity, edit: impl FnOnce(&mut Chunk)) -> Edit {
+        let mut chunk = world.get_mut::<Chunk>(entity).unwrap();
+        let before = ChunkSnapshot::of(&chunk);
+        edit(&mut chunk);
+        let mut recorded = Edit::default();
+        recorded.record(entity, &before, &chunk);
+        recorded
+    }
+
+    fn undo(world: &mut World, history: &mut EditHistory) -> bool {
+        let mut state = SystemState::<Query<&mut Chunk>>::new(world);
+        history.undo(&mut state.get_mut(world))
+    }
+
+    fn redo(world: &mut World, history: &mut EditHistory) -> bool {
+        let mut state = SystemState::<Query<&mut Chunk>>::new(world);
+        history.redo(&mut state.get_mut(world))
+    }
+
+    #[test]
+    fn undo_and_redo_restore_values_and_range() {
+        let mut world = World::new();
+        let original = sphere();
+        let (values, range) = (Arc::clone(original.values()), original.value_range());
+        let entity = world.spawn(original).id();
+        let mut history = EditHistory::default();
+        history.push(record(&mut world, entity, |chunk| {
+            chunk.set(2, 2, 2, -5.0);
+            chunk.set(0, 0, 0, 9.0);
+        }));
+        let edited = Arc::clone(world.get::<Chunk>(entity).unwrap().values());
+
+        assert!(undo(&mut world, &mut history));
+        let chunk = world.get::<Chunk>(entity).unwrap();
+        assert_eq!(**chunk.values(), *values);
+        assert_eq!(chunk.value_range(), range);
+
+        assert!(redo(&mut world, &mut history));
+        let chunk = world.get::<Chunk>(entity).unwrap();
+        assert_eq!(**chunk.values(), *edited);
+        assert_eq!(chunk.value_range(), Some((-5.0, 9.0)));
+        assert!(!redo(&mut world, &mut history));
+    }
+
+    #[test]
+    fn undo_removes_created_material_grid() {
+        let mut world = World::new();
+        let entity = world.spawn(sphere()).id();
+        let mut history = EditHistory::default();
+        history.push(record(&mut world, entity, |chunk| {
+            chunk.set_material(1, 1, 1, 3);
+        }));
+
+        undo(&mut world, &mut history);
+        assert!(world.get::<Chunk>(entity).unwrap().materials.is_none());
+        redo(&mut world, &mut history);
+        assert_eq!(world.get::<Chunk>(entity).unwrap().get_material(1, 1, 1), 3);
+    }
+
+    #[test]
+    fn undo_restores_normals() {
+        let mut world = World::new();
+        let mut original = sphere();
+        original.fill_normals(&|x, y, z| x + 2.0 * y + 3.0 * z);
+        let normals = Arc::clone(original.normals.as_ref().unwrap());
+        let entity = world.spawn(original).id();
+        let mut history = EditHistory::default();
+        history.push(record(&mut world, entity, |chunk| {
+            chunk.set(2, 2, 2, -5.0);
+            chunk.fill_normals(&|x, y, z| 3.0 * x - y + z);
+        }));
+        let edited = Arc::clone(
+            world
+                .get::<Chunk>(entity)
+                .unwrap()
+                .normals
+                .as_ref()
+                .unwrap(),
+        );
+        assert_ne!(*edited, *normals);
+
+        undo(&mut world, &mut history);
+        let chunk = world.get::<Chunk>(entity).unwrap();
+        assert_eq!(**chunk.normals.as_ref().unwrap(), *normals);
+        redo(&mut world, &mut history);
+        let chunk = world.get::<Chunk>(entity).unwrap();
+        assert_eq!(**chunk.normals.as_ref().unwrap(), *edited);
+    }
+
+    #[test]
+    fn push_clears_redo() {
+        let mut world = World::new();
+        let entity = world.spawn(sphere()).id();
+        let mut history = EditHistory::default();
+        history.push(record(&mut world, entity, |chunk| chunk.set(1, 1, 1, 2.0)));
+        undo(&mut world, &mut history);
+        assert_eq!(history.redo_len(), 1);
+
+        let edit = record(&mut world, entity, |chunk| chunk.set(3, 3, 3, 2.0));
+        let bytes = edit.bytes();
+        history.push(edit);
+        assert_eq!(history.redo_len(), 0);
+        assert_eq!(history.undo_len(), 1);
+        assert_eq!(history.bytes(), bytes);
+    }
+
+    #[test]
+    fn push_evicts_edits_beyond_budget() {
+        let mut world = World::new();
+        let entity = world.spawn(sphere()).id();
+        let first = record(&mut world, entity, |chunk| chunk.set(1, 1, 1, 2.0));
+        let second = record(&mut world, entity, |chunk| chunk.set(3, 3, 3, 2.0));
+        let mut history = EditHistory::new(first.bytes() + second.bytes() - 1);
+        history.push(first);
+        history.push(second.clone());
+        assert_eq!(history.undo_len(), 1);
+        assert_eq!(history.bytes(), second.bytes());
+
+        // An edit larger than the whole budget isn't kept either.
+        let mut history = EditHistory::new(second.bytes() - 1);
+        history.push(second);
+        assert_eq!(history.undo_len(), 0);
+        assert_eq!(history.bytes(), 0);
+    }
+
+    #[test]
+    fn undo_skips_resized_chunks() {
+        let mut world = World::new();
+        let entity = world.spawn(sphere()).id();
+        let mut history = EditHistory::default();
+        history.push(record(&mut world, entity, |chunk| chunk.set(1, 1, 1, 2.0)));
+
+        let mut resized = Chunk::new(2, 2, 2);
+        resized.fill(&|_, _, _| 1.0);
+        let values = Arc::clone(resized.values());
+        world.entity_mut(entity).insert(resized);
+        assert!(undo(&mut world, &mut history));
+        assert_eq!(**world.get::<Chunk>(entity).unwrap().values(), *values);
+    }
+}
@@ -29,3 +29,13 @@ pub fn interpolate_points(p0: [f32; 3], p1: [f32; 3], t: Value) -> [f32; 3] {
         lerp(p0[2], p1[2], t),
     ]
 }
+
+/// Interpolates component-wise between two RGBA colours by factor `t`.
+pub fn interpolate_colors(c0: [f32; 4], c1: [f32; 4], t: Value) -> [f32; 4] {
+    [
+        lerp(c0[0], c1[0], t),
+        lerp(c0[1], c1[1], t),
+        lerp(c0[2], c1[2], t),
+        lerp(c0[3], c1[3], t),
+    ]
+}
@@ -1,11 +1,41 @@
+pub mod ambiguity;
+pub mod brush;
 pub mod chunk;
+mod dual_contouring;
 pub mod error;
+pub mod generator;
+pub mod grid;
+pub mod history;
 pub mod interp;
+pub mod lod;
+mod marching_tetrahedra;
 pub mod mesh;
+pub mod neighbours;
+pub mod octree;
 pub mod plugin;
+pub mod sdf;
+pub mod sdf_node;
+pub mod simplify;
+pub mod streaming;
+mod surface_nets;
 pub mod tables;
+#[cfg(test)]
+mod test_support;
+pub mod transition_tables;
+pub mod transvoxel;
 pub mod types;
 pub mod utils;
 
-pub use mesh::GeneratedMesh;
-pub use plugin::{MarchingCubesConfig, MarchingCubesPlugin, MarchingCubesSet, QueuedChunk};
+pub use brush::{Brush, BrushOp, BrushShape};
+pub use generator::{ChunkGenerator, ChunkRequest};
+pub use history::{ChunkSnapshot, Edit, EditHistory};
+pub use lod::ChunkLods;
+pub use mesh::{GeneratedMesh, MeshingAlgorithm, NormalMode, RetainedMesh};
+pub use octree::VoxelOctree;
+pub use plugin::{
+    EmptyChunk, MarchingCubesConfig, MarchingCubesPlugin, MarchingCubesSet, QueuedChunk,
+};
+pub use sdf::Sdf;
+pub use sdf_node::{PrunedSdfFill, SdfFill, SdfNode};
+pub use simplify::Simplification;
+pub use streaming::{ChunkStreamer, ChunkStreamingConfig, ChunkStreamingPlugin};
@@ -0,0 +1,189 @@
+use bevy::prelude::*;
+
+use crate::{chunk::Chunk, plugin::MarchingCubesConfig};
+
+/// Meshes of a [`Chunk`] at decreasing resolution, swapped into its [`Mesh3d`] by distance.
+///
+/// Inserted by [`MarchingCubesPlugin`](crate::MarchingCubesPlugin) when
+/// [`MarchingCubesConfig::lod_distances`] is set. Level `i` samples every `2^i`th corner
+/// of the chunk's values, so the grid stays the single source of truth while far chunks
+/// render cheaper geometry. All levels are meshed by the same task and replaced together
+/// whenever the chunk is re-meshed.
+///
+/// Levels whose step doesn't divide the chunk's size along every axis are skipped, as are
+/// levels beyond the number of distances.
+///
+/// ```rust,ignore
+/// app.add_plugins(MarchingCubesPlugin::default().with_lods(vec![64.0, 128.0, 256.0]));
+///
+/// fn report(chunks: Query<&ChunkLods, Changed<ChunkLods>>) {
+///     for lods in &chunks {
+///         info!("showing level {} of {}", lods.current, lods.meshes.len());
+///     }
+/// }
+/// ```
+#[derive(Component, Debug, Clone)]
+pub struct ChunkLods {
+    /// Mesh of each level, finest first.
+    pub meshes: Vec<Handle<Mesh>>,
+    /// Level currently in the chunk's [`Mesh3d`].
+    pub current: usize,
+}
+
+impl ChunkLods {
+    /// Returns the level to show at `distance` world units from the nearest camera.
+    ///
+    /// Level `i + 1` is used from `distances[i]` onwards, clamped to the levels available.
+    pub fn level_at(&self, distances: &[f32], distance: f32) -> usize {
+        let level = distances.iter().take_while(|&&d| distance >= d).count();
+        level.min(self.meshes.len().saturating_sub(1))
+    }
+}
+
+/// Returns how many levels coarser than the chunk itself can be meshed from a chunk of
+/// `size` voxels, at most `max`.
+pub(crate) fn coarse_levels(size: [usize; 3], max: usize) -> usize {
+    (1..=max)
+        .take_while(|&level| size.iter().all(|&s| s % (1 << level) == 0))
+        .count()
+}
+
+/// Points each chunk's [`Mesh3d`] at the [`ChunkLods`] level for its distance from the
+/// nearest camera, measured to the chunk's centre.
+///
+/// Only writes [`Mesh3d`] when the level changes. Chunks whose [`ChunkLods`] has no meshes
+/// are left alone.
+pub(crate) fn select_chunk_lods(
+    config: Res<MarchingCubesConfig>,
+    cameras: Query<&GlobalTransform, With<Camera>>,
+    mut chunks: Query<(&Chunk, &GlobalTransform, &mut ChunkLods, &mut Mesh3d)>,
+) {
+    let cameras: Vec<Vec3> = cameras.iter().map(GlobalTransform::translation).collect();
+    if cameras.is_empty() {
+        return;
+    }
+
+    for (chunk, transform, mut lods, mut mesh) in chunks.iter_mut() {
+        if lods.meshes.is_empty() {
+            continue;
+        }
+        let extent = Vec3::new(
+            chunk.size_x as f32,
+            chunk.size_y as f32,
+            chunk.size_z as f32,
+        ) * chunk.scale;
+        let centre = transform.transform_point(extent * 0.5);
+        let distance = cameras
+            .iter()
+            .map(|camera| camera.distance(centre))
+            .fold(f32::INFINITY, f32::min);
+        let level = lods.level_at(&config.lod_distances, distance);
+        if level != lods.current || mesh.0 != lods.meshes[level] {
+            lods.current = level;
+            mesh.0 = lods.meshes[level].clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn lods(levels: usize) -> ChunkLods {
+        let meshes = Assets::<Mesh>::default();
+        ChunkLods {
+            meshes: (0..levels).map(|_| meshes.reserve_handle()).collect(),
+            current: 0,
+        }
+    }
+
+    #[test]
+    fn coarse_levels_need_every_axis_to_divide() {
+        assert_eq!(coarse_levels([16, 16, 16], 3), 3);
+        assert_eq!(coarse_levels([16, 16, 16], 2), 2);
+        assert_eq!(coarse_levels([12, 8, 8], 3), 2);
+        assert_eq!(coarse_levels([8, 8, 6], 3), 1);
+        assert_eq!(coarse_levels([8, 9, 8], 3), 0);
+        assert_eq!(coarse_levels([8, 8, 8], 0), 0);
+    }
+
+    #[test]
+    fn level_at_switches_at_each_distance() {
+        let distances = [10.0, 20.0, 30.0];
+        let four = lods(4);
+        for (distance, level) in [
+            (0.0, 0),
+            (9.9, 0),
+            (10.0, 1),
+            (19.9, 1),
+            (20.0, 2),
+            (30.0, 3),
+            (1e6, 3),
+        ] {
+            assert_eq!(four.level_at(&distances, distance), level, "at {distance}");
+        }
+
+        // Clamped to the levels the chunk's size allowed.
+        assert_eq!(lods(2).level_at(&distances, 25.0), 1);
+        assert_eq!(lods(0).level_at(&distances, 25.0), 0);
+    }
+
+    #[test]
+    fn select_chunk_lods_uses_the_nearest_camera() {
+        let mut world = World::new();
+        world.insert_resource(MarchingCubesConfig {
+            lod_distances: vec![10.0, 20.0],
+            ..default()
+        });
+        let lods = lods(3);
+        let meshes = lods.meshes.clone();
+        // The chunk's centre is at (12, 4, 4).
+        let chunk = world
+            .spawn((
+                Chunk::new(8, 8, 8),
+                GlobalTransform::from_xyz(8.0, 0.0, 0.0),
+                lods,
+                Mesh3d(meshes[0].clone()),
+            ))
+            .id();
+        let far = world
+            .spawn((
+                Camera::default(),
+                GlobalTransform::from_xyz(12.0, 4.0, 29.0),
+            ))
+            .id();
+        let select = |world: &mut World| {
+            world.run_system_once(select_chunk_lods).unwrap();
+            let lods = world.get::<ChunkLods>(chunk).unwrap();
+            assert_eq!(world.get::<Mesh3d>(chunk).unwrap().0, meshes[lods.current]);
+            lods.current
+        };
+        assert_eq!(select(&mut world), 2);
+
+        let near = world
+            .spawn((Camera::default(), GlobalTransform::from_xyz(27.0, 4.0, 4.0)))
+            .id();
+        assert_eq!(select(&mut world), 1);
+
+        // Mesh3d is only written when the level changes.
+        let changed = world
+            .entity(chunk)
+            .get_ref::<Mesh3d>()
+            .unwrap()
+            .last_changed();
+        world.entity_mut(far).despawn();
+        assert_eq!(select(&mut world), 1);
+        let unchanged = world
+            .entity(chunk)
+            .get_ref::<Mesh3d>()
+            .unwrap()
+            .last_changed();
+        assert_eq!(changed, unchanged);
+
+        // Without cameras, chunks keep their level.
+        world.entity_mut(near).despawn();
+        assert_eq!(select(&mut world), 1);
+    }
+}
@@ -0,0 +1,261 @@
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{
+    grid::VoxelGrid,
+    interp::{interpolate_colors, interpolate_points},
+    mesh::{GeneratedMesh, NormalMode},
+    neighbours::ChunkHalo,
+    surface_nets::split_quad,
+    types::{MaterialId, Value},
+    utils::{corner_gradient, edge_t, normalize_or_zero},
+};
+
+/// Marks lattice edges without a vertex in the edge → vertex lookup.
+const NO_VERTEX: u32 = u32::MAX;
+
+/// The six tetrahedra each voxel is split into, as corners numbered `x | y << 1 | z << 2`.
+///
+/// Every tetrahedron runs from corner `0` to corner `7` along the voxel's edges, one per
+/// order of the axes, so they all share the body diagonal. Each voxel face is split along
+/// the diagonal from its lowest corner, the same one in both voxels sharing it. Corners are
+/// listed so that `(v1 - v0) × (v2 - v0) · (v3 - v0) > 0`.
+const TETRAHEDRA: [[usize; 4]; 6] = [
+    [0, 1, 3, 7],
+    [0, 2, 6, 7],
+    [0, 4, 5, 7],
+    [0, 1, 7, 5],
+    [0, 2, 7, 3],
+    [0, 4, 7, 6],
+];
+
+/// A lattice edge's vertex, before vertices are numbered.
+struct EdgeVertex {
+    position: [f32; 3],
+    /// Gradient normal; only set for [`NormalMode::Gradient`].
+    normal: Option<[f32; 3]>,
+    /// Interpolated colour; only set if the chunk has colours.
+    color: Option<[f32; 4]>,
+    /// Material; only set if the chunk has materials.
+    material: Option<MaterialId>,
+}
+
+/// Runs marching tetrahedra over the given voxel grid.
+///
+/// ```text
+/// 1. Every voxel is split into six tetrahedra around its body diagonal; see TETRAHEDRA.
+/// 2. Every lattice edge of a tetrahedron crossed by the surface gets one vertex: the
+///    voxel edges, the face diagonals and the body diagonal.
+/// 3. Each tetrahedron with one corner on its own side of the threshold gets a triangle
+///    cutting that corner off, and one with two corners on each side gets a quad.
+/// ```
+///
+/// A tetrahedron's sixteen cases have no ambiguity, so the mesh is watertight and follows
+/// the piecewise-linear interpolation of the values exactly, at the cost of more triangles
+/// than marching cubes. Vertices are shared by every triangle touching their edge.
+///
+/// Vertices take the same colours, materials and gradient normals as in marching cubes,
+/// interpolated along their edge; gradient normals sample `halo` past the chunk boundary.
+pub(crate) fn run_marching_tetrahedra(
+    size_x: usize,
+    size_y: usize,
+    size_z: usize,
+    scale: Value,
+    threshold: Value,
+    normal_mode: NormalMode,
+    values: &VoxelGrid,
+    materials: Option<&VoxelGrid<MaterialId>>,
+    colors: Option<&VoxelGrid<[f32; 4]>>,
+    halo: &ChunkHalo,
+) -> GeneratedMesh {
+    let size = [size_x, size_y, size_z];
+    let corners = size.map(|s| s + 1);
+    // Edges are indexed by their lower end and their direction, `1..=7` as a corner number.
+    let edge_index = |[x, y, z]: [usize; 3], direction: usize| {
+        ((z * corners[1] + y) * corners[0] + x) * 7 + direction - 1
+    };
+    let inside = |[x, y, z]: [usize; 3]| values.get(x, y, z) <= threshold;
+    let sample = |x: isize, y: isize, z: isize| halo.sample(values, x, y, z);
+
+    let per_z: Vec<Vec<(usize, EdgeVertex)>> = (0..corners[2])
+        .into_par_iter()
+        .map(|z| {
+            let mut slab = Vec::new();
+            for y in 0..corners[1] {
+                for x in 0..corners[0] {
+                    let a = [x, y, z];
+                    for direction in 1..8 {
+                        let b = offset(a, direction);
+                        if (0..3).any(|axis| b[axis] == corners[axis]) || inside(a) == inside(b) {
+                            continue;
+                        }
+                        let [va, vb] = [a, b].map(|[x, y, z]| values.get(x, y, z));
+                        let t = edge_t(va, vb, threshold);
+                        let position = interpolate_points(
+                            a.map(|c| c as f32 * scale),
+                            b.map(|c| c as f32 * scale),
+                            t,
+                        );
+                        let normal = (normal_mode == NormalMode::Gradient).then(|| {
+                            let [ga, gb] =
+                                [a, b].map(|[x, y, z]| corner_gradient(x, y, z, scale, sample));
+                            normalize_or_zero(interpolate_points(ga, gb, t))
+                        });
+                        let color = colors.map(|c| {
+                            let [ca, cb] = [a, b].map(|[x, y, z]| c.get(x, y, z));
+                            interpolate_colors(ca, cb, t)
+                        });
+                        // Vertices take the material of the solid side of their edge.
+                        let material = materials.map(|m| {
+                            let [x, y, z] = if inside(a) { a } else { b };
+                            m.get(x, y, z)
+                        });
+                        let vertex = EdgeVertex {
+                            position,
+                            normal,
+                            color,
+                            material,
+                        };
+                        slab.push((edge_index(a, direction), vertex));
+                    }
+                }
+            }
+            slab
+        })
+        .collect();
+
+    let mut edge_vertices = vec![NO_VERTEX; corners.iter().product::<usize>() * 7];
+    let mut vertices: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut vertex_colors: Vec<[f32; 4]> = Vec::new();
+    let mut vertex_materials: Vec<MaterialId> = Vec::new();
+    for (edge, vertex) in per_z.into_iter().flatten() {
+        edge_vertices[edge] = vertices.len() as u32;
+        vertices.push(vertex.position);
+        normals.extend(vertex.normal);
+        vertex_colors.extend(vertex.color);
+        vertex_materials.extend(vertex.material);
+    }
+
+    let per_z: Vec<Vec<u32>> = (0..size_z)
+        .into_par_iter()
+        .map(|z| {
+            let mut indices = Vec::new();
+            for y in 0..size_y {
+                for x in 0..size_x {
+                    let voxel = [x, y, z];
+                    let corner_inside = [0, 1, 2, 3, 4, 5, 6, 7].map(|i| inside(offset(voxel, i)));
+                    if corner_inside.iter().all(|&i| i == corner_inside[0]) {
+                        continue;
+                    }
+                    // Corners of a tetrahedron are nested: each edge runs up from `a & b`.
+                    let vertex = |a: usize, b: usize| {
+                        let index = edge_vertices[edge_index(offset(voxel, a & b), a ^ b)];
+                        debug_assert_ne!(index, NO_VERTEX, "crossed edge has no vertex");
+                        index
+                    };
+                    for tetrahedron in TETRAHEDRA {
+                        let states = tetrahedron.map(|i| corner_inside[i]);
+                        let inside_corners: Vec<usize> = (0..4).filter(|&i| states[i]).collect();
+                        match inside_corners[..] {
+                            [lone] => {
+                                let [i, j, k, l] = even_order(&[lone]).map(|n| tetrahedron[n]);
+                                indices.extend([vertex(i, j), vertex(i, k), vertex(i, l)]);
+                            }
+                            [a, b] => {
+                                let [i, j, k, l] = even_order(&[a, b]).map(|n| tetrahedron[n]);
+                                let quad = [vertex(i, k), vertex(i, l), vertex(j, l), vertex(j, k)];
+                                indices.extend(split_quad(quad, &vertices));
+                            }
+                            [_, _, _] => {
+                                let lone =
+                                    (0..4).find(|&i| !states[i]).expect("one outside corner");
+                                let [i, j, k, l] = even_order(&[lone]).map(|n| tetrahedron[n]);
+                                // Faces towards the lone corner, which is outside.
+                                indices.extend([vertex(i, j), vertex(i, l), vertex(i, k)]);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            indices
+        })
+        .collect();
+
+    GeneratedMesh::build(
+        vertices,
+        per_z.concat(),
+        normals,
+        vertex_colors,
+        vertex_materials,
+        normal_mode,
+    )
+}
+
+/// Returns the corners `0..4` of a tetrahedron starting with `first`, followed by the rest
+/// in an order that keeps the permutation even.
+///
+/// With corners listed as in [`TETRAHEDRA`], a triangle across the edges from a lone corner
+/// `i` to `j`, `k` and `l` in this order faces away from `i`, and with two corners `i` and
+/// `j` first, the quad across the edges `ik`, `il`, `jl`, `jk` faces away from them.
+fn even_order(first: &[usize]) -> [usize; 4] {
+    let mut order = [0; 4];
+    order[..first.len()].copy_from_slice(first);
+    let rest = (0..4).filter(|i| !first.contains(i));
+    for (slot, i) in order[first.len()..].iter_mut().zip(rest) {
+        *slot = i;
+    }
+    let inversions = (0..4)
+        .flat_map(|a| (a + 1..4).map(move |b| (a, b)))
+        .filter(|&(a, b)| order[a] > order[b])
+        .count();
+    if inversions % 2 == 1 {
+        order.swap(2, 3);
+    }
+    order
+}
+
+/// Returns the lattice point `corner` steps from `p`, with corners numbered
+/// `x | y << 1 | z << 2`.
+#[inline]
+fn offset(p: [usize; 3], corner: usize) -> [usize; 3] {
+    [0, 1, 2].map(|axis| p[axis] + (corner >> axis & 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::Vec3;
+
+    use super::*;
+    use crate::{
+        chunk::Chunk,
+        mesh::MeshingAlgorithm,
+        sdf,
+        test_support::{count_non_manifold, is_closed, mesh_standalone},
+    };
+
+    #[test]
+    fn marching_tetrahedra_closes_a_torus() {
+        let mut chunk = Chunk::new(16, 16, 16);
+        chunk.fill(&sdf::translate(
+            sdf::torus(4.5, 1.7),
+            Vec3::new(8.1, 7.9, 8.2),
+        ));
+        let mesh = mesh_standalone(
+            &chunk,
+            MeshingAlgorithm::MarchingTetrahedra,
+            NormalMode::AreaWeighted,
+        );
+        assert!(mesh.tri_count() > 0);
+        assert!(is_closed(&[(&mesh, Vec3::ZERO)], false));
+    }
+
+    #[test]
+    fn marching_tetrahedra_is_manifold_on_closed_fields() {
+        // Random fields are full of ambiguous faces and interiors.
+        assert_eq!(
+            count_non_manifold(MeshingAlgorithm::MarchingTetrahedra, 6, 300),
+            0
+        );
+    }
+}
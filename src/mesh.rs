@@ -1,4 +1,122 @@
-use bevy::prelude::*;
+use std::collections::HashMap;
+
+use bevy::{
+    mesh::{MeshVertexAttribute, VertexFormat},
+    prelude::*,
+};
+
+use crate::{types::MaterialId, utils::normalize_or_zero};
+
+/// Vertex attribute holding up to four [`MaterialId`]s, one per byte from the lowest.
+///
+/// Every vertex of a triangle carries the same IDs: the distinct materials of the triangle's
+/// three vertices in ascending order, with unused slots set to `0`.
+pub const ATTRIBUTE_MATERIAL_IDS: MeshVertexAttribute =
+    MeshVertexAttribute::new("Vertex_MaterialIds", 0x6d63_6d61_7400, VertexFormat::Uint32);
+
+/// Vertex attribute holding one blend weight per slot of [`ATTRIBUTE_MATERIAL_IDS`].
+///
+/// Each vertex has weight `1.0` in the slot of its own material, so the interpolated weights
+/// blend smoothly between materials across a triangle.
+pub const ATTRIBUTE_MATERIAL_WEIGHTS: MeshVertexAttribute = MeshVertexAttribute::new(
+    "Vertex_MaterialWeights",
+    0x6d63_6d61_7401,
+    VertexFormat::Float32x4,
+);
+
+/// How per-vertex normals are computed for a [`GeneratedMesh`].
+///
+/// Set the default for every chunk with [`MarchingCubesConfig::normal_mode`](crate::MarchingCubesConfig::normal_mode),
+/// or override it per chunk with [`Chunk::with_normal_mode`](crate::chunk::Chunk::with_normal_mode).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NormalMode {
+    /// Faceted shading: the mesh is unwelded so every triangle owns its three vertices,
+    /// each carrying the triangle's face normal.
+    Flat,
+    /// Each shared vertex gets the average of its adjacent face normals, weighted by
+    /// triangle area.
+    #[default]
+    AreaWeighted,
+    /// Normals come from the central-difference gradient of the scalar field at the two
+    /// corners of each vertex's edge, interpolated along the edge. Smoothest result on
+    /// organic shapes since it doesn't depend on triangle shape at all.
+    Gradient,
+}
+
+/// Which algorithm turns a [`Chunk`](crate::chunk::Chunk)'s values into a [`GeneratedMesh`].
+///
+/// Set the default for every chunk with [`MarchingCubesConfig::meshing_algorithm`](crate::MarchingCubesConfig::meshing_algorithm),
+/// or override it per chunk with [`Chunk::with_meshing_algorithm`](crate::chunk::Chunk::with_meshing_algorithm).
+/// Every algorithm produces the same attributes and honours the chunk's [`NormalMode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MeshingAlgorithm {
+    /// Classic marching cubes: one vertex on every grid edge the surface crosses. Supports
+    /// [transition faces](crate::chunk::Chunk::transition_faces).
+    #[default]
+    MarchingCubes,
+    /// Marching cubes with its ambiguous cases resolved the way Marching Cubes 33 does, so
+    /// closed fields give a closed 2-manifold mesh, e.g. for 3D printing.
+    ///
+    /// The classic table splits faces with inside corners on one diagonal by a fixed rule and
+    /// caps every loop separately, so its surface can disagree with the field's topology.
+    /// Here those faces are split by the asymptotic decider, which both voxels sharing the
+    /// face evaluate identically, and ambiguous voxels are meshed by tracing the surface
+    /// around their faces. Two loops whose parts of one side are joined through the voxel's
+    /// trilinear interpolant are connected by a tube rather than capped separately.
+    /// Unambiguous voxels use the classic table, so meshes are otherwise identical. Supports
+    /// transition faces.
+    MarchingCubes33,
+    /// Surface nets: one vertex per cell the surface passes through, at the average of the
+    /// cell's edge crossings, joined into a quad around every crossed edge.
+    ///
+    /// Gives smoother, more even triangles than marching cubes, especially at low
+    /// resolution, but rounds off sharp features. Chunks with a
+    /// [`ChunkCoord`](crate::neighbours::ChunkCoord) read the first layer of their
+    /// neighbours on the positive side to close seams; see
+    /// [`ChunkPadding`](crate::neighbours::ChunkPadding). Transition faces are ignored.
+    SurfaceNets,
+    /// Dual contouring: like surface nets, but each cell's vertex is placed where the
+    /// tangent planes at its edge crossings meet, keeping sharp edges and corners.
+    ///
+    /// Best with exact gradients in [`Chunk::normals`](crate::chunk::Chunk::normals), e.g.
+    /// from [`Chunk::fill_normals`](crate::chunk::Chunk::fill_normals); without them the
+    /// gradient is estimated from the values and features come out softer. Pair with
+    /// [`NormalMode::Flat`] for crisp shading. Reads neighbours and ignores transition faces
+    /// like surface nets.
+    DualContouring,
+    /// Marching tetrahedra: every voxel is split into six tetrahedra, each meshed on its
+    /// own with one vertex on every lattice edge the surface crosses, including the face and
+    /// body diagonals.
+    ///
+    /// A tetrahedron's cases have no ambiguity, so meshes are watertight and follow the
+    /// piecewise-linear interpolation of the values exactly, for around three times as many
+    /// triangles as marching cubes. Useful for scientific visualisation where topology
+    /// matters more than triangle count. Transition faces are ignored.
+    MarchingTetrahedra,
+}
+
+impl MeshingAlgorithm {
+    /// Returns `true` for algorithms that place vertices inside cells rather than on edges.
+    ///
+    /// Their meshes depend on the neighbours past the chunk's positive faces.
+    pub fn is_dual(self) -> bool {
+        match self {
+            MeshingAlgorithm::MarchingCubes
+            | MeshingAlgorithm::MarchingCubes33
+            | MeshingAlgorithm::MarchingTetrahedra => false,
+            MeshingAlgorithm::SurfaceNets | MeshingAlgorithm::DualContouring => true,
+        }
+    }
+
+    /// Returns `true` for algorithms that mesh a chunk's
+    /// [transition faces](crate::chunk::Chunk::transition_faces) as transition cells.
+    pub fn supports_transitions(self) -> bool {
+        matches!(
+            self,
+            MeshingAlgorithm::MarchingCubes | MeshingAlgorithm::MarchingCubes33
+        )
+    }
+}
 
 /// The raw mesh data produced by the marching cubes algorithm for a [`Chunk`](crate::chunk::Chunk).
 ///
@@ -12,18 +130,85 @@ use bevy::prelude::*;
 /// [your collider system]      →  read GeneratedMesh, build collider
 /// MarchingCubesSet::Upload    →  Mesh3d inserted, GeneratedMesh removed
 /// ```
-#[derive(Component)]
+///
+/// To keep the data afterwards, for raycasts or navigation, enable
+/// [`MarchingCubesConfig::retain_mesh_data`](crate::MarchingCubesConfig::retain_mesh_data)
+/// and read [`RetainedMesh`] instead.
+#[derive(Component, Clone)]
 pub struct GeneratedMesh {
     /// Flat list of vertex positions: `[[x, y, z], ...]`
     pub vertices: Vec<[f32; 3]>,
 
     /// Flat list of triangle indices in groups of 3: `[v0, v1, v2, v3, v4, v5, ...]`
+    ///
+    /// Vertices are shared: each grid edge crossed by the surface contributes one vertex,
+    /// referenced by every triangle that touches that edge. With
+    /// [`MeshingAlgorithm::SurfaceNets`] and [`MeshingAlgorithm::DualContouring`] each cell the
+    /// surface passes through contributes one instead, and with
+    /// [`MeshingAlgorithm::MarchingTetrahedra`] so do crossed face and body diagonals.
     pub indices: Vec<u32>,
 
-    /// Per-vertex face normals, one per vertex: `[[nx, ny, nz], ...]`
+    /// Per-vertex normals, one per vertex: `[[nx, ny, nz], ...]`
     pub normals: Vec<[f32; 3]>,
+
+    /// Per-vertex RGBA colours, one per vertex.
+    ///
+    /// Empty unless the chunk has [`colors`](crate::chunk::Chunk::colors). Each vertex takes
+    /// the colours of its edge's two corners, interpolated to where the surface crosses it.
+    pub colors: Vec<[f32; 4]>,
+
+    /// Per-vertex material IDs packed as described by [`ATTRIBUTE_MATERIAL_IDS`].
+    ///
+    /// Empty unless the chunk has [`materials`](crate::chunk::Chunk::materials). A vertex's
+    /// own material is that of the inside corner of its edge. Vertices on the border between
+    /// materials are duplicated so each triangle can carry its own set of IDs.
+    pub material_ids: Vec<u32>,
+
+    /// Per-vertex blend weights matching [`material_ids`](GeneratedMesh::material_ids).
+    pub material_weights: Vec<[f32; 4]>,
+
+    /// Per-vertex texture coordinates; empty unless UVs were generated with
+    /// [`compute_triplanar_uvs`](GeneratedMesh::compute_triplanar_uvs).
+    pub uvs: Vec<[f32; 2]>,
+
+    /// Per-vertex tangents for normal mapping, matching [`uvs`](GeneratedMesh::uvs): `[tx, ty,
+    /// tz, w]`, where `w` is the handedness of the bitangent.
+    pub tangents: Vec<[f32; 4]>,
 }
 
+/// Main-world copy of the last [`GeneratedMesh`] uploaded for an entity.
+///
+/// Inserted by [`MarchingCubesSet::Upload`](crate::MarchingCubesSet::Upload) when
+/// [`MarchingCubesConfig::retain_mesh_data`](crate::MarchingCubesConfig::retain_mesh_data)
+/// is set, replaced on every re-mesh and removed when the chunk becomes
+/// [empty](crate::EmptyChunk). Unlike the [`Mesh`] asset, it stays readable whatever the
+/// [`RenderAssetUsages`](bevy::asset::RenderAssetUsages), so gameplay code can raycast
+/// against or walk the surface:
+///
+/// ```rust,ignore
+/// fn count_triangles(chunks: Query<&RetainedMesh>) -> usize {
+///     chunks.iter().map(|mesh| mesh.tri_count()).sum()
+/// }
+/// ```
+///
+/// With levels of detail it holds the finest level.
+#[derive(Component, Clone, Deref)]
+pub struct RetainedMesh(pub GeneratedMesh);
+
+/// Box projections used by [`GeneratedMesh::compute_triplanar_uvs`]: the direction a
+/// triangle faces, and the world axes along which U and V increase on it.
+///
+/// Indexed by `axis * 2`, plus one for the negative direction. Side faces keep V pointing
+/// down so textures stay upright.
+const UV_PROJECTIONS: [(Vec3, Vec3, Vec3); 6] = [
+    (Vec3::X, Vec3::NEG_Z, Vec3::NEG_Y),
+    (Vec3::NEG_X, Vec3::Z, Vec3::NEG_Y),
+    (Vec3::Y, Vec3::X, Vec3::Z),
+    (Vec3::NEG_Y, Vec3::X, Vec3::NEG_Z),
+    (Vec3::Z, Vec3::X, Vec3::NEG_Y),
+    (Vec3::NEG_Z, Vec3::NEG_X, Vec3::NEG_Y),
+];
+
 impl GeneratedMesh {
     /// Returns the three vertex positions of triangle `tri`.
     pub fn tri_coords(&self, tri: usize) -> [[f32; 3]; 3] {
@@ -35,27 +220,27 @@ impl GeneratedMesh {
         ]
     }
 
-    /// Computes the face normal for triangle `tri`.
+    /// Computes the unnormalised face normal for triangle `tri`.
     ///
-    /// Returns the zero vector if the triangle is degenerate.
-    pub fn tri_normal(&self, tri: usize) -> [f32; 3] {
+    /// The length of the result is twice the triangle's area.
+    fn tri_cross(&self, tri: usize) -> [f32; 3] {
         let [a, b, c] = self.tri_coords(tri);
 
         let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
         let bc = [c[0] - b[0], c[1] - b[1], c[2] - b[2]];
 
-        let cross = [
+        [
             ab[1] * bc[2] - ab[2] * bc[1],
             ab[2] * bc[0] - ab[0] * bc[2],
             ab[0] * bc[1] - ab[1] * bc[0],
-        ];
+        ]
+    }
 
-        let len = (cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2]).sqrt();
-        if len == 0.0 {
-            [0.0, 0.0, 0.0]
-        } else {
-            [cross[0] / len, cross[1] / len, cross[2] / len]
-        }
+    /// Computes the face normal for triangle `tri`.
+    ///
+    /// Returns the zero vector if the triangle is degenerate.
+    pub fn tri_normal(&self, tri: usize) -> [f32; 3] {
+        normalize_or_zero(self.tri_cross(tri))
     }
 
     /// Returns the number of triangles.
@@ -63,33 +248,333 @@ impl GeneratedMesh {
         self.indices.len() / 3
     }
 
-    /// Builds a [`GeneratedMesh`] from a flat vertex buffer.
+    /// Builds a [`GeneratedMesh`] from an indexed vertex buffer and applies `mode`.
     ///
-    /// Indices are generated sequentially (`0,1,2, 3,4,5, ...`) and face normals
-    /// are computed per triangle and duplicated across each triangle's three vertices.
-    pub(crate) fn build(vertices: Vec<[f32; 3]>) -> Self {
-        let vert_count = vertices.len();
-        let indices: Vec<u32> = (0..vert_count as u32).collect();
+    /// `normals` is only used for [`NormalMode::Gradient`], where it must hold one normal per
+    /// vertex; the other modes compute their normals from the triangles and ignore it.
+    /// `colors` and `materials` hold one entry per vertex, or nothing if the chunk has none.
+    pub(crate) fn build(
+        vertices: Vec<[f32; 3]>,
+        indices: Vec<u32>,
+        normals: Vec<[f32; 3]>,
+        colors: Vec<[f32; 4]>,
+        materials: Vec<MaterialId>,
+        mode: NormalMode,
+    ) -> Self {
         let mut mesh = Self {
             vertices,
             indices,
-            normals: Vec::with_capacity(vert_count),
+            normals,
+            colors,
+            material_ids: Vec::new(),
+            material_weights: Vec::new(),
+            uvs: Vec::new(),
+            tangents: Vec::new(),
         };
-        mesh.compute_normals();
+        match mode {
+            NormalMode::Flat => {}
+            NormalMode::AreaWeighted => mesh.compute_normals(),
+            NormalMode::Gradient => debug_assert_eq!(mesh.normals.len(), mesh.vertices.len()),
+        }
+        if !materials.is_empty() {
+            mesh.blend_materials(&materials);
+        }
+        if mode == NormalMode::Flat {
+            mesh.compute_flat_normals();
+        }
         mesh
     }
 
-    /// Recomputes face normals for all triangles, replacing any existing normals.
+    /// Fills [`material_ids`](GeneratedMesh::material_ids) and
+    /// [`material_weights`](GeneratedMesh::material_weights) from each vertex's own material.
+    ///
+    /// A vertex is duplicated for every extra set of triangle materials it takes part in, so
+    /// only vertices on material borders are unwelded.
+    fn blend_materials(&mut self, materials: &[MaterialId]) {
+        debug_assert_eq!(materials.len(), self.vertices.len());
+        let packed: Vec<u32> = self
+            .indices
+            .chunks_exact(3)
+            .map(|corners| {
+                let mut distinct: Vec<MaterialId> =
+                    corners.iter().map(|&i| materials[i as usize]).collect();
+                distinct.sort_unstable();
+                distinct.dedup();
+                distinct
+                    .iter()
+                    .enumerate()
+                    .fold(0, |acc, (slot, &id)| acc | (id as u32) << (slot * 8))
+            })
+            .collect();
+        let (ids, sources) = self.split_vertices(&packed);
+
+        self.material_weights = ids
+            .iter()
+            .zip(&sources)
+            .map(|(&packed, &source)| {
+                let material = materials[source as usize];
+                let mut weights = [0.0; 4];
+                let slot = (0..4)
+                    .find(|slot| (packed >> (slot * 8)) as u8 == material)
+                    .unwrap_or(0);
+                weights[slot] = 1.0;
+                weights
+            })
+            .collect();
+        self.material_ids = ids;
+    }
+
+    /// Generates box-projected [`uvs`](GeneratedMesh::uvs) and
+    /// [`tangents`](GeneratedMesh::tangents), replacing any existing ones.
+    ///
+    /// Each triangle is projected onto the plane facing its dominant normal axis, using the
+    /// world-space position `origin + vertex`, so textures line up across chunks of any
+    /// [`scale`](crate::chunk::Chunk::scale). One UV unit spans `tile_size` world units.
+    /// Vertices shared by triangles with different projections are duplicated.
+    pub fn compute_triplanar_uvs(&mut self, origin: Vec3, tile_size: f32) {
+        let projections: Vec<u32> = (0..self.tri_count())
+            .map(|tri| {
+                let n = self.tri_cross(tri);
+                let axis = (0..3)
+                    .max_by(|&a, &b| n[a].abs().total_cmp(&n[b].abs()))
+                    .unwrap_or(0);
+                (axis * 2 + usize::from(n[axis] < 0.0)) as u32
+            })
+            .collect();
+        self.uvs.clear();
+        self.tangents.clear();
+        let (projections, _) = self.split_vertices(&projections);
+
+        self.uvs.reserve(self.vertices.len());
+        self.tangents.reserve(self.vertices.len());
+        for (i, &projection) in projections.iter().enumerate() {
+            let (axis, u, v) = UV_PROJECTIONS[projection as usize];
+            let p = origin + Vec3::from(self.vertices[i]);
+            self.uvs.push([p.dot(u) / tile_size, p.dot(v) / tile_size]);
+
+            // Bevy rebuilds the bitangent as `cross(normal, tangent) * w`.
+            let normal = Vec3::from(self.normals[i]);
+            let tangent = (u - normal * normal.dot(u)).normalize_or(u);
+            let w = axis.cross(u).dot(v).signum();
+            self.tangents.push([tangent.x, tangent.y, tangent.z, w]);
+        }
+    }
+
+    /// Unwelds vertices so every vertex is only used by triangles with the same key.
+    ///
+    /// `tri_keys` holds one key per triangle. A vertex is duplicated, with all of its
+    /// attributes, for every extra key among the triangles using it. Returns the key of each
+    /// vertex and the original vertex it was copied from.
+    fn split_vertices(&mut self, tri_keys: &[u32]) -> (Vec<u32>, Vec<u32>) {
+        debug_assert_eq!(tri_keys.len(), self.tri_count());
+        let mut keys: Vec<Option<u32>> = vec![None; self.vertices.len()];
+        let mut sources: Vec<u32> = (0..self.vertices.len() as u32).collect();
+        let mut copies: HashMap<(u32, u32), u32> = HashMap::new();
+
+        for (tri, &key) in tri_keys.iter().enumerate() {
+            for k in 0..3 {
+                let i = self.indices[tri * 3 + k];
+                match keys[i as usize] {
+                    None => keys[i as usize] = Some(key),
+                    Some(existing) if existing == key => {}
+                    Some(_) => {
+                        let copy = *copies.entry((i, key)).or_insert_with(|| {
+                            self.copy_vertex(i as usize);
+                            keys.push(Some(key));
+                            sources.push(i);
+                            (self.vertices.len() - 1) as u32
+                        });
+                        self.indices[tri * 3 + k] = copy;
+                    }
+                }
+            }
+        }
+
+        let keys = keys.into_iter().map(Option::unwrap_or_default).collect();
+        (keys, sources)
+    }
+
+    /// Appends a copy of vertex `i` with every attribute the mesh has.
+    fn copy_vertex(&mut self, i: usize) {
+        fn copy<T: Copy>(attribute: &mut Vec<T>, i: usize) {
+            if !attribute.is_empty() {
+                attribute.push(attribute[i]);
+            }
+        }
+        copy(&mut self.vertices, i);
+        copy(&mut self.normals, i);
+        copy(&mut self.colors, i);
+        copy(&mut self.material_ids, i);
+        copy(&mut self.material_weights, i);
+        copy(&mut self.uvs, i);
+        copy(&mut self.tangents, i);
+    }
+
+    /// Recomputes per-vertex normals, replacing any existing normals.
     ///
-    /// Each triangle's normal is pushed once per vertex (flat shading).
-    /// TODO: Experiment with option for interpolated normals.
+    /// Each vertex normal is the average of the face normals of every triangle that uses it,
+    /// weighted by triangle area so slivers barely contribute.
     pub fn compute_normals(&mut self) {
         self.normals.clear();
+        self.normals.resize(self.vertices.len(), [0.0; 3]);
+        for tri in 0..self.tri_count() {
+            let n = self.tri_cross(tri);
+            for &i in &self.indices[tri * 3..tri * 3 + 3] {
+                let acc = &mut self.normals[i as usize];
+                acc[0] += n[0];
+                acc[1] += n[1];
+                acc[2] += n[2];
+            }
+        }
+        for n in self.normals.iter_mut() {
+            *n = normalize_or_zero(*n);
+        }
+    }
+
+    /// Unwelds the mesh and recomputes flat face normals, replacing any existing normals.
+    ///
+    /// Every triangle gets its own copy of its three vertices so each can carry the
+    /// triangle's face normal. Indices become sequential (`0,1,2, 3,4,5, ...`). Vertex
+    /// colours, materials, UVs and tangents are copied along with positions.
+    pub fn compute_flat_normals(&mut self) {
+        fn unweld<T: Copy>(attribute: &mut Vec<T>, indices: &[u32]) {
+            if !attribute.is_empty() {
+                *attribute = indices.iter().map(|&i| attribute[i as usize]).collect();
+            }
+        }
+
+        let vertices: Vec<[f32; 3]> = self
+            .indices
+            .iter()
+            .map(|&i| self.vertices[i as usize])
+            .collect();
+        self.normals.clear();
+        self.normals.reserve(vertices.len());
         for tri in 0..self.tri_count() {
             let n = self.tri_normal(tri);
             self.normals.push(n);
             self.normals.push(n);
             self.normals.push(n);
         }
+        unweld(&mut self.colors, &self.indices);
+        unweld(&mut self.material_ids, &self.indices);
+        unweld(&mut self.material_weights, &self.indices);
+        unweld(&mut self.uvs, &self.indices);
+        unweld(&mut self.tangents, &self.indices);
+        self.indices = (0..vertices.len() as u32).collect();
+        self.vertices = vertices;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns an octahedron with its corners on the axes, coloured by position, with
+    /// material `1` on the positive axes and `2` on the negative ones.
+    fn octahedron(mode: NormalMode) -> GeneratedMesh {
+        let vertices = vec![
+            [1.0, 0.0, 0.0],
+            [-1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, -1.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [0.0, 0.0, -1.0],
+        ];
+        let mut indices = Vec::new();
+        for x in [0, 1] {
+            for y in [2, 3] {
+                for z in [4, 5] {
+                    // Mirroring an octant flips its winding, so flip it back.
+                    if (x + y + z) % 2 == 0 {
+                        indices.extend([x, y, z]);
+                    } else {
+                        indices.extend([x, z, y]);
+                    }
+                }
+            }
+        }
+        let colors = vertices.iter().map(|&[x, y, z]| [x, y, z, 1.0]).collect();
+        let materials = vec![1, 2, 1, 2, 1, 2];
+        GeneratedMesh::build(vertices, indices, Vec::new(), colors, materials, mode)
+    }
+
+    /// Asserts that vertex `i` of `mesh` carries every attribute of vertex `j` of `source`.
+    fn assert_copied(mesh: &GeneratedMesh, i: usize, source: &GeneratedMesh, j: usize) {
+        assert_eq!(mesh.vertices[i], source.vertices[j]);
+        assert_eq!(mesh.colors[i], source.colors[j]);
+        assert_eq!(mesh.material_ids[i], source.material_ids[j]);
+        assert_eq!(mesh.material_weights[i], source.material_weights[j]);
+        assert_eq!(mesh.uvs[i], source.uvs[j]);
+        assert_eq!(mesh.tangents[i], source.tangents[j]);
+    }
+
+    #[test]
+    fn built_meshes_keep_materials_and_colours_with_their_vertices() {
+        for mode in [NormalMode::AreaWeighted, NormalMode::Flat] {
+            let mesh = octahedron(mode);
+            let n = mesh.vertices.len();
+            assert_eq!(mesh.normals.len(), n);
+            assert_eq!(mesh.colors.len(), n);
+            assert_eq!(mesh.material_ids.len(), n);
+            assert_eq!(mesh.material_weights.len(), n);
+
+            for (i, &[x, y, z]) in mesh.vertices.iter().enumerate() {
+                assert_eq!(mesh.colors[i], [x, y, z, 1.0], "{mode:?}");
+                let material = if x + y + z > 0.0 { 1 } else { 2 };
+                let slot = mesh.material_weights[i].iter().position(|&w| w == 1.0);
+                assert_eq!(
+                    slot.map(|slot| (mesh.material_ids[i] >> (slot * 8)) as u8),
+                    Some(material),
+                    "{mode:?}"
+                );
+            }
+            for triangle in mesh.indices.chunks_exact(3) {
+                let id = mesh.material_ids[triangle[0] as usize];
+                assert!(
+                    triangle
+                        .iter()
+                        .all(|&i| mesh.material_ids[i as usize] == id),
+                    "{mode:?}"
+                );
+            }
+        }
+        assert_eq!(octahedron(NormalMode::Flat).vertices.len(), 24);
+    }
+
+    #[test]
+    fn compute_flat_normals_copies_every_attribute() {
+        let mut mesh = octahedron(NormalMode::AreaWeighted);
+        mesh.compute_triplanar_uvs(Vec3::ZERO, 1.0);
+        let welded = mesh.clone();
+        mesh.compute_flat_normals();
+
+        assert_eq!(
+            mesh.indices,
+            (0..welded.indices.len() as u32).collect::<Vec<_>>()
+        );
+        for (i, &j) in welded.indices.iter().enumerate() {
+            assert_copied(&mesh, i, &welded, j as usize);
+            assert_eq!(mesh.normals[i], mesh.tri_normal(i / 3));
+        }
+    }
+
+    #[test]
+    fn split_vertices_copies_every_attribute() {
+        let mut mesh = octahedron(NormalMode::AreaWeighted);
+        mesh.compute_triplanar_uvs(Vec3::ZERO, 1.0);
+        let original = mesh.clone();
+        let tri_keys: Vec<u32> = (0..mesh.tri_count() as u32).map(|tri| tri % 3).collect();
+        let (keys, sources) = mesh.split_vertices(&tri_keys);
+
+        assert_eq!(keys.len(), mesh.vertices.len());
+        for (i, &j) in sources.iter().enumerate() {
+            assert_copied(&mesh, i, &original, j as usize);
+            assert_eq!(mesh.normals[i], original.normals[j as usize]);
+        }
+        for (k, (&i, &j)) in mesh.indices.iter().zip(&original.indices).enumerate() {
+            assert_eq!(keys[i as usize], tri_keys[k / 3]);
+            assert_eq!(sources[i as usize], j);
+        }
     }
 }
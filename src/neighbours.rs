@@ -0,0 +1,499 @@
+use std::{collections::HashMap, sync::Arc};
+
+use bevy::{
+    ecs::{lifecycle::HookContext, world::DeferredWorld},
+    prelude::*,
+};
+
+use crate::{
+    chunk::Chunk,
+    grid::VoxelGrid,
+    transvoxel::fine_count,
+    types::{MaterialId, Value},
+};
+
+/// One of the six faces of a [`Chunk`], named after the direction it faces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Face {
+    /// The `x = 0` face.
+    NegX,
+    /// The `x = size_x` face.
+    PosX,
+    /// The `y = 0` face.
+    NegY,
+    /// The `y = size_y` face.
+    PosY,
+    /// The `z = 0` face.
+    NegZ,
+    /// The `z = size_z` face.
+    PosZ,
+}
+
+impl Face {
+    /// All six faces, in the same order as [`Face::index`].
+    pub const ALL: [Face; 6] = [
+        Face::NegX,
+        Face::PosX,
+        Face::NegY,
+        Face::PosY,
+        Face::NegZ,
+        Face::PosZ,
+    ];
+
+    /// Returns the position of this face in [`Face::ALL`].
+    #[inline]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Returns the axis the face is perpendicular to (`0` = X, `1` = Y, `2` = Z).
+    #[inline]
+    pub fn axis(self) -> usize {
+        self.index() / 2
+    }
+
+    /// Returns `true` for the faces pointing along +X, +Y or +Z.
+    #[inline]
+    pub fn is_positive(self) -> bool {
+        self.index() % 2 == 1
+    }
+
+    /// Returns the chunk coordinate offset to the neighbour across this face.
+    #[inline]
+    pub fn offset(self) -> IVec3 {
+        let mut offset = IVec3::ZERO;
+        offset[self.axis()] = if self.is_positive() { 1 } else { -1 };
+        offset
+    }
+
+    /// Returns the face pointing the other way.
+    #[inline]
+    pub fn opposite(self) -> Face {
+        Face::ALL[self.index() ^ 1]
+    }
+}
+
+/// Integer position of a [`Chunk`] in the chunk grid.
+///
+/// Chunks carrying this component are registered in [`ChunkMap`], which lets the plugin
+/// find their neighbours to keep shared faces in sync and sample one layer past each face
+/// while meshing. The chunk's [`Transform`] is still up to you; neighbours are expected to
+/// cover the same extent and sit exactly one chunk extent apart, with either the same
+/// dimensions or, across a face listed in [`Chunk::transition_faces`], twice the resolution.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Deref)]
+#[component(on_insert = register_chunk_coord, on_replace = unregister_chunk_coord)]
+pub struct ChunkCoord(pub IVec3);
+
+fn register_chunk_coord(mut world: DeferredWorld, context: HookContext) {
+    let coord = world.get::<ChunkCoord>(context.entity).map(|c| c.0);
+    if let (Some(coord), Some(mut map)) = (coord, world.get_resource_mut::<ChunkMap>()) {
+        map.chunks.insert(coord, context.entity);
+    }
+}
+
+fn unregister_chunk_coord(mut world: DeferredWorld, context: HookContext) {
+    let coord = world.get::<ChunkCoord>(context.entity).map(|c| c.0);
+    if let (Some(coord), Some(mut map)) = (coord, world.get_resource_mut::<ChunkMap>())
+        && map.chunks.get(&coord) == Some(&context.entity)
+    {
+        map.chunks.remove(&coord);
+    }
+}
+
+/// Maps every [`ChunkCoord`] in the world to its entity.
+///
+/// Kept up to date automatically as [`ChunkCoord`] components are inserted, replaced and
+/// removed. Inserted as a resource by [`MarchingCubesPlugin`](crate::MarchingCubesPlugin).
+#[derive(Resource, Default, Debug)]
+pub struct ChunkMap {
+    chunks: HashMap<IVec3, Entity>,
+}
+
+impl ChunkMap {
+    /// Returns the chunk entity at `coord`, if one is registered.
+    pub fn get(&self, coord: IVec3) -> Option<Entity> {
+        self.chunks.get(&coord).copied()
+    }
+
+    /// Returns the chunk entity across `face` of the chunk at `coord`.
+    pub fn neighbour(&self, coord: IVec3, face: Face) -> Option<Entity> {
+        self.get(coord + face.offset())
+    }
+
+    /// Returns `true` if a chunk is registered at `coord`.
+    pub fn contains(&self, coord: IVec3) -> bool {
+        self.chunks.contains_key(&coord)
+    }
+
+    /// Iterates over every registered `(coord, entity)` pair.
+    pub fn iter(&self) -> impl Iterator<Item = (IVec3, Entity)> + '_ {
+        self.chunks.iter().map(|(coord, entity)| (*coord, *entity))
+    }
+
+    /// Returns the number of registered chunks.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Returns `true` if no chunks are registered.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
+
+/// Copies the face shared by two adjacent chunks from `src` into `dst`, including
+/// [`materials`](Chunk::materials) when both chunks have them.
+///
+/// `face` is the face of `src` that touches `dst`. If `src` has twice the resolution of
+/// `dst` across the face, every other sample is copied. Returns `false` without writing if
+/// the chunks' dimensions don't line up or the samples already agree, so `dst` is only
+/// marked as changed when its values actually differ.
+pub fn copy_shared_face(src: &Chunk, dst: &mut Mut<Chunk>, face: Face) -> bool {
+    let axis = face.axis();
+    let (src_dims, dst_dims) = (src.values().dims(), dst.values().dims());
+    let lines_up =
+        |f: fn(usize) -> usize| (0..3).all(|a| a == axis || src_dims[a] == f(dst_dims[a]));
+    let step = if lines_up(|d| d) {
+        1
+    } else if lines_up(fine_count) {
+        2
+    } else {
+        return false;
+    };
+
+    let src_index = if face.is_positive() {
+        src_dims[axis] - 1
+    } else {
+        0
+    };
+    let dst_index = if face.is_positive() {
+        0
+    } else {
+        dst_dims[axis] - 1
+    };
+
+    let mut changed = false;
+    let layer = src.values().layer(axis, src_index).downsample(step);
+    if dst.values().layer(axis, dst_index) != layer {
+        dst.values_mut().set_layer(axis, dst_index, &layer);
+        dst.update_value_range();
+        changed = true;
+    }
+
+    // Materials and colours only need to agree if both sides have them. Change detection is
+    // bypassed so an unchanged layer doesn't mark the chunk changed.
+    let chunk = dst.bypass_change_detection();
+    if let (Some(src_materials), Some(dst_materials)) = (&src.materials, &mut chunk.materials) {
+        changed |= copy_layer(
+            src_materials,
+            src_index,
+            dst_materials,
+            dst_index,
+            axis,
+            step,
+        );
+    }
+    if let (Some(src_colors), Some(dst_colors)) = (&src.colors, &mut chunk.colors) {
+        changed |= copy_layer(src_colors, src_index, dst_colors, dst_index, axis, step);
+    }
+    if changed {
+        dst.set_changed();
+    }
+
+    changed
+}
+
+/// Copies layer `src_index` of `src`, downsampled by `step`, over layer `dst_index` of `dst`.
+///
+/// Returns whether the layer changed. `dst` is only cloned out of a shared [`Arc`] if it did.
+fn copy_layer<T: Copy + PartialEq>(
+    src: &VoxelGrid<T>,
+    src_index: usize,
+    dst: &mut Arc<VoxelGrid<T>>,
+    dst_index: usize,
+    axis: usize,
+    step: usize,
+) -> bool {
+    let layer = src.layer(axis, src_index).downsample(step);
+    if dst.layer(axis, dst_index) == layer {
+        return false;
+    }
+    Arc::make_mut(dst).set_layer(axis, dst_index, &layer);
+    true
+}
+
+/// One layer of neighbour samples just past each face of a chunk.
+///
+/// Gathered when a chunk with a [`ChunkCoord`] is queued for meshing, so the mesher can
+/// take central differences across the chunk boundary. Gradient normals on either side of a
+/// seam then come from the same samples and match exactly.
+///
+/// ```text
+///   neighbour (-X)          this chunk            neighbour (+X)
+/// .. [n-1] [n]   ==   [0] [1] .. [n-1] [n]   ==   [0] [1] ..
+///     ^ NegX layer                                     ^ PosX layer
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ChunkHalo {
+    layers: [Option<VoxelGrid>; 6],
+}
+
+impl ChunkHalo {
+    /// Collects the layer one step past each face of `chunk` from its registered neighbours.
+    ///
+    /// `get_chunk` resolves a neighbour entity to its [`Chunk`]. Faces without a neighbour,
+    /// or whose neighbour's dimensions don't line up, are left empty.
+    pub fn gather<'a>(
+        chunk: &Chunk,
+        coord: IVec3,
+        map: &ChunkMap,
+        get_chunk: impl Fn(Entity) -> Option<&'a Chunk>,
+    ) -> Self {
+        let dims = chunk.values().dims();
+        let mut halo = Self::default();
+        for face in Face::ALL {
+            let Some(other) = map.neighbour(coord, face).and_then(&get_chunk) else {
+                continue;
+            };
+            let axis = face.axis();
+            let other_dims = other.values().dims();
+            if other_dims[axis] < 2 || (0..3).any(|a| a != axis && other_dims[a] != dims[a]) {
+                continue;
+            }
+            let index = if face.is_positive() {
+                1
+            } else {
+                other_dims[axis] - 2
+            };
+            halo.layers[face.index()] = Some(other.values().layer(axis, index));
+        }
+        halo
+    }
+
+    /// Samples `values` at `(x, y, z)`, reaching one step past any face into the halo.
+    ///
+    /// Returns `None` outside the grid where no halo layer is available, including points
+    /// more than one step outside or outside along more than one axis.
+    pub fn sample(&self, values: &VoxelGrid, x: isize, y: isize, z: isize) -> Option<Value> {
+        let dims = values.dims();
+        let mut p = [x, y, z];
+        let mut outside = None;
+        for axis in 0..3 {
+            let face = if p[axis] == -1 {
+                Face::ALL[axis * 2]
+            } else if p[axis] == dims[axis] as isize {
+                Face::ALL[axis * 2 + 1]
+            } else if p[axis] < -1 || p[axis] > dims[axis] as isize {
+                return None;
+            } else {
+                continue;
+            };
+            if outside.is_some() {
+                return None;
+            }
+            outside = Some(face);
+            p[axis] = 0;
+        }
+
+        let [x, y, z] = p.map(|c| c as usize);
+        match outside {
+            None => Some(values.get(x, y, z)),
+            Some(face) => self.layers[face.index()]
+                .as_ref()
+                .map(|layer| layer.get(x, y, z)),
+        }
+    }
+}
+
+/// Samples one step past the positive faces, edges and corner of a chunk.
+///
+/// [Dual](crate::mesh::MeshingAlgorithm::is_dual) meshers put one vertex in each cell and
+/// join the cells around every crossed grid edge. The cells around edges on a chunk's
+/// positive faces straddle the seam, so the chunk reads the first layer of samples from up
+/// to seven neighbours on its positive side (three across faces, three across edges and one
+/// across the corner) and meshes those edges itself. The neighbours skip them, so seams
+/// close without overlapping.
+///
+/// ```text
+///        this chunk          +X neighbour
+/// .. [n-1]  [n]   ==   [0]   [1] ..
+///                             ^ padding
+/// ```
+///
+/// Neighbours that are missing or whose dimensions differ leave that part of the seam open.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkPadding {
+    values: [Option<VoxelGrid>; 7],
+    materials: [Option<VoxelGrid<MaterialId>>; 7],
+    colors: [Option<VoxelGrid<[f32; 4]>>; 7],
+    normals: [Option<VoxelGrid<[f32; 3]>>; 7],
+}
+
+impl ChunkPadding {
+    /// Chunk coordinate offsets of the neighbours read, indexed by `x | y << 1 | z << 2`,
+    /// minus one.
+    pub const OFFSETS: [IVec3; 7] = [
+        IVec3::new(1, 0, 0),
+        IVec3::new(0, 1, 0),
+        IVec3::new(1, 1, 0),
+        IVec3::new(0, 0, 1),
+        IVec3::new(1, 0, 1),
+        IVec3::new(0, 1, 1),
+        IVec3::new(1, 1, 1),
+    ];
+
+    /// Collects the samples past the positive side of `chunk` from its registered
+    /// neighbours.
+    ///
+    /// `get_chunk` resolves a neighbour entity to its [`Chunk`].
+    pub fn gather<'a>(
+        chunk: &Chunk,
+        coord: IVec3,
+        map: &ChunkMap,
+        get_chunk: impl Fn(Entity) -> Option<&'a Chunk>,
+    ) -> Self {
+        Self::gather_downsampled(chunk, coord, map, get_chunk, 1)
+    }
+
+    /// Like [`gather`](ChunkPadding::gather), for meshing the chunk's values
+    /// [downsampled](VoxelGrid::downsample) by `step`: the padding is the neighbours' layer
+    /// `step` samples in, keeping every `step`th sample.
+    pub fn gather_downsampled<'a>(
+        chunk: &Chunk,
+        coord: IVec3,
+        map: &ChunkMap,
+        get_chunk: impl Fn(Entity) -> Option<&'a Chunk>,
+        step: usize,
+    ) -> Self {
+        let dims = chunk.values().dims();
+        let mut padding = Self::default();
+        if dims.iter().any(|&d| d <= step) {
+            return padding;
+        }
+        for (i, offset) in Self::OFFSETS.into_iter().enumerate() {
+            let Some(other) = map.get(coord + offset).and_then(&get_chunk) else {
+                continue;
+            };
+            if other.values().dims() != dims {
+                continue;
+            }
+            let bits = i + 1;
+            padding.values[i] = Some(padding_block(other.values(), bits, step));
+            padding.materials[i] = other
+                .materials
+                .as_ref()
+                .map(|m| padding_block(m, bits, step));
+            padding.colors[i] = other.colors.as_ref().map(|c| padding_block(c, bits, step));
+            padding.normals[i] = other.normals.as_ref().map(|n| padding_block(n, bits, step));
+        }
+        padding
+    }
+
+    /// Samples `values` at `(x, y, z)`, reaching one step past the positive faces into the
+    /// padding.
+    ///
+    /// Returns `None` where no padding is available.
+    pub fn value(&self, values: &VoxelGrid, x: usize, y: usize, z: usize) -> Option<Value> {
+        padded_sample(values, &self.values, [x, y, z])
+    }
+
+    /// Samples `materials` like [`value`](ChunkPadding::value), returning `0` where the
+    /// neighbour has no materials.
+    pub fn material(
+        &self,
+        materials: &VoxelGrid<MaterialId>,
+        x: usize,
+        y: usize,
+        z: usize,
+    ) -> MaterialId {
+        padded_sample(materials, &self.materials, [x, y, z]).unwrap_or(0)
+    }
+
+    /// Samples `colors` like [`value`](ChunkPadding::value), returning opaque white where
+    /// the neighbour has no colours.
+    pub fn color(&self, colors: &VoxelGrid<[f32; 4]>, x: usize, y: usize, z: usize) -> [f32; 4] {
+        padded_sample(colors, &self.colors, [x, y, z]).unwrap_or([1.0; 4])
+    }
+
+    /// Samples `normals` like [`value`](ChunkPadding::value), returning zero where the
+    /// neighbour has no normals.
+    pub fn normal(&self, normals: &VoxelGrid<[f32; 3]>, x: usize, y: usize, z: usize) -> [f32; 3] {
+        padded_sample(normals, &self.normals, [x, y, z]).unwrap_or([0.0; 3])
+    }
+}
+
+/// Copies the samples at index `step` along every axis set in `bits`, keeping every
+/// `step`th sample along the others.
+fn padding_block<T: Copy>(grid: &VoxelGrid<T>, bits: usize, step: usize) -> VoxelGrid<T> {
+    let mut axes = (0..3).filter(|axis| bits >> axis & 1 == 1);
+    let first = axes.next().expect("padding block without an axis");
+    let block = axes.fold(grid.layer(first, step), |block, axis| {
+        block.layer(axis, step)
+    });
+    if step == 1 {
+        block
+    } else {
+        block.downsample(step)
+    }
+}
+
+/// Samples `grid` at `p`, or the padding block past its positive side.
+fn padded_sample<T: Copy>(
+    grid: &VoxelGrid<T>,
+    blocks: &[Option<VoxelGrid<T>>; 7],
+    mut p: [usize; 3],
+) -> Option<T> {
+    let dims = grid.dims();
+    let mut bits = 0;
+    for axis in 0..3 {
+        if p[axis] == dims[axis] {
+            bits |= 1 << axis;
+            p[axis] = 0;
+        } else if p[axis] > dims[axis] {
+            return None;
+        }
+    }
+    let [x, y, z] = p;
+    match bits {
+        0 => Some(grid.get(x, y, z)),
+        bits => blocks[bits - 1].as_ref().map(|block| block.get(x, y, z)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_map_follows_chunk_coords() {
+        let mut world = World::new();
+        world.init_resource::<ChunkMap>();
+        let a = world.spawn(ChunkCoord(IVec3::ZERO)).id();
+        let b = world.spawn(ChunkCoord(IVec3::X)).id();
+        let map = world.resource::<ChunkMap>();
+        assert_eq!(
+            (map.get(IVec3::ZERO), map.get(IVec3::X)),
+            (Some(a), Some(b))
+        );
+        assert_eq!(map.neighbour(IVec3::ZERO, Face::PosX), Some(b));
+
+        world.despawn(a);
+        assert_eq!(world.resource::<ChunkMap>().get(IVec3::ZERO), None);
+        assert_eq!(world.resource::<ChunkMap>().len(), 1);
+
+        // Moving a chunk frees its old coordinate.
+        world.entity_mut(b).insert(ChunkCoord(IVec3::Y));
+        let map = world.resource::<ChunkMap>();
+        assert_eq!((map.get(IVec3::X), map.get(IVec3::Y)), (None, Some(b)));
+
+        // A chunk taking over an occupied coordinate keeps it when the old one goes away.
+        let c = world.spawn(ChunkCoord(IVec3::Y)).id();
+        world.despawn(b);
+        let map = world.resource::<ChunkMap>();
+        assert_eq!(map.get(IVec3::Y), Some(c));
+        assert_eq!(map.len(), 1);
+
+        world.entity_mut(c).remove::<ChunkCoord>();
+        assert!(world.resource::<ChunkMap>().is_empty());
+    }
+}
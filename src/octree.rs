@@ -0,0 +1,732 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{
+    dual_contouring::Qef,
+    interp::{find_t, interpolate_points},
+    mesh::{GeneratedMesh, NormalMode},
+    surface_nets::split_quad,
+    types::Value,
+    utils::normalize_or_zero,
+};
+
+/// Marks nodes without a vertex in the node → vertex lookup.
+const NO_VERTEX: u32 = u32::MAX;
+
+/// The four cells around an edge, as offsets along the two other axes, counter-clockwise
+/// seen from the positive end of the edge. `0` is the cell on the negative side.
+const AROUND: [[usize; 2]; 4] = [[0, 0], [1, 0], [1, 1], [0, 1]];
+
+/// A voxel volume stored as an octree that is only refined near the surface.
+///
+/// Where a [`Chunk`](crate::chunk::Chunk) stores `(n + 1)³` values, an octree spanning `n`
+/// voxels per axis stores a single node for every region its bounds place entirely on one
+/// side of the [`threshold`](VoxelOctree::threshold), so memory grows with the area of the
+/// surface rather than the volume. Nodes the surface may cross are split down to single
+/// voxels, or stop early where the field is close enough to linear; see
+/// [`with_max_error`](VoxelOctree::with_max_error).
+///
+/// Meshed with adaptive dual contouring: every leaf the surface crosses gets one vertex,
+/// placed with the same Hermite data as [`MeshingAlgorithm::DualContouring`](crate::MeshingAlgorithm::DualContouring),
+/// and every edge crossed by the surface gets a polygon joining the leaves around it,
+/// whatever their size. Leaves of different sizes share their vertices along the seam
+/// between them. Nodes larger than a voxel only become leaves where the surface crosses
+/// them, and each of their faces, in a single sheet, so collapsing never tears or pinches
+/// the mesh: it is as watertight as dual contouring every voxel.
+///
+/// Spawn it as a component and [`MarchingCubesPlugin`](crate::MarchingCubesPlugin) meshes it
+/// off the main thread like a chunk, through the same [`GeneratedMesh`] and
+/// [`MarchingCubesSet`](crate::MarchingCubesSet) stages:
+///
+/// ```rust,ignore
+/// let shape = sdf::translate(sdf::sphere(200.0), Vec3::splat(256.0));
+/// let mut octree = VoxelOctree::new(9).with_max_error(0.05);
+/// octree.fill(&shape, |min, max| sdf::lipschitz_bounds(&shape, min, max, 1.0));
+/// commands.spawn((octree, MeshMaterial3d(material)));
+/// ```
+///
+/// The scale, threshold and error bound are baked into the nodes by
+/// [`fill`](VoxelOctree::fill), so they are only set through the builders, which discard
+/// anything filled so far.
+///
+/// Octrees have no materials or colours, and don't take part in chunk seams.
+#[derive(Component, Clone)]
+pub struct VoxelOctree {
+    /// Number of times the root is split to reach single voxels.
+    depth: u32,
+    /// World-space size of one voxel.
+    scale: Value,
+    /// Iso-surface threshold; values at or below it are inside.
+    threshold: Value,
+    /// See [`with_max_error`](VoxelOctree::with_max_error).
+    max_error: Value,
+    /// Per-octree override of [`MarchingCubesConfig::normal_mode`](crate::MarchingCubesConfig::normal_mode).
+    pub normal_mode: Option<NormalMode>,
+    /// Nodes in breadth-first order, root first; siblings are stored together.
+    nodes: Arc<Vec<OctreeNode>>,
+}
+
+/// A node of a [`VoxelOctree`].
+#[derive(Clone, Copy)]
+enum OctreeNode {
+    /// A region entirely on one side of the threshold.
+    Empty { inside: bool },
+    /// A region the surface crosses, with the vertex dual contouring places in it.
+    Leaf(OctreeLeaf),
+    /// A region split in eight; children are numbered `x | y << 1 | z << 2`.
+    Branch { first_child: u32 },
+}
+
+/// A [`VoxelOctree`] leaf the surface crosses.
+#[derive(Clone, Copy)]
+struct OctreeLeaf {
+    /// Values at the leaf's corners, numbered `x | y << 1 | z << 2`.
+    corners: [Value; 8],
+    /// Position of the leaf's vertex.
+    vertex: [f32; 3],
+    /// Normalised gradient of the field at `vertex`.
+    normal: [f32; 3],
+}
+
+/// How a node is stored, decided while filling.
+enum Classified {
+    Node(OctreeNode),
+    Split,
+}
+
+impl VoxelOctree {
+    /// Creates an empty octree spanning `2^depth` voxels per axis, with every point outside
+    /// the surface.
+    pub fn new(depth: u32) -> Self {
+        Self {
+            depth,
+            scale: 1.0,
+            threshold: 0.0,
+            max_error: 0.0,
+            normal_mode: None,
+            nodes: Arc::new(vec![OctreeNode::Empty { inside: false }]),
+        }
+    }
+
+    /// Sets the world-space size of one voxel, emptying the octree.
+    pub fn with_scale(self, scale: f32) -> Self {
+        Self {
+            scale,
+            ..self.cleared()
+        }
+    }
+
+    /// Sets the iso-surface threshold, emptying the octree.
+    pub fn with_threshold(self, threshold: f32) -> Self {
+        Self {
+            threshold,
+            ..self.cleared()
+        }
+    }
+
+    /// Sets how far the field may stray from the trilinear interpolation of a node's corners
+    /// for the node to be kept as a leaf, emptying the octree. `0.0`, the default, refines
+    /// every node the surface crosses down to single voxels. Nodes the surface crosses in
+    /// more than one sheet are refined whatever the error.
+    pub fn with_max_error(self, max_error: Value) -> Self {
+        Self {
+            max_error,
+            ..self.cleared()
+        }
+    }
+
+    /// Overrides [`MarchingCubesConfig::normal_mode`](crate::MarchingCubesConfig::normal_mode)
+    /// for this octree.
+    pub fn with_normal_mode(mut self, normal_mode: NormalMode) -> Self {
+        self.normal_mode = Some(normal_mode);
+        self
+    }
+
+    /// Returns the number of voxels the octree spans along each axis.
+    pub fn size(&self) -> usize {
+        1 << self.depth
+    }
+
+    /// Returns the number of times the root is split to reach single voxels.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Returns the world-space size of one voxel.
+    pub fn scale(&self) -> Value {
+        self.scale
+    }
+
+    /// Returns the iso-surface threshold; values at or below it are inside.
+    pub fn threshold(&self) -> Value {
+        self.threshold
+    }
+
+    /// Returns the error bound set by [`with_max_error`](VoxelOctree::with_max_error).
+    pub fn max_error(&self) -> Value {
+        self.max_error
+    }
+
+    /// Drops every node, leaving the whole octree outside the surface.
+    fn cleared(self) -> Self {
+        Self {
+            nodes: Arc::new(vec![OctreeNode::Empty { inside: false }]),
+            ..self
+        }
+    }
+
+    /// Returns the number of nodes stored, a measure of the octree's memory use.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the number of leaves the surface crosses, i.e. the vertices of its mesh.
+    pub fn leaf_count(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|node| matches!(node, OctreeNode::Leaf(_)))
+            .count()
+    }
+
+    /// Returns `true` if the point `p`, in the octree's local space, is inside the surface.
+    ///
+    /// Leaves the surface crosses interpolate their corners trilinearly. Points outside the
+    /// octree are outside.
+    pub fn is_inside(&self, p: Vec3) -> bool {
+        let mut local = p / self.scale;
+        let mut size = self.size() as f32;
+        if local.cmplt(Vec3::ZERO).any() || local.cmpgt(Vec3::splat(size)).any() {
+            return false;
+        }
+        let mut node = self.nodes[0];
+        loop {
+            match node {
+                OctreeNode::Empty { inside } => return inside,
+                OctreeNode::Leaf(leaf) => {
+                    let f = (local / size).to_array();
+                    return trilinear(&leaf.corners, f) <= self.threshold;
+                }
+                OctreeNode::Branch { first_child } => {
+                    size *= 0.5;
+                    let upper = local.cmpge(Vec3::splat(size));
+                    let child = upper.bitmask() as usize;
+                    local -= Vec3::select(upper, Vec3::splat(size), Vec3::ZERO);
+                    node = self.nodes[first_child as usize + child];
+                }
+            }
+        }
+    }
+
+    /// Rebuilds the octree from `function`, like [`Chunk::fill_pruned`](crate::chunk::Chunk::fill_pruned).
+    ///
+    /// `bounds(min, max)` must return a lower and upper bound of `function` over the box from
+    /// `min` to `max`; see [`SdfNode::bounds`](crate::SdfNode::bounds) and
+    /// [`sdf::lipschitz_bounds`](crate::sdf::lipschitz_bounds). Starting from the root, nodes
+    /// whose bounds stay above or below the threshold become empty, and the rest are split
+    /// until they reach single voxels or fit within [`max_error`](VoxelOctree::max_error).
+    /// Leaves take the gradient of `function`, estimated by central differences a
+    /// thousandth of a voxel wide, as the normals of their Hermite data.
+    ///
+    /// Coordinates passed to `function` are scaled by [`scale`](VoxelOctree::scale). Each
+    /// level of the octree is classified in parallel using Rayon.
+    pub fn fill<F, B>(&mut self, function: F, bounds: B)
+    where
+        F: Fn(f32, f32, f32) -> Value + Sync,
+        B: Fn(Vec3, Vec3) -> (Value, Value) + Sync,
+    {
+        let mut nodes = vec![OctreeNode::Empty { inside: false }];
+        let mut level = vec![(0, [0; 3], self.size())];
+        while !level.is_empty() {
+            let classified: Vec<Classified> = level
+                .par_iter()
+                .map(|&(_, min, size)| self.classify(&function, &bounds, min, size))
+                .collect();
+            let mut next = Vec::new();
+            for ((index, min, size), classified) in level.into_iter().zip(classified) {
+                nodes[index] = match classified {
+                    Classified::Node(node) => node,
+                    Classified::Split => {
+                        let first_child = nodes.len();
+                        let half = size / 2;
+                        for child in 0..8 {
+                            nodes.push(OctreeNode::Empty { inside: false });
+                            next.push((first_child + child, offset(min, child, half), half));
+                        }
+                        OctreeNode::Branch {
+                            first_child: first_child as u32,
+                        }
+                    }
+                };
+            }
+            level = next;
+        }
+        self.nodes = Arc::new(nodes);
+    }
+
+    /// Decides how the node spanning `size` voxels from `min` is stored.
+    fn classify<F, B>(&self, function: &F, bounds: &B, min: [usize; 3], size: usize) -> Classified
+    where
+        F: Fn(f32, f32, f32) -> Value,
+        B: Fn(Vec3, Vec3) -> (Value, Value),
+    {
+        let threshold = self.threshold;
+        let (low, high) = bounds(
+            self.position(min.map(|c| c as f32)),
+            self.position(min.map(|c| (c + size) as f32)),
+        );
+        if low > threshold || high < threshold {
+            return Classified::Node(OctreeNode::Empty {
+                inside: high < threshold,
+            });
+        }
+
+        let sample = |p: Vec3| function(p.x, p.y, p.z);
+        let corners = [0, 1, 2, 3, 4, 5, 6, 7]
+            .map(|i| sample(self.position(offset(min, i, size).map(|c| c as f32))));
+        let inside = corners.map(|v| v <= threshold);
+        let crossed = inside.iter().any(|&i| i != inside[0]);
+        if size == 1 && !crossed {
+            return Classified::Node(OctreeNode::Empty { inside: inside[0] });
+        }
+        if size == 1
+            || crossed
+                && self.max_error > 0.0
+                && is_manifold(inside)
+                && self.fits(&sample, min, size, &corners)
+                && self.faces_match(&sample, min, size, inside)
+        {
+            return Classified::Node(OctreeNode::Leaf(self.leaf(&sample, min, size, corners)));
+        }
+        Classified::Split
+    }
+
+    /// Returns `true` if `sample` stays within [`max_error`](VoxelOctree::max_error) of the
+    /// trilinear interpolation of `corners`, and on the same side of the threshold, at the
+    /// centres of the node's edges, faces and body.
+    fn fits(
+        &self,
+        sample: &impl Fn(Vec3) -> Value,
+        min: [usize; 3],
+        size: usize,
+        corners: &[Value; 8],
+    ) -> bool {
+        let threshold = self.threshold;
+        (0..27).all(|n| {
+            let steps = [n % 3, n / 3 % 3, n / 9];
+            if steps.iter().all(|&s| s != 1) {
+                return true;
+            }
+            let f = steps.map(|s| s as f32 * 0.5);
+            let expected = trilinear(corners, f);
+            let actual = sample(
+                self.position([0, 1, 2].map(|axis| min[axis] as f32 + f[axis] * size as f32)),
+            );
+            (actual - expected).abs() <= self.max_error
+                && (actual <= threshold) == (expected <= threshold)
+        })
+    }
+
+    /// Returns `true` if the surface crosses each of the node's faces, sampled at every
+    /// voxel, the way it crosses the face between its corners: not at all, or in one arc.
+    ///
+    /// Smaller neighbours sample the node's faces at their own resolution, so without this
+    /// they could see the surface cross a face where the leaf doesn't, or cross it twice, and
+    /// join their vertices to the leaf's in more than one sheet.
+    fn faces_match(
+        &self,
+        sample: &impl Fn(Vec3) -> Value,
+        min: [usize; 3],
+        size: usize,
+        inside: [bool; 8],
+    ) -> bool {
+        let n = size + 1;
+        (0..3).all(|axis| {
+            let (b, c) = ((axis + 1) % 3, (axis + 2) % 3);
+            [0, 1].into_iter().all(|side| {
+                let signs: Vec<bool> = (0..n * n)
+                    .map(|i| {
+                        let mut local = [0; 3];
+                        local[axis] = side * size;
+                        local[b] = i % n;
+                        local[c] = i / n;
+                        let p = [0, 1, 2].map(|k| (min[k] + local[k]) as f32);
+                        sample(self.position(p)) <= self.threshold
+                    })
+                    .collect();
+                let crossed = (0..8)
+                    .filter(|corner| corner >> axis & 1 == side)
+                    .any(|corner| inside[corner] != inside[side << axis]);
+                face_regions(&signs, n) == Some(1 + crossed as usize)
+            })
+        })
+    }
+
+    /// Builds the leaf spanning `size` voxels from `min`, placing its vertex with a [`Qef`]
+    /// over the crossings on its edges.
+    fn leaf(
+        &self,
+        sample: &impl Fn(Vec3) -> Value,
+        min: [usize; 3],
+        size: usize,
+        corners: [Value; 8],
+    ) -> OctreeLeaf {
+        let h = self.scale * 1e-3;
+        let gradient = |p: Vec3| {
+            normalize_or_zero(
+                [Vec3::X, Vec3::Y, Vec3::Z].map(|axis| sample(p + axis * h) - sample(p - axis * h)),
+            )
+        };
+        // Positions are in node units from its minimum corner, which keeps the QEF well
+        // conditioned at any size.
+        let origin = self.position(min.map(|c| c as f32));
+        let world = |local: Vec3| origin + local * size as f32 * self.scale;
+        let corner = |i: usize| offset([0; 3], i, 1).map(|c| c as f32);
+
+        let mut qef = Qef::default();
+        for a in 0..8 {
+            for axis in 0..3 {
+                let b = a | 1 << axis;
+                if a == b || (corners[a] <= self.threshold) == (corners[b] <= self.threshold) {
+                    continue;
+                }
+                let t = find_t(corners[a], corners[b], self.threshold);
+                let crossing = Vec3::from(interpolate_points(corner(a), corner(b), t));
+                qef.add(crossing, Vec3::from(gradient(world(crossing))));
+            }
+        }
+        let vertex = world(qef.solve().clamp(Vec3::ZERO, Vec3::ONE));
+        OctreeLeaf {
+            corners,
+            vertex: vertex.to_array(),
+            normal: gradient(vertex),
+        }
+    }
+
+    /// Returns the position of the lattice point `p`, in voxels.
+    fn position(&self, p: [f32; 3]) -> Vec3 {
+        Vec3::from(p) * self.scale
+    }
+
+    /// Meshes the octree with adaptive dual contouring; see [`VoxelOctree`].
+    ///
+    /// Cells, faces and edges are visited recursively from the root, so every edge is met
+    /// once at the size of the smallest leaf along it. Where the surface crosses it, the
+    /// vertices of the leaves around the edge are joined into a quad, split along its
+    /// shorter diagonal, or a triangle when one leaf borders the edge on two sides.
+    pub fn mesh(&self, normal_mode: NormalMode) -> GeneratedMesh {
+        let mut contour = Contour {
+            nodes: &self.nodes,
+            threshold: self.threshold,
+            node_vertices: vec![NO_VERTEX; self.nodes.len()],
+            vertices: Vec::new(),
+            normals: Vec::new(),
+            indices: Vec::new(),
+        };
+        contour.cell(NodeRef { index: 0, depth: 0 });
+        if normal_mode != NormalMode::Gradient {
+            contour.normals.clear();
+        }
+        GeneratedMesh::build(
+            contour.vertices,
+            contour.indices,
+            contour.normals,
+            Vec::new(),
+            Vec::new(),
+            normal_mode,
+        )
+    }
+}
+
+/// A node being contoured, with its depth below the root.
+#[derive(Clone, Copy)]
+struct NodeRef {
+    index: usize,
+    depth: u32,
+}
+
+/// State of [`VoxelOctree::mesh`].
+struct Contour<'a> {
+    nodes: &'a [OctreeNode],
+    threshold: Value,
+    /// Node → index into `vertices`, assigned on first use.
+    node_vertices: Vec<u32>,
+    vertices: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    indices: Vec<u32>,
+}
+
+impl Contour<'_> {
+    /// Returns child `corner` of `node`, or `node` itself if it isn't split.
+    fn child(&self, node: NodeRef, corner: usize) -> NodeRef {
+        match self.nodes[node.index] {
+            OctreeNode::Branch { first_child } => NodeRef {
+                index: first_child as usize + corner,
+                depth: node.depth + 1,
+            },
+            _ => node,
+        }
+    }
+
+    fn is_branch(&self, node: NodeRef) -> bool {
+        matches!(self.nodes[node.index], OctreeNode::Branch { .. })
+    }
+
+    /// Contours the inside of `node`: its children, and the faces and edges between them.
+    fn cell(&mut self, node: NodeRef) {
+        if !self.is_branch(node) {
+            return;
+        }
+        for corner in 0..8 {
+            self.cell(self.child(node, corner));
+        }
+        for axis in 0..3 {
+            for corner in (0..8).filter(|c| c >> axis & 1 == 0) {
+                let pair = [corner, corner | 1 << axis].map(|c| self.child(node, c));
+                self.face(pair, axis);
+            }
+        }
+        for axis in 0..3 {
+            let (b, c) = ((axis + 1) % 3, (axis + 2) % 3);
+            for t in 0..2 {
+                let cells = AROUND.map(|[db, dc]| self.child(node, t << axis | db << b | dc << c));
+                self.edge(cells, axis);
+            }
+        }
+    }
+
+    /// Contours the face between `pair`, the nodes below and above it along `axis`.
+    fn face(&mut self, pair: [NodeRef; 2], axis: usize) {
+        if !pair.iter().any(|&node| self.is_branch(node)) {
+            return;
+        }
+        let bit = 1 << axis;
+        for corner in (0..8).filter(|c| c & bit == 0) {
+            let children = [
+                self.child(pair[0], corner | bit),
+                self.child(pair[1], corner),
+            ];
+            self.face(children, axis);
+        }
+        // Edges within the face, running along `edge_axis` across the middle of `across`.
+        for edge_axis in (0..3).filter(|&a| a != axis) {
+            let across = 3 - axis - edge_axis;
+            let (b, c) = ((edge_axis + 1) % 3, (edge_axis + 2) % 3);
+            for t in 0..2 {
+                let cells = AROUND.map(|[db, dc]| {
+                    let mut side = [0; 3];
+                    side[b] = db;
+                    side[c] = dc;
+                    let child = (1 - side[axis]) << axis | side[across] << across | t << edge_axis;
+                    self.child(pair[side[axis]], child)
+                });
+                self.edge(cells, edge_axis);
+            }
+        }
+    }
+
+    /// Contours the edge along `axis` between `cells`, ordered as in [`AROUND`].
+    fn edge(&mut self, cells: [NodeRef; 4], axis: usize) {
+        let (b, c) = ((axis + 1) % 3, (axis + 2) % 3);
+        if cells.iter().any(|&node| self.is_branch(node)) {
+            for t in 0..2 {
+                let halves = [0, 1, 2, 3].map(|i| {
+                    let [db, dc] = AROUND[i];
+                    self.child(cells[i], t << axis | (1 - db) << b | (1 - dc) << c)
+                });
+                self.edge(halves, axis);
+            }
+            return;
+        }
+
+        // The smallest leaf has the edge as one of its own, so its corners give the signs.
+        let smallest = (0..4).max_by_key(|&i| cells[i].depth).expect("four cells");
+        let OctreeNode::Leaf(leaf) = self.nodes[cells[smallest].index] else {
+            return;
+        };
+        let [db, dc] = AROUND[smallest];
+        let low = (1 - db) << b | (1 - dc) << c;
+        let inside = leaf.corners[low] <= self.threshold;
+        if (leaf.corners[low | 1 << axis] <= self.threshold) == inside {
+            return;
+        }
+
+        let mut polygon = Vec::with_capacity(4);
+        for node in cells {
+            let Some(vertex) = self.vertex(node) else {
+                debug_assert!(false, "crossed edge borders a leaf without a vertex");
+                return;
+            };
+            if polygon.last() != Some(&vertex) && polygon.first() != Some(&vertex) {
+                polygon.push(vertex);
+            }
+        }
+        // The surface faces away from the inside end of the edge.
+        if !inside {
+            polygon.reverse();
+        }
+        match polygon[..] {
+            [a, b, c] => self.indices.extend([a, b, c]),
+            [a, b, c, d] => self
+                .indices
+                .extend(split_quad([a, b, c, d], &self.vertices)),
+            _ => {}
+        }
+    }
+
+    /// Returns the vertex of `node`, adding it on first use, or `None` if it has none.
+    fn vertex(&mut self, node: NodeRef) -> Option<u32> {
+        let OctreeNode::Leaf(leaf) = self.nodes[node.index] else {
+            return None;
+        };
+        if self.node_vertices[node.index] == NO_VERTEX {
+            self.node_vertices[node.index] = self.vertices.len() as u32;
+            self.vertices.push(leaf.vertex);
+            self.normals.push(leaf.normal);
+        }
+        Some(self.node_vertices[node.index])
+    }
+}
+
+/// Returns the lattice point `corner` of a node spanning `size` voxels from `min`, with
+/// corners numbered `x | y << 1 | z << 2`.
+#[inline]
+fn offset(min: [usize; 3], corner: usize, size: usize) -> [usize; 3] {
+    [0, 1, 2].map(|axis| min[axis] + (corner >> axis & 1) * size)
+}
+
+/// Returns `true` if the surface crosses a node with these corner signs, numbered
+/// `x | y << 1 | z << 2`, as a single sheet: the inside corners are connected along the
+/// node's edges, and so are the outside ones.
+///
+/// A leaf has a single vertex, so leaves whose surface is split into several sheets would
+/// join them at that vertex. This is the manifold sign test of Ju et al.'s dual contouring;
+/// [`VoxelOctree::faces_match`] checks that the finer signs on the node's faces agree.
+fn is_manifold(inside: [bool; 8]) -> bool {
+    let connected = |side: bool| {
+        let Some(start) = (0..8).find(|&i| inside[i] == side) else {
+            return true;
+        };
+        let mut reached = 1u8 << start;
+        let mut stack = vec![start];
+        while let Some(corner) = stack.pop() {
+            for axis in 0..3 {
+                let next = corner ^ 1 << axis;
+                if inside[next] == side && reached & 1 << next == 0 {
+                    reached |= 1 << next;
+                    stack.push(next);
+                }
+            }
+        }
+        (0..8).all(|i| inside[i] != side || reached & 1 << i != 0)
+    };
+    connected(true) && connected(false)
+}
+
+/// Returns the number of regions of an `n` × `n` grid of signs, joining neighbours along
+/// the grid's rows and columns, or `None` if a region doesn't reach the grid's border.
+fn face_regions(signs: &[bool], n: usize) -> Option<usize> {
+    let mut reached = vec![false; n * n];
+    let mut regions = 0;
+    let mut stack = Vec::new();
+    let border = (0..n * n).filter(|&i| {
+        let (u, v) = (i % n, i / n);
+        u == 0 || v == 0 || u == n - 1 || v == n - 1
+    });
+    for start in border {
+        if reached[start] {
+            continue;
+        }
+        regions += 1;
+        reached[start] = true;
+        stack.push(start);
+        while let Some(i) = stack.pop() {
+            let (u, v) = (i % n, i / n);
+            let neighbours = [
+                (u > 0).then(|| i - 1),
+                (u + 1 < n).then(|| i + 1),
+                (v > 0).then(|| i - n),
+                (v + 1 < n).then(|| i + n),
+            ];
+            for j in neighbours.into_iter().flatten() {
+                if !reached[j] && signs[j] == signs[i] {
+                    reached[j] = true;
+                    stack.push(j);
+                }
+            }
+        }
+    }
+    reached.iter().all(|&r| r).then_some(regions)
+}
+
+/// Returns the trilinear interpolation of `corners`, numbered `x | y << 1 | z << 2`, at `f`
+/// within the cell.
+fn trilinear(corners: &[Value; 8], f: [f32; 3]) -> Value {
+    (0..8)
+        .map(|i| {
+            let weight: f32 = (0..3)
+                .map(|axis| {
+                    if i >> axis & 1 == 1 {
+                        f[axis]
+                    } else {
+                        1.0 - f[axis]
+                    }
+                })
+                .product();
+            corners[i] * weight
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::sdf;
+
+    /// Returns the directed edges of `mesh` that aren't matched by exactly one reversed twin.
+    fn unmatched_edges(mesh: &GeneratedMesh) -> usize {
+        let mut edges: HashMap<(u32, u32), u32> = HashMap::new();
+        for triangle in mesh.indices.chunks_exact(3) {
+            for k in 0..3 {
+                *edges
+                    .entry((triangle[k], triangle[(k + 1) % 3]))
+                    .or_default() += 1;
+            }
+        }
+        edges
+            .iter()
+            .filter(|&(&(a, b), &uses)| uses != 1 || edges.get(&(b, a)) != Some(&1))
+            .count()
+    }
+
+    /// Fills a depth-6 octree with `shape` at each error bound and checks its mesh.
+    fn assert_manifold(shape: impl sdf::Sdf) {
+        for max_error in [0.0, 0.05, 0.3, 0.5, 1.0, 2.0] {
+            let mut octree = VoxelOctree::new(6).with_max_error(max_error);
+            octree.fill(&shape, |min, max| {
+                sdf::lipschitz_bounds(&shape, min, max, 1.0)
+            });
+            let mesh = octree.mesh(NormalMode::AreaWeighted);
+            assert!(mesh.tri_count() > 0);
+            assert_eq!(unmatched_edges(&mesh), 0, "max_error {max_error}");
+        }
+    }
+
+    #[test]
+    fn collapsed_octree_meshes_are_manifold() {
+        assert_manifold(sdf::translate(sdf::torus(20.0, 8.0), Vec3::splat(32.0)));
+        assert_manifold(sdf::translate(
+            sdf::torus(18.2, 6.1),
+            Vec3::new(32.3, 31.8, 32.1),
+        ));
+        let centre = Vec3::new(32.26, 31.84, 32.2);
+        assert_manifold(sdf::smooth_union(
+            sdf::translate(sdf::sphere(10.0), centre - 9.0),
+            sdf::translate(sdf::sphere(8.6), centre + 8.0),
+            2.0,
+        ));
+    }
+}
@@ -1,4 +1,9 @@
-use std::sync::Arc;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    iter,
+    sync::Arc,
+};
 
 use bevy::{
     asset::RenderAssetUsages,
@@ -8,12 +13,35 @@ use bevy::{
 };
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
+#[cfg(feature = "serialize")]
+use crate::sdf_node::SdfNodeLoader;
+
 use crate::{
+    ambiguity::is_ambiguous,
+    brush::{Brush, apply_brushes},
     chunk::Chunk,
-    mesh::GeneratedMesh,
-    tables::{CORNER_POINT_INDICES, EDGE_TABLE},
-    types::Value,
-    utils::{get_corner_positions, get_edge_midpoints, get_state, triangle_verts_from_state},
+    dual_contouring::run_dual_contouring,
+    generator::{ChunkGenerator, ChunkRequest},
+    grid::VoxelGrid,
+    interp::{interpolate_colors, interpolate_points},
+    lod::{ChunkLods, coarse_levels, select_chunk_lods},
+    marching_tetrahedra::run_marching_tetrahedra,
+    mesh::{
+        ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_WEIGHTS, GeneratedMesh, MeshingAlgorithm,
+        NormalMode, RetainedMesh,
+    },
+    neighbours::{ChunkCoord, ChunkHalo, ChunkMap, ChunkPadding, Face, copy_shared_face},
+    octree::VoxelOctree,
+    sdf_node::{SdfNode, fill_from_sdf_assets},
+    simplify::Simplification,
+    surface_nets::run_surface_nets,
+    tables::{EDGE_ENDPOINTS, EDGE_TABLE},
+    transvoxel::{TransitionCells, TransitionSamples},
+    types::{MaterialId, Value},
+    utils::{
+        corner_gradient, edge_key, edge_t, get_corner_positions, get_edge_midpoints, get_state,
+        normalize_or_zero, triangle_edges_from_state,
+    },
 };
 
 /// System sets for the marching cubes pipeline.
@@ -39,18 +67,55 @@ pub enum MarchingCubesSet {
     Upload,
 }
 
-/// Marker component added to [`Chunk`] entities that are waiting to be processed.
+/// Marker component added to [`Chunk`] and [`VoxelOctree`] entities that are waiting to be
+/// processed.
 ///
-/// Removed automatically once the chunk's mesh has been generated and uploaded.
+/// Inserted automatically whenever a [`Chunk`] or [`VoxelOctree`] is added or changed, and
+/// removed once its mesh has been generated and uploaded. Insert it manually to force a re-mesh
+/// without touching the chunk's values.
 #[derive(Component)]
 pub struct QueuedChunk;
 
+/// Which side of the threshold an empty [`Chunk`] lies on.
+///
+/// Inserted by [`MarchingCubesPlugin`] instead of meshing a
+/// chunk whose every corner is on the same side of its threshold, such as open air or deep
+/// rock; see [`Chunk::empty_kind`]. Such chunks get no [`ComputeTask`], [`GeneratedMesh`]
+/// or [`Mesh3d`], and a chunk that had a mesh loses it. The component is removed again once
+/// the chunk next gets a mesh.
+///
+/// ```rust,ignore
+/// fn count_air(chunks: Query<&EmptyChunk>) -> usize {
+///     chunks.iter().filter(|empty| **empty == EmptyChunk::Outside).count()
+/// }
+/// ```
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EmptyChunk {
+    /// Every corner is above the threshold.
+    Outside,
+    /// Every corner is at or below the threshold.
+    Inside,
+}
+
 /// Holds the in-flight async compute task for a [`Chunk`].
 ///
 /// Inserted by [`MarchingCubesSet::Spawn`], removed once the task completes
 /// and [`GeneratedMesh`] has been inserted by [`MarchingCubesSet::Generate`].
+///
+/// If the chunk changes while a task is in flight, the component is removed and the
+/// dropped [`Task`] is cancelled, so only the newest version of the chunk is meshed.
+///
+/// Tasks spawned for a [`ChunkRequest`] also generate the [`Chunk`] itself, and skip
+/// meshing it if it turns out to be an [`EmptyChunk`]. With
+/// [`MarchingCubesConfig::lod_distances`] set, tasks for chunks also mesh their coarser
+/// levels of detail.
+#[derive(Component)]
+pub struct ComputeTask(Task<(Option<Chunk>, Option<GeneratedMesh>, Vec<GeneratedMesh>)>);
+
+/// Coarser levels of detail meshed alongside a [`GeneratedMesh`], finest first, waiting to
+/// be uploaded into [`ChunkLods`].
 #[derive(Component)]
-pub struct ComputeTask(Task<GeneratedMesh>);
+struct GeneratedLods(Vec<GeneratedMesh>);
 
 /// Runtime configuration for the marching cubes pipeline.
 ///
@@ -71,50 +136,232 @@ pub struct MarchingCubesConfig {
     /// Higher values load chunks faster but may cause frame hitches when many chunks
     /// are queued at once. Default: `4`.
     pub max_tasks_per_frame: usize,
+
+    /// How vertex normals are computed for chunks that don't set
+    /// [`Chunk::normal_mode`]. Default: [`NormalMode::AreaWeighted`].
+    pub normal_mode: NormalMode,
+
+    /// Which algorithm meshes chunks that don't set [`Chunk::meshing_algorithm`].
+    /// Default: [`MeshingAlgorithm::MarchingCubes`].
+    pub meshing_algorithm: MeshingAlgorithm,
+
+    /// Whether chunks with a [`ChunkCoord`] read one layer of samples from their
+    /// neighbours while meshing.
+    ///
+    /// Only affects [`NormalMode::Gradient`], which then uses central differences across
+    /// chunk boundaries so normals match exactly on both sides of a seam. Neighbours are
+    /// re-meshed when a chunk next to them changes. Default: `true`.
+    pub sample_neighbours: bool,
+
+    /// Fills the chunks of [`ChunkRequest`]s. Requests wait while this is `None`.
+    /// Default: `None`.
+    pub generator: Option<Arc<dyn ChunkGenerator>>,
+
+    /// World-space size of one texture tile for generated UVs, or `None` to skip UVs.
+    ///
+    /// When set, meshes get box-projected [`Mesh::ATTRIBUTE_UV_0`] and
+    /// [`Mesh::ATTRIBUTE_TANGENT`] so textured and normal-mapped materials work. Projection
+    /// uses the chunk's [`Transform`] translation at meshing time, so chunks are expected at
+    /// the root of the hierarchy, as with [`Brush`]es; see
+    /// [`GeneratedMesh::compute_triplanar_uvs`]. Default: `None`.
+    pub uv_tile_size: Option<Value>,
+
+    /// Decimates every mesh before it is inserted, or `None` to keep the full mesh.
+    ///
+    /// Runs [`GeneratedMesh::simplify`] on the async pool, so the [`GeneratedMesh`] seen in
+    /// [`MarchingCubesSet::Generate`] is already simplified. Chunk boundaries are kept, so
+    /// neighbouring chunks still line up. Default: `None`.
+    pub simplification: Option<Simplification>,
+
+    /// Camera distances, in world units, at which chunks switch to each coarser level of
+    /// detail, or empty to mesh chunks at full resolution only.
+    ///
+    /// Level `i + 1` samples every `2^(i + 1)`th corner and is shown from `lod_distances[i]`
+    /// onwards; see [`ChunkLods`]. Coarser levels are meshed without transition faces or
+    /// neighbour halos, so seams between chunks at different levels can show small cracks.
+    /// Default: empty.
+    pub lod_distances: Vec<Value>,
+
+    /// Worlds that keep the uploaded [`Mesh`] assets, for chunks that don't set
+    /// [`Chunk::asset_usage`].
+    ///
+    /// With only [`RenderAssetUsages::RENDER_WORLD`], vertex data is freed on the CPU once it
+    /// reaches the GPU. Add [`RenderAssetUsages::MAIN_WORLD`] to read or modify the assets
+    /// from gameplay code afterwards, at the cost of keeping both copies. Default:
+    /// [`RenderAssetUsages::RENDER_WORLD`].
+    pub asset_usage: RenderAssetUsages,
+
+    /// Whether meshed entities keep a [`RetainedMesh`] copy of their [`GeneratedMesh`] after
+    /// upload, for raycasts, picking or navigation. Default: `false`.
+    pub retain_mesh_data: bool,
 }
 
 impl Default for MarchingCubesConfig {
     fn default() -> Self {
         Self {
             max_tasks_per_frame: 4,
+            normal_mode: NormalMode::default(),
+            meshing_algorithm: MeshingAlgorithm::default(),
+            sample_neighbours: true,
+            generator: None,
+            uv_tile_size: None,
+            simplification: None,
+            lod_distances: Vec::new(),
+            asset_usage: RenderAssetUsages::RENDER_WORLD,
+            retain_mesh_data: false,
         }
     }
 }
 
 /// Bevy plugin that drives marching cubes mesh generation.
 ///
-/// When the `auto_queue` feature is enabled, any [`Chunk`] added to the world is
-/// automatically processed. Mesh generation runs on Bevy's `AsyncComputeTaskPool`
+/// When the `auto_queue` feature is enabled, any [`Chunk`] added to or changed in the world
+/// is automatically processed. Mesh generation runs on Bevy's `AsyncComputeTaskPool`
 /// so the main thread is never blocked:
 ///
 /// ```text
-/// Chunk added
-///   → QueuedChunk inserted          (on_chunk_add)
+/// Chunk added or changed
+///   → QueuedChunk inserted          (queue_changed_chunks, stale ComputeTask dropped)
 ///   → ComputeTask spawned           (MarchingCubesSet::Spawn)
 ///   → [async compute runs]
-///   → GeneratedMesh inserted        (MarchingCubesSet::Generate, once task completes)
+///   → GeneratedMesh inserted        (MarchingCubesSet::Generate, once task completes,
+///                                    simplified if MarchingCubesConfig::simplification)
 ///   → [your collider systems here]
-///   → Mesh3d inserted               (MarchingCubesSet::Upload)
+///   → Mesh3d inserted or swapped    (MarchingCubesSet::Upload)
 ///   → QueuedChunk + GeneratedMesh removed
+///                                   (kept as RetainedMesh if MarchingCubesConfig::retain_mesh_data)
 /// ```
+///
+/// Chunks entirely on one side of their threshold skip the task: they get an
+/// [`EmptyChunk`] instead of a mesh.
+///
+/// Re-meshing an already meshed chunk replaces the asset behind its existing [`Mesh3d`]
+/// handle, so the old mesh stays visible until the new one is ready.
+///
+/// Chunks that also carry a [`ChunkCoord`] are treated as part of one grid: before
+/// queueing, the faces they share with neighbours are synchronised so vertices along seams
+/// line up exactly.
+///
+/// With a [`ChunkGenerator`] set, entities can also spawn a [`ChunkRequest`] instead of a
+/// filled [`Chunk`]; filling and meshing then both happen on the async pool.
+///
+/// Entities with a [`VoxelOctree`] instead of a [`Chunk`] go through the same stages, meshed
+/// with adaptive dual contouring.
+///
+/// With [`MarchingCubesConfig::lod_distances`] set, chunks also get a [`ChunkLods`] with
+/// one mesh per level of detail, and their [`Mesh3d`] follows the nearest camera's
+/// distance. The [`GeneratedMesh`] seen between the stages is always the finest level.
+///
+/// Before [`MarchingCubesSet::Spawn`], chunks with an [`SdfFill`](crate::SdfFill) are
+/// filled from their [`SdfNode`] asset, then [`Brush`] messages are applied to the chunks
+/// they overlap.
 pub struct MarchingCubesPlugin {
     /// Initial value for [`MarchingCubesConfig::max_tasks_per_frame`].
     pub max_tasks_per_frame: usize,
+    /// Initial value for [`MarchingCubesConfig::normal_mode`].
+    pub normal_mode: NormalMode,
+    /// Initial value for [`MarchingCubesConfig::meshing_algorithm`].
+    pub meshing_algorithm: MeshingAlgorithm,
+    /// Initial value for [`MarchingCubesConfig::sample_neighbours`].
+    pub sample_neighbours: bool,
+    /// Initial value for [`MarchingCubesConfig::generator`].
+    pub generator: Option<Arc<dyn ChunkGenerator>>,
+    /// Initial value for [`MarchingCubesConfig::uv_tile_size`].
+    pub uv_tile_size: Option<Value>,
+    /// Initial value for [`MarchingCubesConfig::simplification`].
+    pub simplification: Option<Simplification>,
+    /// Initial value for [`MarchingCubesConfig::lod_distances`].
+    pub lod_distances: Vec<Value>,
+    /// Initial value for [`MarchingCubesConfig::asset_usage`].
+    pub asset_usage: RenderAssetUsages,
+    /// Initial value for [`MarchingCubesConfig::retain_mesh_data`].
+    pub retain_mesh_data: bool,
 }
 
 impl Default for MarchingCubesPlugin {
     fn default() -> Self {
+        let config = MarchingCubesConfig::default();
         Self {
-            max_tasks_per_frame: MarchingCubesConfig::default().max_tasks_per_frame,
+            max_tasks_per_frame: config.max_tasks_per_frame,
+            normal_mode: config.normal_mode,
+            meshing_algorithm: config.meshing_algorithm,
+            sample_neighbours: config.sample_neighbours,
+            generator: config.generator,
+            uv_tile_size: config.uv_tile_size,
+            simplification: config.simplification,
+            lod_distances: config.lod_distances,
+            asset_usage: config.asset_usage,
+            retain_mesh_data: config.retain_mesh_data,
         }
     }
 }
 
+impl MarchingCubesPlugin {
+    /// Sets the [`ChunkGenerator`] used to fill [`ChunkRequest`]s.
+    pub fn with_generator(mut self, generator: impl ChunkGenerator) -> Self {
+        self.generator = Some(Arc::new(generator));
+        self
+    }
+
+    /// Generates box-projected UVs and tangents, with one texture tile every `tile_size`
+    /// world units.
+    pub fn with_uvs(mut self, tile_size: Value) -> Self {
+        self.uv_tile_size = Some(tile_size);
+        self
+    }
+
+    /// Decimates every mesh with quadric edge collapse; see [`GeneratedMesh::simplify`].
+    pub fn with_simplification(mut self, simplification: Simplification) -> Self {
+        self.simplification = Some(simplification);
+        self
+    }
+
+    /// Meshes chunks at coarser levels of detail too, switching to level `i + 1` at
+    /// `distances[i]` world units from the nearest camera.
+    pub fn with_lods(mut self, distances: Vec<Value>) -> Self {
+        self.lod_distances = distances;
+        self
+    }
+
+    /// Sets which worlds keep the uploaded mesh assets.
+    pub fn with_asset_usage(mut self, asset_usage: RenderAssetUsages) -> Self {
+        self.asset_usage = asset_usage;
+        self
+    }
+
+    /// Keeps a [`RetainedMesh`] copy of every uploaded mesh in the main world.
+    pub fn with_retained_meshes(mut self) -> Self {
+        self.retain_mesh_data = true;
+        self
+    }
+}
+
 impl Plugin for MarchingCubesPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(MarchingCubesConfig {
             max_tasks_per_frame: self.max_tasks_per_frame,
-        });
+            normal_mode: self.normal_mode,
+            meshing_algorithm: self.meshing_algorithm,
+            sample_neighbours: self.sample_neighbours,
+            generator: self.generator.clone(),
+            uv_tile_size: self.uv_tile_size,
+            simplification: self.simplification,
+            lod_distances: self.lod_distances.clone(),
+            asset_usage: self.asset_usage,
+            retain_mesh_data: self.retain_mesh_data,
+        })
+        .init_resource::<ChunkMap>()
+        .init_asset::<SdfNode>()
+        .add_message::<Brush>()
+        .add_systems(
+            Update,
+            (fill_from_sdf_assets, apply_brushes)
+                .chain()
+                .before(MarchingCubesSet::Spawn),
+        );
+
+        #[cfg(feature = "serialize")]
+        app.init_asset_loader::<SdfNodeLoader>();
 
         #[cfg(feature = "auto_queue")]
         app.configure_sets(
@@ -129,155 +376,1074 @@ impl Plugin for MarchingCubesPlugin {
         .add_systems(
             Update,
             (
-                on_chunk_add,
-                spawn_mesh_tasks.in_set(MarchingCubesSet::Spawn),
+                (
+                    sync_chunk_seams,
+                    queue_changed_chunks,
+                    queue_changed_octrees,
+                    spawn_mesh_tasks,
+                )
+                    .chain()
+                    .in_set(MarchingCubesSet::Spawn),
                 poll_mesh_tasks.in_set(MarchingCubesSet::Generate),
-                upload_mesh.in_set(MarchingCubesSet::Upload),
+                (upload_mesh, select_chunk_lods)
+                    .chain()
+                    .in_set(MarchingCubesSet::Upload),
             ),
         );
     }
 }
 
-/// Inserts [`QueuedChunk`] on every newly added [`Chunk`] that doesn't already have it.
-fn on_chunk_add(
+/// Inserts [`QueuedChunk`] on every [`Chunk`] that was added or modified since the last run.
+///
+/// Any in-flight [`ComputeTask`] or not-yet-uploaded [`GeneratedMesh`] belongs to an older
+/// version of the chunk, so both are removed. Dropping the task cancels it.
+///
+/// Chunks that were just generated from a [`ChunkRequest`] were meshed by the same task, so
+/// the request is removed instead. They are only re-queued if their gradient normals should
+/// have sampled neighbours that weren't available off-thread, or if a
+/// [dual](MeshingAlgorithm::is_dual) algorithm should have read the neighbours past their
+/// positive faces.
+fn queue_changed_chunks(
     mut commands: Commands,
-    query: Query<Entity, (Added<Chunk>, Without<QueuedChunk>)>,
+    config: Res<MarchingCubesConfig>,
+    map: Res<ChunkMap>,
+    query: Query<(Entity, &Chunk, Option<&ChunkCoord>, Has<ChunkRequest>), Changed<Chunk>>,
 ) {
+    for (entity, chunk, coord, requested) in query.iter() {
+        if !requested {
+            requeue(&mut commands, entity);
+            continue;
+        }
+
+        commands.entity(entity).remove::<ChunkRequest>();
+        let normal_mode = chunk.normal_mode.unwrap_or(config.normal_mode);
+        let has_neighbours = coord.is_some_and(|coord| {
+            Face::ALL
+                .into_iter()
+                .any(|face| map.neighbour(**coord, face).is_some())
+        });
+        let algorithm = chunk.meshing_algorithm.unwrap_or(config.meshing_algorithm);
+        let has_padding = coord.is_some_and(|coord| {
+            ChunkPadding::OFFSETS
+                .into_iter()
+                .any(|offset| map.contains(**coord + offset))
+        });
+        if (config.sample_neighbours && normal_mode == NormalMode::Gradient && has_neighbours)
+            || (algorithm.is_dual() && has_padding)
+        {
+            requeue(&mut commands, entity);
+        }
+    }
+}
+
+/// Inserts [`QueuedChunk`] on every [`VoxelOctree`] that was added or modified since the
+/// last run, dropping any work in flight for an older version of it.
+fn queue_changed_octrees(mut commands: Commands, query: Query<Entity, Changed<VoxelOctree>>) {
     for entity in query.iter() {
-        commands.entity(entity).insert(QueuedChunk);
+        requeue(&mut commands, entity);
+    }
+}
+
+/// Queues `entity` for meshing, dropping any work in flight for an older version of it.
+fn requeue(commands: &mut Commands, entity: Entity) {
+    commands
+        .entity(entity)
+        .insert(QueuedChunk)
+        .remove::<(ComputeTask, GeneratedMesh, GeneratedLods)>();
+}
+
+/// Keeps the faces shared by neighbouring [`ChunkCoord`] chunks identical.
+///
+/// For every changed chunk, each face it shares with a neighbour of the same resolution is
+/// copied from the changed chunk into the neighbour, so both produce the same vertices along
+/// the seam and edits on any face carry over. If both chunks changed in the same frame, the
+/// chunk on the negative side of the face owns it. Faces between chunks of different
+/// resolution are always copied from the finer chunk. A chunk is only marked as changed when
+/// its face actually differed.
+///
+/// With [`MarchingCubesConfig::sample_neighbours`], neighbours using
+/// [`NormalMode::Gradient`] are re-queued as well since their halo came from this chunk, as
+/// are coarser neighbours with a transition face towards it. So are the neighbours on the
+/// negative side, edges and corner using a [dual](MeshingAlgorithm::is_dual) algorithm,
+/// whose [`ChunkPadding`] came from this chunk.
+fn sync_chunk_seams(
+    mut commands: Commands,
+    config: Res<MarchingCubesConfig>,
+    map: Res<ChunkMap>,
+    mut chunks: ParamSet<(
+        Query<(Entity, &ChunkCoord), Changed<Chunk>>,
+        Query<&mut Chunk>,
+    )>,
+) {
+    let changed: Vec<(Entity, ChunkCoord)> =
+        chunks.p0().iter().map(|(e, coord)| (e, *coord)).collect();
+    let changed_entities: HashSet<Entity> = changed.iter().map(|(e, _)| *e).collect();
+    let mut chunks = chunks.p1();
+
+    for (entity, coord) in changed {
+        for face in Face::ALL {
+            let Some(neighbour) = map.neighbour(*coord, face) else {
+                continue;
+            };
+            let Ok([mut this, mut other]) = chunks.get_many_mut([entity, neighbour]) else {
+                continue;
+            };
+
+            // A finer chunk owns the face it shares with a coarser one, since faces can only
+            // be downsampled. Between chunks of the same resolution the changed one owns it,
+            // or the one on the negative side if both changed.
+            let this_owns = match this.values().len().cmp(&other.values().len()) {
+                Ordering::Equal if !changed_entities.contains(&neighbour) => true,
+                Ordering::Equal => face.is_positive(),
+                order => order == Ordering::Greater,
+            };
+            // Re-queue explicitly: a chunk fresh from a ChunkRequest isn't re-queued just
+            // for changing.
+            if this_owns {
+                if copy_shared_face(&this, &mut other, face) {
+                    requeue(&mut commands, neighbour);
+                }
+            } else if copy_shared_face(&other, &mut this, face.opposite()) {
+                requeue(&mut commands, entity);
+            }
+
+            // The neighbour's halo or transition samples came from this chunk.
+            let other_mode = other.normal_mode.unwrap_or(config.normal_mode);
+            if (config.sample_neighbours && other_mode == NormalMode::Gradient)
+                || other.transition_faces.contains(face.opposite())
+            {
+                requeue(&mut commands, neighbour);
+            }
+        }
+
+        // Dual meshers on the negative side read this chunk as padding.
+        for offset in ChunkPadding::OFFSETS {
+            let Some(neighbour) = map.get(*coord - offset) else {
+                continue;
+            };
+            let dual = chunks.get(neighbour).is_ok_and(|other| {
+                other
+                    .meshing_algorithm
+                    .unwrap_or(config.meshing_algorithm)
+                    .is_dual()
+            });
+            if dual {
+                requeue(&mut commands, neighbour);
+            }
+        }
     }
 }
 
-/// Spawns async compute tasks for [`QueuedChunk`]s, up to [`MarchingCubesConfig::max_tasks_per_frame`] per frame.
+/// Spawns async compute tasks for [`QueuedChunk`]s, queued [`VoxelOctree`]s and
+/// [`ChunkRequest`]s, up to [`MarchingCubesConfig::max_tasks_per_frame`] per frame.
+///
+/// Queued chunks and octrees go first so edits to loaded chunks aren't held up by generation. Queued
+/// chunks that are [empty](Chunk::empty_kind) are marked with [`EmptyChunk`] straight away
+/// and don't count towards the limit, unless a transition face could still carry surface
+/// from the finer neighbour.
 fn spawn_mesh_tasks(
     mut commands: Commands,
     config: Res<MarchingCubesConfig>,
-    query: Query<(Entity, &Chunk), (With<QueuedChunk>, Without<ComputeTask>, Without<Mesh3d>)>,
+    map: Res<ChunkMap>,
+    query: Query<
+        (Entity, &Chunk, Option<&ChunkCoord>, Option<&Transform>),
+        (With<QueuedChunk>, Without<ComputeTask>),
+    >,
+    requests: Query<
+        (Entity, &ChunkRequest, Option<&Transform>),
+        (Without<Chunk>, Without<ComputeTask>),
+    >,
+    octrees: Query<
+        (Entity, &VoxelOctree, Option<&Transform>),
+        (With<QueuedChunk>, Without<Chunk>, Without<ComputeTask>),
+    >,
+    chunks: Query<&Chunk>,
 ) {
     let task_pool = AsyncComputeTaskPool::get();
+    let mut spawned = 0;
+
+    for (entity, chunk, coord, transform) in query.iter() {
+        if chunk.transition_faces.is_empty()
+            && let Some(empty) = chunk.empty_kind()
+        {
+            commands
+                .entity(entity)
+                .insert(empty)
+                .remove::<(QueuedChunk, Mesh3d, ChunkLods, RetainedMesh)>();
+            continue;
+        }
+        if spawned == config.max_tasks_per_frame {
+            break;
+        }
+        spawned += 1;
 
-    for (entity, chunk) in query.iter().take(config.max_tasks_per_frame) {
         // Arc::clone is a single pointer bump — no heap allocation on the main thread.
         let size_x = chunk.size_x;
         let size_y = chunk.size_y;
         let size_z = chunk.size_z;
         let scale = chunk.scale;
         let threshold = chunk.threshold;
-        let values: Arc<Vec<Vec<Vec<Value>>>> = Arc::clone(&chunk.values);
+        let normal_mode = chunk.normal_mode.unwrap_or(config.normal_mode);
+        let algorithm = chunk.meshing_algorithm.unwrap_or(config.meshing_algorithm);
+        let values: Arc<VoxelGrid> = Arc::clone(chunk.values());
+        let materials = chunk.materials.clone();
+        let colors = chunk.colors.clone();
+        let normals = chunk.normals.clone();
+        let uv_tile_size = config.uv_tile_size;
+        let simplification = config.simplification;
+        let origin = transform.map_or(Vec3::ZERO, |t| t.translation);
+
+        // Only gradient normals look past the chunk boundary; copying one layer per face
+        // is cheap compared to meshing.
+        let halo = match coord {
+            Some(coord)
+                if !algorithm.is_dual()
+                    && config.sample_neighbours
+                    && normal_mode == NormalMode::Gradient =>
+            {
+                ChunkHalo::gather(chunk, **coord, &map, |e| chunks.get(e).ok())
+            }
+            _ => ChunkHalo::default(),
+        };
+        let transitions = match coord {
+            Some(coord)
+                if algorithm.supports_transitions() && !chunk.transition_faces.is_empty() =>
+            {
+                TransitionSamples::gather(chunk, **coord, &map, |e| chunks.get(e).ok())
+            }
+            _ => TransitionSamples::default(),
+        };
+        // Dual meshers need the cells straddling the positive faces.
+        let padding = match coord {
+            Some(coord) if algorithm.is_dual() => {
+                ChunkPadding::gather(chunk, **coord, &map, |e| chunks.get(e).ok())
+            }
+            _ => ChunkPadding::default(),
+        };
+        let levels = coarse_levels([size_x, size_y, size_z], config.lod_distances.len());
+        let lod_padding: Vec<ChunkPadding> = (1..=levels)
+            .map(|level| match coord {
+                Some(coord) if algorithm.is_dual() => ChunkPadding::gather_downsampled(
+                    chunk,
+                    **coord,
+                    &map,
+                    |e| chunks.get(e).ok(),
+                    1 << level,
+                ),
+                _ => ChunkPadding::default(),
+            })
+            .collect();
+
+        let task = task_pool.spawn(async move {
+            let mut mesh = run_meshing(
+                algorithm,
+                size_x,
+                size_y,
+                size_z,
+                scale,
+                threshold,
+                normal_mode,
+                &values,
+                materials.as_deref(),
+                colors.as_deref(),
+                normals.as_deref(),
+                &halo,
+                &transitions,
+                &padding,
+            );
+            let mut lods = run_lod_meshing(
+                algorithm,
+                [size_x, size_y, size_z],
+                scale,
+                threshold,
+                normal_mode,
+                &values,
+                materials.as_deref(),
+                colors.as_deref(),
+                normals.as_deref(),
+                &lod_padding,
+            );
+            post_process(
+                iter::once(&mut mesh).chain(&mut lods),
+                simplification.as_ref(),
+                uv_tile_size,
+                origin,
+                normal_mode,
+            );
+            (None, Some(mesh), lods)
+        });
+
+        commands.entity(entity).insert(ComputeTask(task));
+    }
+
+    for (entity, octree, transform) in octrees.iter() {
+        if spawned == config.max_tasks_per_frame {
+            break;
+        }
+        spawned += 1;
+
+        // Cloning shares the nodes behind an Arc.
+        let octree = octree.clone();
+        let normal_mode = octree.normal_mode.unwrap_or(config.normal_mode);
+        let uv_tile_size = config.uv_tile_size;
+        let simplification = config.simplification;
+        let origin = transform.map_or(Vec3::ZERO, |t| t.translation);
 
         let task = task_pool.spawn(async move {
-            run_marching_cubes(size_x, size_y, size_z, scale, threshold, &values)
+            let mut mesh = octree.mesh(normal_mode);
+            post_process(
+                iter::once(&mut mesh),
+                simplification.as_ref(),
+                uv_tile_size,
+                origin,
+                normal_mode,
+            );
+            (None, Some(mesh), Vec::new())
         });
 
         commands.entity(entity).insert(ComputeTask(task));
     }
+
+    let Some(generator) = &config.generator else {
+        return;
+    };
+
+    let budget = config.max_tasks_per_frame.saturating_sub(spawned);
+    for (entity, request, transform) in requests.iter().take(budget) {
+        let request = *request;
+        let generator = Arc::clone(generator);
+        let normal_mode = config.normal_mode;
+        let meshing_algorithm = config.meshing_algorithm;
+        let lod_count = config.lod_distances.len();
+        let uv_tile_size = config.uv_tile_size;
+        let simplification = config.simplification;
+        let origin = transform.map_or(Vec3::ZERO, |t| t.translation);
+
+        let task = task_pool.spawn(async move {
+            let chunk = request.generate(generator.as_ref());
+            if chunk.empty_kind().is_some() {
+                return (Some(chunk), None, Vec::new());
+            }
+            let normal_mode = chunk.normal_mode.unwrap_or(normal_mode);
+            let algorithm = chunk.meshing_algorithm.unwrap_or(meshing_algorithm);
+            let size = [chunk.size_x, chunk.size_y, chunk.size_z];
+            let mut mesh = run_meshing(
+                algorithm,
+                chunk.size_x,
+                chunk.size_y,
+                chunk.size_z,
+                chunk.scale,
+                chunk.threshold,
+                normal_mode,
+                chunk.values(),
+                chunk.materials.as_deref(),
+                chunk.colors.as_deref(),
+                chunk.normals.as_deref(),
+                &ChunkHalo::default(),
+                &TransitionSamples::default(),
+                &ChunkPadding::default(),
+            );
+            let lod_padding = vec![ChunkPadding::default(); coarse_levels(size, lod_count)];
+            let mut lods = run_lod_meshing(
+                algorithm,
+                size,
+                chunk.scale,
+                chunk.threshold,
+                normal_mode,
+                chunk.values(),
+                chunk.materials.as_deref(),
+                chunk.colors.as_deref(),
+                chunk.normals.as_deref(),
+                &lod_padding,
+            );
+            post_process(
+                iter::once(&mut mesh).chain(&mut lods),
+                simplification.as_ref(),
+                uv_tile_size,
+                origin,
+                normal_mode,
+            );
+            (Some(chunk), Some(mesh), lods)
+        });
+
+        commands
+            .entity(entity)
+            .insert((QueuedChunk, ComputeTask(task)));
+    }
 }
 
-/// Polls in-flight [`ComputeTask`]s each frame and inserts [`GeneratedMesh`] on completion.
+/// Polls in-flight [`ComputeTask`]s each frame and inserts [`GeneratedMesh`] on completion,
+/// along with the [`Chunk`] for tasks spawned from a [`ChunkRequest`] and the coarser levels
+/// of detail, if any.
+///
+/// Generated chunks that turned out empty get an [`EmptyChunk`] instead of a mesh.
 ///
 /// Non-blocking: tasks that haven't finished are skipped and retried next frame.
 fn poll_mesh_tasks(mut commands: Commands, mut query: Query<(Entity, &mut ComputeTask)>) {
     for (entity, mut compute_task) in query.iter_mut() {
-        if let Some(generated_mesh) = block_on(future::poll_once(&mut compute_task.0)) {
-            commands
-                .entity(entity)
-                .insert(generated_mesh)
-                .remove::<ComputeTask>();
+        if let Some((chunk, generated_mesh, lods)) =
+            block_on(future::poll_once(&mut compute_task.0))
+        {
+            let mut entity = commands.entity(entity);
+            entity.remove::<ComputeTask>();
+            if let Some(generated_mesh) = generated_mesh {
+                entity.insert(generated_mesh);
+                if !lods.is_empty() {
+                    entity.insert(GeneratedLods(lods));
+                }
+            } else if let Some(empty) = chunk.as_ref().and_then(Chunk::empty_kind) {
+                entity
+                    .insert(empty)
+                    .remove::<(QueuedChunk, Mesh3d, ChunkLods, RetainedMesh)>();
+            }
+            if let Some(chunk) = chunk {
+                entity.insert(chunk);
+            }
         }
     }
 }
 
-/// Uploads a [`GeneratedMesh`] into a Bevy [`Mesh3d`], then removes [`GeneratedMesh`] and [`QueuedChunk`].
+/// Uploads a [`GeneratedMesh`] into a Bevy [`Mesh3d`], then removes [`GeneratedMesh`],
+/// [`QueuedChunk`] and any [`EmptyChunk`].
 ///
-/// The three vertex data Vecs are **moved** directly into the Bevy mesh with no copies.
+/// If the chunk already has a [`Mesh3d`] (i.e. it is being re-meshed), the asset behind the
+/// existing handle is replaced in place instead of allocating a new handle.
+///
+/// Chunks meshed with levels of detail get every level uploaded into their [`ChunkLods`],
+/// reusing its handles the same way, and keep showing the level they were showing.
+///
+/// Assets are created with [`MarchingCubesConfig::asset_usage`], or the chunk's own
+/// [`Chunk::asset_usage`]. With [`MarchingCubesConfig::retain_mesh_data`], the
+/// [`GeneratedMesh`] is copied into a [`RetainedMesh`] before it is removed.
 fn upload_mesh(
     mut commands: Commands,
-    query: Query<(Entity, &GeneratedMesh), With<QueuedChunk>>,
+    config: Res<MarchingCubesConfig>,
+    query: Query<
+        (
+            Entity,
+            &GeneratedMesh,
+            Option<&GeneratedLods>,
+            Option<&Mesh3d>,
+            Option<&ChunkLods>,
+            Option<&Chunk>,
+        ),
+        With<QueuedChunk>,
+    >,
     mut meshes: ResMut<Assets<Mesh>>,
 ) {
-    for (entity, generated) in query.iter() {
-        let mut bevy_mesh = Mesh::new(
-            PrimitiveTopology::TriangleList,
-            RenderAssetUsages::RENDER_WORLD,
+    for (entity, generated, generated_lods, existing, lods, chunk) in query.iter() {
+        let usage = chunk
+            .and_then(|chunk| chunk.asset_usage)
+            .unwrap_or(config.asset_usage);
+        let mut entity = commands.entity(entity);
+        match generated_lods {
+            Some(GeneratedLods(coarse)) => {
+                // A chunk meshed without levels before keeps its handle for the finest one.
+                let mut handles = match lods {
+                    Some(lods) => lods.meshes.clone(),
+                    None => existing
+                        .map(|mesh3d| mesh3d.0.clone())
+                        .into_iter()
+                        .collect(),
+                };
+                handles.truncate(coarse.len() + 1);
+                for (level, generated) in iter::once(generated).chain(coarse).enumerate() {
+                    let bevy_mesh = to_bevy_mesh(generated, usage);
+                    match handles.get(level).and_then(|handle| meshes.get_mut(handle)) {
+                        Some(mesh) => *mesh = bevy_mesh,
+                        None if level < handles.len() => handles[level] = meshes.add(bevy_mesh),
+                        None => handles.push(meshes.add(bevy_mesh)),
+                    }
+                }
+                let current = lods.map_or(0, |lods| lods.current).min(coarse.len());
+                if existing.is_none_or(|mesh3d| mesh3d.0 != handles[current]) {
+                    entity.insert(Mesh3d(handles[current].clone()));
+                }
+                entity.insert(ChunkLods {
+                    meshes: handles,
+                    current,
+                });
+            }
+            None => {
+                let bevy_mesh = to_bevy_mesh(generated, usage);
+                match existing.and_then(|mesh3d| meshes.get_mut(&mesh3d.0)) {
+                    Some(mesh) => *mesh = bevy_mesh,
+                    None => {
+                        entity.insert(Mesh3d(meshes.add(bevy_mesh)));
+                    }
+                }
+                entity.remove::<ChunkLods>();
+            }
+        }
+
+        if config.retain_mesh_data {
+            entity.insert(RetainedMesh(generated.clone()));
+        } else {
+            entity.remove::<RetainedMesh>();
+        }
+        entity.remove::<(QueuedChunk, GeneratedMesh, GeneratedLods, EmptyChunk)>();
+    }
+}
+
+/// Copies a [`GeneratedMesh`] into a Bevy [`Mesh`] kept in the worlds given by `usage`.
+fn to_bevy_mesh(generated: &GeneratedMesh, usage: RenderAssetUsages) -> Mesh {
+    let mut bevy_mesh = Mesh::new(PrimitiveTopology::TriangleList, usage);
+
+    bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, generated.vertices.clone());
+    bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, generated.normals.clone());
+    if !generated.colors.is_empty() {
+        bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, generated.colors.clone());
+    }
+    if !generated.uvs.is_empty() {
+        bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, generated.uvs.clone());
+        bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_TANGENT, generated.tangents.clone());
+    }
+    if !generated.material_ids.is_empty() {
+        bevy_mesh.insert_attribute(ATTRIBUTE_MATERIAL_IDS, generated.material_ids.clone());
+        bevy_mesh.insert_attribute(
+            ATTRIBUTE_MATERIAL_WEIGHTS,
+            generated.material_weights.clone(),
         );
+    }
+    bevy_mesh.insert_indices(Indices::U32(generated.indices.clone()));
+    bevy_mesh
+}
 
-        bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, generated.vertices.clone());
-        bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, generated.normals.clone());
-        bevy_mesh.insert_indices(Indices::U32(generated.indices.clone()));
+/// Runs the optional post-processing stages on freshly generated `meshes`: simplification
+/// first, then box-projected UVs relative to `origin`.
+fn post_process<'a>(
+    meshes: impl Iterator<Item = &'a mut GeneratedMesh>,
+    simplification: Option<&Simplification>,
+    uv_tile_size: Option<f32>,
+    origin: Vec3,
+    normal_mode: NormalMode,
+) {
+    for mesh in meshes {
+        if let Some(simplification) = simplification {
+            mesh.simplify(simplification, normal_mode);
+        }
+        if let Some(tile_size) = uv_tile_size {
+            mesh.compute_triplanar_uvs(origin, tile_size);
+        }
+    }
+}
 
-        commands
-            .entity(entity)
-            .insert(Mesh3d(meshes.add(bevy_mesh)))
-            .remove::<QueuedChunk>();
+/// Meshes the coarser levels of detail of a chunk of `size` voxels, one per entry of
+/// `padding`, finest first.
+///
+/// Level `i + 1` [downsamples](VoxelGrid::downsample) every grid by `2^(i + 1)` and meshes
+/// it at that multiple of `scale`, reading `padding[i]` past the positive faces.
+fn run_lod_meshing(
+    algorithm: MeshingAlgorithm,
+    size: [usize; 3],
+    scale: Value,
+    threshold: Value,
+    normal_mode: NormalMode,
+    values: &VoxelGrid,
+    materials: Option<&VoxelGrid<MaterialId>>,
+    colors: Option<&VoxelGrid<[f32; 4]>>,
+    normals: Option<&VoxelGrid<[f32; 3]>>,
+    padding: &[ChunkPadding],
+) -> Vec<GeneratedMesh> {
+    padding
+        .iter()
+        .enumerate()
+        .map(|(i, padding)| {
+            let step = 1 << (i + 1);
+            let [size_x, size_y, size_z] = size.map(|s| s / step);
+            run_meshing(
+                algorithm,
+                size_x,
+                size_y,
+                size_z,
+                scale * step as Value,
+                threshold,
+                normal_mode,
+                &values.downsample(step),
+                materials.map(|m| m.downsample(step)).as_ref(),
+                colors.map(|c| c.downsample(step)).as_ref(),
+                normals.map(|n| n.downsample(step)).as_ref(),
+                &ChunkHalo::default(),
+                &TransitionSamples::default(),
+                padding,
+            )
+        })
+        .collect()
+}
+
+/// Meshes the given voxel grid with `algorithm`.
+///
+/// `halo` is only used by marching cubes and tetrahedra, `transitions` only by
+/// [algorithms that support them](MeshingAlgorithm::supports_transitions), `padding` only
+/// by the [dual](MeshingAlgorithm::is_dual) algorithms, and `normals` only by dual
+/// contouring.
+pub(crate) fn run_meshing(
+    algorithm: MeshingAlgorithm,
+    size_x: usize,
+    size_y: usize,
+    size_z: usize,
+    scale: Value,
+    threshold: Value,
+    normal_mode: NormalMode,
+    values: &VoxelGrid,
+    materials: Option<&VoxelGrid<MaterialId>>,
+    colors: Option<&VoxelGrid<[f32; 4]>>,
+    normals: Option<&VoxelGrid<[f32; 3]>>,
+    halo: &ChunkHalo,
+    transitions: &TransitionSamples,
+    padding: &ChunkPadding,
+) -> GeneratedMesh {
+    match algorithm {
+        MeshingAlgorithm::MarchingCubes | MeshingAlgorithm::MarchingCubes33 => run_marching_cubes(
+            size_x,
+            size_y,
+            size_z,
+            scale,
+            threshold,
+            normal_mode,
+            values,
+            materials,
+            colors,
+            halo,
+            transitions,
+            algorithm == MeshingAlgorithm::MarchingCubes33,
+        ),
+        MeshingAlgorithm::SurfaceNets => run_surface_nets(
+            size_x,
+            size_y,
+            size_z,
+            scale,
+            threshold,
+            normal_mode,
+            values,
+            materials,
+            colors,
+            padding,
+        ),
+        MeshingAlgorithm::DualContouring => run_dual_contouring(
+            size_x,
+            size_y,
+            size_z,
+            scale,
+            threshold,
+            normal_mode,
+            values,
+            materials,
+            colors,
+            normals,
+            padding,
+        ),
+        MeshingAlgorithm::MarchingTetrahedra => run_marching_tetrahedra(
+            size_x,
+            size_y,
+            size_z,
+            scale,
+            threshold,
+            normal_mode,
+            values,
+            materials,
+            colors,
+            halo,
+        ),
     }
 }
 
 /// Runs the marching cubes algorithm over the given voxel grid.
 ///
-/// Work is parallelised over X slices using Rayon. Returns a [`GeneratedMesh`]
-/// with vertices, sequential indices, and flat-shaded normals.
+/// Work is parallelised over X slices using Rayon. Returns an indexed [`GeneratedMesh`]
+/// in which every grid edge crossed by the surface owns exactly one vertex, shared by all
+/// triangles of the (up to four) voxels around that edge.
 ///
 /// ```text
 /// Per voxel:
 /// 1. get_corner_positions       →  8 world-space points
-/// 2. values[z][y][x] (×8)      →  8 scalar values
+/// 2. values.get(x, y, z) (×8)  →  8 scalar values
 /// 3. get_state                  →  256-entry lookup key
 /// 4. EDGE_TABLE[state]          →  bitmask of intersected edges
 /// 5. get_edge_midpoints         →  up to 12 interpolated points
-/// 6. triangle_verts_from_state  →  triangle vertices from TRI_TABLE
+/// 6. triangle_edges_from_state  →  edge indices from TRI_TABLE
+/// 7. edge_key                   →  look up / insert the shared vertex for each edge
 /// ```
+///
+/// Each slice welds its own vertices; slices are then merged in order and vertices on the
+/// shared X planes are welded a second time by the same edge key.
+///
+/// Gradient normals sample `halo` past the chunk boundary, falling back to one-sided
+/// differences where it has no data.
+///
+/// Voxels along faces with `transitions` samples are meshed as transition cells instead of
+/// through `TRI_TABLE`; see [`TransitionSamples`].
+///
+/// With `resolve_ambiguities`, [ambiguous](is_ambiguous) voxels are traced like transition
+/// cells, with ambiguous faces split by the asymptotic decider and a tube where the voxel's
+/// interior joins two loops; see [`MeshingAlgorithm::MarchingCubes33`].
+///
+/// With this crate's corner layout `TRI_TABLE` lists each triangle clockwise as seen from
+/// outside the surface, so triangles are emitted in reverse to match Bevy's
+/// counter-clockwise front faces and the outward-pointing field gradient.
 fn run_marching_cubes(
     size_x: usize,
     size_y: usize,
     size_z: usize,
     scale: Value,
     threshold: Value,
-    values: &Vec<Vec<Vec<Value>>>,
+    normal_mode: NormalMode,
+    values: &VoxelGrid,
+    materials: Option<&VoxelGrid<MaterialId>>,
+    colors: Option<&VoxelGrid<[f32; 4]>>,
+    halo: &ChunkHalo,
+    transitions: &TransitionSamples,
+    resolve_ambiguities: bool,
 ) -> GeneratedMesh {
-    let per_x: Vec<Vec<[f32; 3]>> = (0..size_x)
+    let (corners_x, corners_y) = (size_x + 1, size_y + 1);
+    let sample = |x: isize, y: isize, z: isize| halo.sample(values, x, y, z);
+    let cells = TransitionCells::new(values, transitions, threshold, resolve_ambiguities);
+
+    let per_x: Vec<SliceMesh> = (0..size_x)
         .into_par_iter()
         .map(|x| {
-            let mut local: Vec<[f32; 3]> = Vec::new();
-            let per_voxel_max = 15_usize; // upper bound of vertices per voxel
-            local.reserve(size_y * size_z * per_voxel_max);
+            let mut slice = SliceMesh::default();
 
             for y in 0..size_y {
                 for z in 0..size_z {
+                    let mask = if transitions.is_empty() {
+                        0
+                    } else {
+                        cells.cell_mask(x, y, z)
+                    };
+                    let traced = mask != 0
+                        || resolve_ambiguities
+                            && is_ambiguous(
+                                &voxel_corner_indices(x, y, z)
+                                    .map(|[cx, cy, cz]| values.get(cx, cy, cz)),
+                                threshold,
+                            );
+                    if traced {
+                        let loops = cells.loops(x, y, z, mask);
+                        let tunnel = if mask == 0 {
+                            cells.tunnel(x, y, z, &loops)
+                        } else {
+                            None
+                        };
+                        let rings: Vec<Vec<u32>> = loops
+                            .iter()
+                            .map(|ring| {
+                                ring.iter()
+                                    .map(|crossing| {
+                                        slice.vertex(crossing.key, || {
+                                            let normal = (normal_mode == NormalMode::Gradient)
+                                                .then(|| {
+                                                    normalize_or_zero(crossing.gradient(
+                                                        |[cx, cy, cz]| {
+                                                            corner_gradient(
+                                                                cx, cy, cz, scale, sample,
+                                                            )
+                                                        },
+                                                    ))
+                                                });
+                                            let color = colors.map(|c| {
+                                                crossing.color(|[cx, cy, cz]| c.get(cx, cy, cz))
+                                            });
+                                            let [mx, my, mz] = crossing.inside_corner;
+                                            let material = materials.map(|m| m.get(mx, my, mz));
+                                            SliceVertex {
+                                                position: crossing.position(scale),
+                                                normal,
+                                                color,
+                                                material,
+                                            }
+                                        })
+                                    })
+                                    .collect()
+                            })
+                            .collect();
+                        for (n, ids) in rings.iter().enumerate() {
+                            match tunnel {
+                                Some((a, b)) if n == a => {
+                                    let voxel_centre = [x, y, z].map(|c| (c as f32 + 0.5) * scale);
+                                    slice.tube(ids, &rings[b], voxel_centre, |i| {
+                                        cells.centre_key(x, y, z, rings.len() + i)
+                                    });
+                                    continue;
+                                }
+                                Some((_, b)) if n == b => continue,
+                                _ => {}
+                            }
+                            if let [a, b, c] = ids[..] {
+                                slice.indices.extend([a, b, c]);
+                                continue;
+                            }
+
+                            // Fanning from a loop vertex can fold triangles back across the
+                            // split face, so larger loops are fanned around their centre.
+                            let centre = slice.centre(ids);
+                            let centre = slice.vertex(cells.centre_key(x, y, z, n), || centre);
+                            for (i, &id) in ids.iter().enumerate() {
+                                slice.indices.extend([centre, id, ids[(i + 1) % ids.len()]]);
+                            }
+                        }
+                        continue;
+                    }
+
                     let corner_positions = get_corner_positions(x, y, z, scale);
 
                     let corner_indices = voxel_corner_indices(x, y, z);
                     let eval_corners: Vec<Value> = corner_indices
                         .iter()
-                        .map(|[cx, cy, cz]| values[*cz][*cy][*cx])
+                        .map(|&[cx, cy, cz]| values.get(cx, cy, cz))
                         .collect();
 
                     let state = get_state(&eval_corners, threshold).expect("Could not get state");
 
                     let edges_mask = EDGE_TABLE[state] as u16;
+                    if edges_mask == 0 {
+                        continue;
+                    }
 
                     let edge_points = get_edge_midpoints(
                         edges_mask,
-                        &CORNER_POINT_INDICES,
+                        &EDGE_ENDPOINTS,
                         &corner_positions,
                         &eval_corners,
                         threshold,
                     );
 
-                    local.extend(triangle_verts_from_state(edge_points, state));
+                    let edges: Vec<usize> = triangle_edges_from_state(state).collect();
+                    for tri in edges.chunks_exact(3) {
+                        for &edge in &[tri[0], tri[2], tri[1]] {
+                            let key = edge_key(x, y, z, edge, corners_x, corners_y);
+                            let index = slice.vertex(key, || {
+                                let position = edge_points[edge].expect("edge midpoint missing");
+                                let [a, b] = EDGE_ENDPOINTS[edge].map(|c| c as usize);
+                                let [ax, ay, az] = corner_indices[a];
+                                let [bx, by, bz] = corner_indices[b];
+                                let t = edge_t(eval_corners[a], eval_corners[b], threshold);
+                                let normal = (normal_mode == NormalMode::Gradient).then(|| {
+                                    let ga = corner_gradient(ax, ay, az, scale, sample);
+                                    let gb = corner_gradient(bx, by, bz, scale, sample);
+                                    normalize_or_zero(interpolate_points(ga, gb, t))
+                                });
+                                let color = colors.map(|c| {
+                                    interpolate_colors(c.get(ax, ay, az), c.get(bx, by, bz), t)
+                                });
+                                // Vertices take the material of the solid side of their edge.
+                                let material = materials.map(|m| {
+                                    let inside = if eval_corners[a] <= threshold { a } else { b };
+                                    let [cx, cy, cz] = corner_indices[inside];
+                                    m.get(cx, cy, cz)
+                                });
+                                SliceVertex {
+                                    position,
+                                    normal,
+                                    color,
+                                    material,
+                                }
+                            });
+                            slice.indices.push(index);
+                        }
+                    }
                 }
             }
-            local
+            slice
         })
         .collect();
 
-    // Merge per-X slices into a single vertex buffer
-    let total: usize = per_x.iter().map(|v| v.len()).sum();
-    let mut vertices: Vec<[f32; 3]> = Vec::with_capacity(total);
-    for mut v in per_x {
-        vertices.append(&mut v);
+    // Merge per-X slices into a single indexed buffer, welding vertices shared across slices
+    let total_vertices: usize = per_x.iter().map(|s| s.vertices.len()).sum();
+    let total_indices: usize = per_x.iter().map(|s| s.indices.len()).sum();
+    let mut vertices: Vec<[f32; 3]> = Vec::with_capacity(total_vertices);
+    let mut normals: Vec<[f32; 3]> = Vec::with_capacity(total_vertices);
+    let mut colors: Vec<[f32; 4]> = Vec::with_capacity(total_vertices);
+    let mut vertex_materials: Vec<MaterialId> = Vec::with_capacity(total_vertices);
+    let mut indices: Vec<u32> = Vec::with_capacity(total_indices);
+    let mut lookup: HashMap<usize, u32> = HashMap::with_capacity(total_vertices);
+
+    for slice in per_x {
+        let remap: Vec<u32> = slice
+            .keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                *lookup.entry(*key).or_insert_with(|| {
+                    vertices.push(slice.vertices[i]);
+                    if let Some(normal) = slice.normals.get(i) {
+                        normals.push(*normal);
+                    }
+                    if let Some(color) = slice.colors.get(i) {
+                        colors.push(*color);
+                    }
+                    if let Some(material) = slice.materials.get(i) {
+                        vertex_materials.push(*material);
+                    }
+                    (vertices.len() - 1) as u32
+                })
+            })
+            .collect();
+        indices.extend(slice.indices.iter().map(|&i| remap[i as usize]));
+    }
+
+    GeneratedMesh::build(
+        vertices,
+        indices,
+        normals,
+        colors,
+        vertex_materials,
+        normal_mode,
+    )
+}
+
+/// Welded vertex data for a single X slice of voxels, before slices are merged.
+#[derive(Default)]
+struct SliceMesh {
+    /// Edge key of each vertex in `vertices`.
+    keys: Vec<usize>,
+    vertices: Vec<[f32; 3]>,
+    /// Gradient normal of each vertex; empty unless using [`NormalMode::Gradient`].
+    normals: Vec<[f32; 3]>,
+    /// Colour of each vertex; empty unless the chunk has colours.
+    colors: Vec<[f32; 4]>,
+    /// Material of each vertex; empty unless the chunk has materials.
+    materials: Vec<MaterialId>,
+    /// Triangle indices into this slice's `vertices`.
+    indices: Vec<u32>,
+    /// Edge key → index into `vertices`.
+    lookup: HashMap<usize, u32>,
+}
+
+/// Attributes of a new [`SliceMesh`] vertex.
+struct SliceVertex {
+    position: [f32; 3],
+    /// Gradient normal; only set for [`NormalMode::Gradient`].
+    normal: Option<[f32; 3]>,
+    /// Interpolated colour; only set if the chunk has colours.
+    color: Option<[f32; 4]>,
+    /// Material; only set if the chunk has materials.
+    material: Option<MaterialId>,
+}
+
+impl SliceMesh {
+    /// Returns the index of the vertex for `key`, creating it with `make` on first use.
+    fn vertex(&mut self, key: usize, make: impl FnOnce() -> SliceVertex) -> u32 {
+        if let Some(&index) = self.lookup.get(&key) {
+            return index;
+        }
+        let vertex = make();
+        self.keys.push(key);
+        self.vertices.push(vertex.position);
+        self.normals.extend(vertex.normal);
+        self.colors.extend(vertex.color);
+        self.materials.extend(vertex.material);
+        let index = (self.vertices.len() - 1) as u32;
+        self.lookup.insert(key, index);
+        index
+    }
+
+    /// Joins the loops of vertices `a` and `b` with a tube through the voxel centred at
+    /// `voxel_centre`.
+    ///
+    /// The tube passes through a ring of new vertices, one per vertex of `a`, halfway between
+    /// the middle of that vertex and its nearest in `b` and the voxel centre. `ring_key`
+    /// gives their keys. Joining the loops directly could lay triangles flat along a face
+    /// where the neighbouring voxel has a tube of its own, sharing their edges.
+    fn tube(
+        &mut self,
+        a: &[u32],
+        b: &[u32],
+        voxel_centre: [f32; 3],
+        ring_key: impl Fn(usize) -> usize,
+    ) {
+        let nearest: Vec<u32> = a
+            .iter()
+            .map(|&i| {
+                *b.iter()
+                    .min_by(|&&p, &&q| self.distance(i, p).total_cmp(&self.distance(i, q)))
+                    .expect("empty loop")
+            })
+            .collect();
+        let ring: Vec<u32> = a
+            .iter()
+            .zip(nearest)
+            .enumerate()
+            .map(|(n, (&i, j))| {
+                let mut vertex = self.centre(&[i, j]);
+                vertex.position = [0, 1, 2].map(|k| (vertex.position[k] + voxel_centre[k]) * 0.5);
+                self.vertex(ring_key(n), || vertex)
+            })
+            .collect();
+        for i in 0..a.len() {
+            let j = (i + 1) % a.len();
+            self.indices
+                .extend([a[i], a[j], ring[j], a[i], ring[j], ring[i]]);
+        }
+        self.strip(&ring, b);
+    }
+
+    /// Joins the loops of vertices `a` and `b` with a strip of triangles.
+    ///
+    /// Both loops are wound counter-clockwise as seen from outside the surface, so the strip
+    /// walks `a` forwards and `b` backwards. It starts from the vertex of `b` closest to the
+    /// first of `a` and advances along whichever loop gives the shorter diagonal, so each
+    /// vertex of `a` joins one run of consecutive vertices of `b`.
+    ///
+    /// Neither loop is closed while the other is still where it was when the first started
+    /// moving: that would return to a diagonal already used and repeat its edge.
+    fn strip(&mut self, a: &[u32], b: &[u32]) {
+        let start = (0..b.len())
+            .min_by(|&i, &j| {
+                self.distance(a[0], b[i])
+                    .total_cmp(&self.distance(a[0], b[j]))
+            })
+            .expect("empty loop");
+        let a_at = |i: usize| a[i % a.len()];
+        let b_at = |j: usize| b[(start + b.len() - j % b.len()) % b.len()];
+        let (mut i, mut j) = (0, 0);
+        // Position of the other loop when each loop first advanced. Closing a loop is only
+        // allowed once the other has moved on from there, or a diagonal would repeat.
+        let (mut a_from, mut b_from) = (None, None);
+        while i < a.len() || j < b.len() {
+            let a_open = i < a.len() && !(i + 1 == a.len() && a_from.is_some_and(|from| j <= from));
+            let b_open = j < b.len() && !(j + 1 == b.len() && b_from.is_some_and(|from| i <= from));
+            let advance_a = a_open
+                && (!b_open
+                    || self.distance(a_at(i + 1), b_at(j)) <= self.distance(a_at(i), b_at(j + 1)));
+            if advance_a {
+                a_from.get_or_insert(j);
+                self.indices.extend([a_at(i), a_at(i + 1), b_at(j)]);
+                i += 1;
+            } else {
+                b_from.get_or_insert(i);
+                self.indices.extend([b_at(j + 1), b_at(j), a_at(i)]);
+                j += 1;
+            }
+        }
     }
 
-    GeneratedMesh::build(vertices)
+    /// Returns the squared distance between vertices `i` and `j`.
+    fn distance(&self, i: u32, j: u32) -> f32 {
+        let (p, q) = (self.vertices[i as usize], self.vertices[j as usize]);
+        (0..3).map(|axis| (p[axis] - q[axis]).powi(2)).sum()
+    }
+
+    /// Returns a vertex at the average position of the vertices `ids`, with their averaged
+    /// normal and colour where this slice has them. The centre takes the material of the
+    /// first vertex.
+    fn centre(&self, ids: &[u32]) -> SliceVertex {
+        fn mean<const N: usize>(ids: &[u32], values: &[[f32; N]]) -> [f32; N] {
+            let mut sum = [0.0; N];
+            for &id in ids {
+                for (s, v) in sum.iter_mut().zip(values[id as usize]) {
+                    *s += v;
+                }
+            }
+            sum.map(|s| s / ids.len() as f32)
+        }
+        SliceVertex {
+            position: mean(ids, &self.vertices),
+            normal: (!self.normals.is_empty()).then(|| normalize_or_zero(mean(ids, &self.normals))),
+            color: (!self.colors.is_empty()).then(|| mean(ids, &self.colors)),
+            material: self.materials.get(ids[0] as usize).copied(),
+        }
+    }
 }
 
 /// Returns the 8 corner indices `[x, y, z]` of the voxel at `(x, y, z)`.
@@ -296,3 +1462,419 @@ fn voxel_corner_indices(x: usize, y: usize, z: usize) -> [[usize; 3]; 8] {
         [x, y + 1, z + 1],
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "auto_queue")]
+    use crate::test_support::{test_app, update_until};
+    use crate::{
+        sdf,
+        test_support::{count_non_manifold, is_closed, mesh_standalone},
+        types::CompiledFunction,
+    };
+
+    /// Returns the number of connected pieces of `mesh`.
+    fn components(mesh: &GeneratedMesh) -> usize {
+        let mut parent: Vec<usize> = (0..mesh.vertices.len()).collect();
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        for triangle in mesh.indices.chunks_exact(3) {
+            for k in 1..3 {
+                let a = find(&mut parent, triangle[0] as usize);
+                let b = find(&mut parent, triangle[k] as usize);
+                parent[a] = b;
+            }
+        }
+        let used: HashSet<usize> = mesh.indices.iter().map(|&i| i as usize).collect();
+        let roots: HashSet<usize> = used.iter().map(|&i| find(&mut parent, i)).collect();
+        roots.len()
+    }
+
+    /// Meshes a single voxel with the given corners, in [`CUBE_CORNER_OFFSETS`] order,
+    /// surrounded by a layer of voxels whose outer corners are outside.
+    ///
+    /// [`CUBE_CORNER_OFFSETS`]: crate::transition_tables::CUBE_CORNER_OFFSETS
+    fn mesh_voxel(algorithm: MeshingAlgorithm, corners: [Value; 8]) -> GeneratedMesh {
+        let mut chunk = Chunk::new(3, 3, 3);
+        chunk.fill(&|_, _, _| 1.0);
+        for (&[x, y, z], value) in crate::transition_tables::CUBE_CORNER_OFFSETS
+            .iter()
+            .zip(corners)
+        {
+            chunk.set(x + 1, y + 1, z + 1, value);
+        }
+        mesh_standalone(&chunk, algorithm, NormalMode::AreaWeighted)
+    }
+
+    #[test]
+    fn marching_cubes_33_is_manifold_on_closed_fields() {
+        assert_eq!(
+            count_non_manifold(MeshingAlgorithm::MarchingCubes33, 6, 300),
+            0
+        );
+    }
+
+    #[test]
+    #[ignore = "slow; run with --ignored after changing the ambiguity handling"]
+    fn marching_cubes_33_is_manifold_on_many_closed_fields() {
+        assert_eq!(
+            count_non_manifold(MeshingAlgorithm::MarchingCubes33, 6, 10_000),
+            0
+        );
+    }
+
+    #[test]
+    fn marching_cubes_33_joins_tunnels_with_tubes() {
+        // Case 4: opposite corners inside, joined through the voxel's interior.
+        let interior = [-1.0, 0.1, 0.1, 0.1, 0.1, 0.1, -1.0, 0.1];
+        // Case 10: two inside edges along Z, separated on both diagonal faces but joined
+        // through the middle of the voxel.
+        let faces = [-4.0, 1.5, -0.25, 1.5, -0.25, 1.5, -4.0, 1.5];
+        for corners in [interior, faces] {
+            let mesh = mesh_voxel(MeshingAlgorithm::MarchingCubes33, corners);
+            assert!(is_closed(&[(&mesh, Vec3::ZERO)], false));
+            assert_eq!(components(&mesh), 1);
+        }
+
+        // Case 4 again, but with the corners too weak to meet inside the voxel.
+        let apart = [-0.1, 1.0, 1.0, 1.0, 1.0, 1.0, -0.1, 1.0];
+        let mesh = mesh_voxel(MeshingAlgorithm::MarchingCubes33, apart);
+        assert!(is_closed(&[(&mesh, Vec3::ZERO)], false));
+        assert_eq!(components(&mesh), 2);
+    }
+
+    #[test]
+    fn marching_cubes_welds_vertices_per_edge() {
+        let mut chunk = Chunk::new(12, 12, 12);
+        chunk.fill(&sdf::translate(sdf::sphere(4.37), Vec3::new(6.1, 5.8, 6.2)));
+        let mesh = mesh_standalone(
+            &chunk,
+            MeshingAlgorithm::MarchingCubes,
+            NormalMode::AreaWeighted,
+        );
+
+        // Every edge is shared by two triangles through the same vertex ids, which for a
+        // closed surface without handles leaves `V = F / 2 + 2` vertices.
+        assert!(is_closed(&[(&mesh, Vec3::ZERO)], false));
+        assert_eq!(mesh.vertices.len(), mesh.tri_count() / 2 + 2);
+        // No corner lies exactly on this sphere, so no two edges share a position either.
+        let positions: HashSet<[u32; 3]> =
+            mesh.vertices.iter().map(|v| v.map(f32::to_bits)).collect();
+        assert_eq!(positions.len(), mesh.vertices.len());
+    }
+
+    #[test]
+    fn gradient_normals_follow_the_field() {
+        let centre = Vec3::new(6.1, 5.8, 6.2);
+        let tilt = Vec3::new(0.3, 1.0, -0.4).normalize();
+        let fields: [(&CompiledFunction, &dyn Fn(Vec3) -> Vec3); 2] = [
+            (&sdf::translate(sdf::sphere(4.3), centre), &|p| {
+                (p - centre).normalize()
+            }),
+            (
+                &move |x, y, z| Vec3::new(x, y - 5.5, z).dot(tilt),
+                &move |_| tilt,
+            ),
+        ];
+        for (field, gradient) in fields {
+            let mut chunk = Chunk::new(24, 24, 24).with_scale(0.5);
+            chunk.fill(field);
+            for algorithm in [
+                MeshingAlgorithm::MarchingCubes,
+                MeshingAlgorithm::MarchingCubes33,
+            ] {
+                let mesh = mesh_standalone(&chunk, algorithm, NormalMode::Gradient);
+                assert!(!mesh.vertices.is_empty());
+                for (&v, &n) in mesh.vertices.iter().zip(&mesh.normals) {
+                    let (n, expected) = (Vec3::from(n), gradient(Vec3::from(v)));
+                    assert!((n.length() - 1.0).abs() < 1e-4, "{n} at {v:?}");
+                    assert!(n.dot(expected) > 0.99, "{n} at {v:?}, expected {expected}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn colours_are_interpolated_to_the_surface_crossing() {
+        let mut chunk = Chunk::new(16, 16, 16).with_scale(0.5);
+        chunk.fill(&sdf::translate(sdf::sphere(2.7), Vec3::new(4.1, 3.8, 4.3)));
+        // Colours that grow linearly with position interpolate to the vertex's own position.
+        for z in 0..=16 {
+            for y in 0..=16 {
+                for x in 0..=16 {
+                    let [r, g, b] = [x, y, z].map(|c| c as f32 / 16.0);
+                    chunk.set_color(x, y, z, [r, g, b, 1.0]);
+                }
+            }
+        }
+        for algorithm in [
+            MeshingAlgorithm::MarchingCubes,
+            MeshingAlgorithm::MarchingCubes33,
+            MeshingAlgorithm::MarchingTetrahedra,
+        ] {
+            for normal_mode in [NormalMode::AreaWeighted, NormalMode::Flat] {
+                let mesh = mesh_standalone(&chunk, algorithm, normal_mode);
+                assert!(!mesh.vertices.is_empty());
+                assert_eq!(mesh.colors.len(), mesh.vertices.len());
+                for (&v, &c) in mesh.vertices.iter().zip(&mesh.colors) {
+                    let expected = (Vec3::from(v) / 8.0).extend(1.0);
+                    assert!(
+                        Vec4::from(c).distance(expected) < 1e-5,
+                        "{algorithm:?}: {c:?} at {v:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "auto_queue")]
+    #[test]
+    fn empty_chunks_are_marked_instead_of_meshed() {
+        let mut app = test_app(MarchingCubesPlugin::default());
+        let entity = app.world_mut().spawn(Chunk::new(8, 8, 8)).id();
+        app.update();
+        assert_eq!(
+            app.world().get::<EmptyChunk>(entity),
+            Some(&EmptyChunk::Inside)
+        );
+        assert!(app.world().get::<ComputeTask>(entity).is_none());
+
+        app.world_mut()
+            .get_mut::<Chunk>(entity)
+            .unwrap()
+            .fill(&sdf::translate(sdf::sphere(3.0), Vec3::splat(4.0)));
+        update_until(&mut app, |world| is_meshed(world, entity));
+        assert!(app.world().get::<EmptyChunk>(entity).is_none());
+
+        // Emptying a meshed chunk drops its mesh without spawning a task.
+        let mut chunk = app.world_mut().get_mut::<Chunk>(entity).unwrap();
+        chunk.fill(&|_, _, _| 1.0);
+        app.update();
+        let world = app.world();
+        assert_eq!(world.get::<EmptyChunk>(entity), Some(&EmptyChunk::Outside));
+        assert!(world.get::<ComputeTask>(entity).is_none());
+        assert!(world.get::<QueuedChunk>(entity).is_none());
+        assert!(world.get::<Mesh3d>(entity).is_none());
+    }
+
+    #[cfg(feature = "auto_queue")]
+    #[test]
+    fn neighbours_agree_on_their_shared_face() {
+        let mut app = test_app(MarchingCubesPlugin::default());
+        let plane = |height: f32| {
+            let mut chunk = Chunk::new(8, 8, 8);
+            chunk.fill(&move |_, y, _| y - height);
+            chunk
+        };
+        let left = app
+            .world_mut()
+            .spawn((plane(3.3), ChunkCoord(IVec3::ZERO)))
+            .id();
+        let right = app
+            .world_mut()
+            .spawn((
+                plane(5.7),
+                ChunkCoord(IVec3::X),
+                Transform::from_xyz(8.0, 0.0, 0.0),
+            ))
+            .id();
+        let faces = |world: &World| {
+            let face = |entity, x| world.get::<Chunk>(entity).unwrap().values().layer(0, x);
+            (face(left, 8), face(right, 0))
+        };
+        let (left_face, right_face) = faces(app.world());
+        assert_ne!(left_face, right_face);
+
+        // Both chunks are new, so the one on the negative side owns the face.
+        update_until(&mut app, |world| {
+            is_meshed(world, left) && is_meshed(world, right)
+        });
+        assert_eq!(faces(app.world()), (left_face.clone(), left_face));
+
+        // Otherwise the chunk that changed owns it.
+        let edited = plane(1.9).values().as_ref().clone();
+        *app.world_mut()
+            .get_mut::<Chunk>(right)
+            .unwrap()
+            .values_mut() = edited;
+        update_until(&mut app, |world| {
+            is_meshed(world, left) && is_meshed(world, right)
+        });
+        let (left_face, right_face) = faces(app.world());
+        assert_eq!(left_face, right_face);
+        assert_eq!(right_face, plane(1.9).values().layer(0, 0));
+    }
+
+    #[cfg(feature = "auto_queue")]
+    #[test]
+    fn uvs_match_across_chunks_spawned_together() {
+        let mut app = test_app(
+            MarchingCubesPlugin::default()
+                .with_uvs(4.0)
+                .with_retained_meshes(),
+        );
+        let plane = |_: f32, y: f32, _: f32| y - 3.3;
+        let mut left = Chunk::new(8, 8, 8);
+        left.fill(&plane);
+        let mut right = Chunk::new(8, 8, 8);
+        right.fill(&|x, y, z| plane(x + 8.0, y, z));
+        let left = app.world_mut().spawn(left).id();
+        let right = app
+            .world_mut()
+            .spawn((right, Transform::from_xyz(8.0, 0.0, 0.0)))
+            .id();
+        update_until(&mut app, |world| {
+            world.get::<RetainedMesh>(left).is_some() && world.get::<RetainedMesh>(right).is_some()
+        });
+
+        // World-space positions of the vertices on the shared face, with their UVs.
+        let seam = |entity: Entity, x: f32| {
+            let mesh = app.world().get::<RetainedMesh>(entity).unwrap();
+            let mut seam: Vec<([f32; 3], [f32; 2])> = mesh
+                .vertices
+                .iter()
+                .zip(&mesh.uvs)
+                .filter(|(v, _)| v[0] == x)
+                .map(|(v, uv)| ([8.0, v[1], v[2]], *uv))
+                .collect();
+            seam.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            seam.dedup();
+            seam
+        };
+        let (left, right) = (seam(left, 8.0), seam(right, 0.0));
+        assert!(!left.is_empty());
+        assert_eq!(left.len(), right.len());
+        for ((p, a), (q, b)) in left.iter().zip(&right) {
+            assert_eq!(p, q);
+            assert!(
+                Vec2::from(*a).distance(Vec2::from(*b)) < 1e-5,
+                "{a:?} != {b:?} at {p:?}"
+            );
+        }
+    }
+
+    /// Returns the positions of the mesh asset shown by `entity`.
+    #[cfg(feature = "auto_queue")]
+    fn shown_positions(world: &World, entity: Entity) -> Vec<[f32; 3]> {
+        let handle = &world.get::<Mesh3d>(entity).unwrap().0;
+        let mesh = world.resource::<Assets<Mesh>>().get(handle).unwrap();
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+            .and_then(|positions| positions.as_float3())
+            .unwrap()
+            .to_vec()
+    }
+
+    /// Returns `true` once `entity` has no meshing work left.
+    #[cfg(feature = "auto_queue")]
+    fn is_meshed(world: &mut World, entity: Entity) -> bool {
+        world.get::<Mesh3d>(entity).is_some() && world.get::<QueuedChunk>(entity).is_none()
+    }
+
+    #[cfg(feature = "auto_queue")]
+    #[test]
+    fn retained_meshes_and_asset_usage_follow_each_chunk() {
+        let sphere = || {
+            let mut chunk = Chunk::new(8, 8, 8);
+            chunk.fill(&sdf::translate(sdf::sphere(3.0), Vec3::splat(4.0)));
+            chunk
+        };
+        let usage = |world: &World, entity| {
+            let handle = &world.get::<Mesh3d>(entity).unwrap().0;
+            world
+                .resource::<Assets<Mesh>>()
+                .get(handle)
+                .unwrap()
+                .asset_usage
+        };
+
+        let mut app = test_app(MarchingCubesPlugin::default());
+        let entity = app.world_mut().spawn(sphere()).id();
+        update_until(&mut app, |world| is_meshed(world, entity));
+        assert!(app.world().get::<RetainedMesh>(entity).is_none());
+        assert_eq!(usage(app.world(), entity), RenderAssetUsages::RENDER_WORLD);
+
+        let mut app = test_app(MarchingCubesPlugin::default().with_retained_meshes());
+        let both = app
+            .world_mut()
+            .spawn(sphere().with_asset_usage(RenderAssetUsages::all()))
+            .id();
+        let render = app
+            .world_mut()
+            .spawn((sphere(), Transform::from_xyz(8.0, 0.0, 0.0)))
+            .id();
+        update_until(&mut app, |world| {
+            is_meshed(world, both) && is_meshed(world, render)
+        });
+        assert_eq!(usage(app.world(), both), RenderAssetUsages::all());
+        assert_eq!(usage(app.world(), render), RenderAssetUsages::RENDER_WORLD);
+        let expected = mesh_standalone(
+            &sphere(),
+            MeshingAlgorithm::MarchingCubes,
+            NormalMode::AreaWeighted,
+        );
+        for entity in [both, render] {
+            let retained = app.world().get::<RetainedMesh>(entity).unwrap();
+            assert_eq!(retained.vertices, expected.vertices);
+            assert_eq!(retained.indices, expected.indices);
+        }
+
+        // Emptying a chunk drops the data along with the mesh.
+        app.world_mut()
+            .get_mut::<Chunk>(both)
+            .unwrap()
+            .fill(&|_, _, _| 1.0);
+        app.update();
+        assert!(app.world().get::<RetainedMesh>(both).is_none());
+        assert!(app.world().get::<RetainedMesh>(render).is_some());
+    }
+
+    #[cfg(feature = "auto_queue")]
+    #[test]
+    fn editing_a_chunk_remeshes_it_in_place() {
+        let mut app = test_app(MarchingCubesPlugin::default());
+        let filled = |radius: f32| {
+            let mut chunk = Chunk::new(16, 16, 16);
+            chunk.fill(&sdf::translate(sdf::sphere(radius), Vec3::splat(8.0)));
+            chunk
+        };
+        let mesh = |radius: f32| {
+            mesh_standalone(
+                &filled(radius),
+                MeshingAlgorithm::MarchingCubes,
+                NormalMode::AreaWeighted,
+            )
+        };
+        let entity = app.world_mut().spawn(filled(3.0)).id();
+        update_until(&mut app, |world| is_meshed(world, entity));
+        let handle = app.world().get::<Mesh3d>(entity).unwrap().0.clone();
+        assert_eq!(shown_positions(app.world(), entity), mesh(3.0).vertices);
+
+        // A task for an older version of the chunk, not yet polled when the chunk is edited.
+        let stale = mesh(4.5);
+        let stale =
+            AsyncComputeTaskPool::get().spawn(async move { (None, Some(stale), Vec::new()) });
+        app.world_mut()
+            .entity_mut(entity)
+            .insert((QueuedChunk, ComputeTask(stale)));
+
+        let values = filled(6.0).values().as_ref().clone();
+        *app.world_mut()
+            .get_mut::<Chunk>(entity)
+            .unwrap()
+            .values_mut() = values;
+        update_until(&mut app, |world| is_meshed(world, entity));
+        for _ in 0..20 {
+            app.update();
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        assert_eq!(app.world().get::<Mesh3d>(entity).unwrap().0, handle);
+        assert_eq!(shown_positions(app.world(), entity), mesh(6.0).vertices);
+    }
+}
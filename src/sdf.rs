@@ -0,0 +1,440 @@
+use bevy::prelude::*;
+
+use crate::types::Value;
+
+/// A signed distance field: any thread-safe `Fn(x, y, z) -> distance` closure.
+///
+/// Implemented for every matching closure. The functions in this module all return an
+/// `Sdf`, so their results can be combined with each other and with plain closures, then
+/// passed straight to [`Chunk::fill`](crate::chunk::Chunk::fill):
+///
+/// ```rust,ignore
+/// use bevy_marching_cubes::sdf::*;
+///
+/// let shape = smooth_union(
+///     translate(sphere(2.0), Vec3::new(4.0, 4.0, 4.0)),
+///     translate(rotate(torus(2.5, 0.5), Quat::from_rotation_x(0.4)), Vec3::new(4.0, 3.0, 4.0)),
+///     0.5,
+/// );
+/// chunk.fill(&shape);
+/// ```
+///
+/// Primitives are centred on the origin and negative inside, matching a chunk threshold of
+/// `0.0`. Domain operations like [`twist`] and [`bend`] distort distances, so their result
+/// is only an approximate distance field; the surface itself is exact.
+pub trait Sdf: Fn(f32, f32, f32) -> Value + Send + Sync {}
+
+impl<F> Sdf for F where F: Fn(f32, f32, f32) -> Value + Send + Sync {}
+
+/// Evaluates `sdf` at `p`.
+#[inline]
+fn at(sdf: &impl Sdf, p: Vec3) -> Value {
+    sdf(p.x, p.y, p.z)
+}
+
+/// Bounds `sdf` over the box from `min` to `max` from a single sample at its centre, for
+/// [`Chunk::fill_pruned`](crate::chunk::Chunk::fill_pruned).
+///
+/// `lipschitz` is the most the field can change per unit of distance: `1.0` for the
+/// primitives and CSG operators in this module. Domain operations like [`twist`] and
+/// [`displace`] can raise it.
+pub fn lipschitz_bounds(sdf: &impl Sdf, min: Vec3, max: Vec3, lipschitz: f32) -> (Value, Value) {
+    let d = at(sdf, (min + max) * 0.5);
+    let reach = (max - min).length() * 0.5 * lipschitz;
+    (d - reach, d + reach)
+}
+
+/// Sphere of `radius`.
+pub fn sphere(radius: f32) -> impl Sdf {
+    move |x, y, z| Vec3::new(x, y, z).length() - radius
+}
+
+/// Axis-aligned box reaching `half_extents` from the origin along each axis.
+pub fn cuboid(half_extents: Vec3) -> impl Sdf {
+    move |x, y, z| {
+        let q = Vec3::new(x, y, z).abs() - half_extents;
+        q.max(Vec3::ZERO).length() + q.max_element().min(0.0)
+    }
+}
+
+/// Box of `half_extents` whose edges are rounded off with `radius`. The rounding stays
+/// inside `half_extents`.
+pub fn rounded_cuboid(half_extents: Vec3, radius: f32) -> impl Sdf {
+    let inner = cuboid((half_extents - radius).max(Vec3::ZERO));
+    move |x, y, z| inner(x, y, z) - radius
+}
+
+/// Segment from `a` to `b` swept by a ball of `radius`.
+pub fn capsule(a: Vec3, b: Vec3, radius: f32) -> impl Sdf {
+    move |x, y, z| {
+        let (pa, ba) = (Vec3::new(x, y, z) - a, b - a);
+        let length_squared = ba.length_squared();
+        let t = if length_squared > 0.0 {
+            (pa.dot(ba) / length_squared).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        pa.distance(ba * t) - radius
+    }
+}
+
+/// Cylinder of `radius` along Y, reaching `half_height` above and below the origin.
+pub fn cylinder(radius: f32, half_height: f32) -> impl Sdf {
+    move |x, y, z| {
+        let d = Vec2::new(Vec2::new(x, z).length() - radius, y.abs() - half_height);
+        d.max_element().min(0.0) + d.max(Vec2::ZERO).length()
+    }
+}
+
+/// Torus in the XZ plane: a tube of `minor_radius` around a ring of `major_radius`.
+pub fn torus(major_radius: f32, minor_radius: f32) -> impl Sdf {
+    move |x, y, z| Vec2::new(Vec2::new(x, z).length() - major_radius, y).length() - minor_radius
+}
+
+/// Half-space below the plane through the origin with the given `normal`, which points out
+/// of the solid.
+pub fn plane(normal: Vec3) -> impl Sdf {
+    let normal = normal.normalize_or(Vec3::Y);
+    move |x, y, z| Vec3::new(x, y, z).dot(normal)
+}
+
+/// Cone along Y with a base of `radius` at the origin and its tip `height` above it.
+pub fn cone(radius: f32, height: f32) -> impl Sdf {
+    let half = height * 0.5;
+    move |x, y, z| {
+        // Capped cone with radius `radius` at the bottom cap and `0` at the top.
+        let q = Vec2::new(Vec2::new(x, z).length(), y - half);
+        let k1 = Vec2::new(0.0, half);
+        let k2 = Vec2::new(-radius, height);
+        let cap_radius = if q.y < 0.0 { radius } else { 0.0 };
+        let ca = Vec2::new(q.x - q.x.min(cap_radius), q.y.abs() - half);
+        let cb = q - k1 + k2 * ((k1 - q).dot(k2) / k2.length_squared()).clamp(0.0, 1.0);
+        let sign = if cb.x < 0.0 && ca.y < 0.0 { -1.0 } else { 1.0 };
+        sign * ca.length_squared().min(cb.length_squared()).sqrt()
+    }
+}
+
+/// Polynomial smooth minimum of `a` and `b`, blending over a distance of `k`.
+///
+/// Equal to `a.min(b)` when `k <= 0`.
+pub fn smooth_min(a: Value, b: Value, k: f32) -> Value {
+    if k <= 0.0 {
+        return a.min(b);
+    }
+    let h = (k - (a - b).abs()).max(0.0) / k;
+    a.min(b) - h * h * k * 0.25
+}
+
+/// Polynomial smooth maximum of `a` and `b`, blending over a distance of `k`.
+pub fn smooth_max(a: Value, b: Value, k: f32) -> Value {
+    -smooth_min(-a, -b, k)
+}
+
+/// Space inside either `a` or `b`.
+pub fn union(a: impl Sdf, b: impl Sdf) -> impl Sdf {
+    move |x, y, z| a(x, y, z).min(b(x, y, z))
+}
+
+/// Space inside both `a` and `b`.
+pub fn intersection(a: impl Sdf, b: impl Sdf) -> impl Sdf {
+    move |x, y, z| a(x, y, z).max(b(x, y, z))
+}
+
+/// Space inside `a` but not `b`.
+pub fn difference(a: impl Sdf, b: impl Sdf) -> impl Sdf {
+    move |x, y, z| a(x, y, z).max(-b(x, y, z))
+}
+
+/// [`union`] with the seam filleted over a distance of `k`.
+pub fn smooth_union(a: impl Sdf, b: impl Sdf, k: f32) -> impl Sdf {
+    move |x, y, z| smooth_min(a(x, y, z), b(x, y, z), k)
+}
+
+/// [`intersection`] with the seam rounded over a distance of `k`.
+pub fn smooth_intersection(a: impl Sdf, b: impl Sdf, k: f32) -> impl Sdf {
+    move |x, y, z| smooth_max(a(x, y, z), b(x, y, z), k)
+}
+
+/// [`difference`] with the cut edge rounded over a distance of `k`.
+pub fn smooth_difference(a: impl Sdf, b: impl Sdf, k: f32) -> impl Sdf {
+    move |x, y, z| smooth_max(a(x, y, z), -b(x, y, z), k)
+}
+
+/// Moves `sdf` by `offset`.
+pub fn translate(sdf: impl Sdf, offset: Vec3) -> impl Sdf {
+    move |x, y, z| at(&sdf, Vec3::new(x, y, z) - offset)
+}
+
+/// Rotates `sdf` about the origin by `rotation`.
+pub fn rotate(sdf: impl Sdf, rotation: Quat) -> impl Sdf {
+    let inverse = rotation.inverse();
+    move |x, y, z| at(&sdf, inverse * Vec3::new(x, y, z))
+}
+
+/// Scales `sdf` uniformly about the origin by `factor`.
+///
+/// Only uniform scaling keeps the result a distance field.
+pub fn scale(sdf: impl Sdf, factor: f32) -> impl Sdf {
+    move |x, y, z| at(&sdf, Vec3::new(x, y, z) / factor) * factor
+}
+
+/// Mirrors the half of `sdf` in front of the plane through the origin with the given
+/// `normal` onto the other side, making it symmetric about that plane.
+pub fn mirror(sdf: impl Sdf, normal: Vec3) -> impl Sdf {
+    let normal = normal.normalize_or(Vec3::X);
+    move |x, y, z| {
+        let p = Vec3::new(x, y, z);
+        at(&sdf, p - 2.0 * p.dot(normal).min(0.0) * normal)
+    }
+}
+
+/// Repeats `sdf` forever with the given `period` along each axis. Axes with a period of
+/// `0.0` aren't repeated.
+///
+/// The shape should fit within one period around the origin.
+pub fn repeat(sdf: impl Sdf, period: Vec3) -> impl Sdf {
+    move |x, y, z| {
+        let p = Vec3::new(x, y, z);
+        let cell = Vec3::select(period.cmpne(Vec3::ZERO), (p / period).round(), Vec3::ZERO);
+        at(&sdf, p - period * cell)
+    }
+}
+
+/// Twists `sdf` about the Y axis by `rate` radians per unit of height.
+pub fn twist(sdf: impl Sdf, rate: f32) -> impl Sdf {
+    move |x, y, z| {
+        let (sin, cos) = (rate * y).sin_cos();
+        at(&sdf, Vec3::new(cos * x - sin * z, y, sin * x + cos * z))
+    }
+}
+
+/// Bends `sdf` in the XY plane by `rate` radians per unit along X.
+pub fn bend(sdf: impl Sdf, rate: f32) -> impl Sdf {
+    move |x, y, z| {
+        let (sin, cos) = (rate * x).sin_cos();
+        at(&sdf, Vec3::new(cos * x - sin * y, sin * x + cos * y, z))
+    }
+}
+
+/// Adds `displacement` to `sdf`, for example noise to roughen a surface.
+///
+/// Keep the displacement's slope below `1.0` so the result stays close to a distance field.
+pub fn displace(sdf: impl Sdf, displacement: impl Sdf) -> impl Sdf {
+    move |x, y, z| sdf(x, y, z) + displacement(x, y, z)
+}
+
+/// Smooth value noise between `-amplitude` and `amplitude`, with features about
+/// `1 / frequency` apart. Different seeds give unrelated noise.
+///
+/// Use it with [`displace`] to roughen a surface.
+pub fn noise(amplitude: f32, frequency: f32, seed: u32) -> impl Sdf {
+    move |x, y, z| amplitude * value_noise(Vec3::new(x, y, z) * frequency, seed)
+}
+
+/// Trilinearly interpolated random values on the integer lattice, in `[-1, 1]`.
+fn value_noise(p: Vec3, seed: u32) -> f32 {
+    let cell = p.floor();
+    let f = p - cell;
+    // Quintic fade so the noise has continuous slope across cell boundaries.
+    let w = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+    let [cx, cy, cz] = cell.as_ivec3().to_array();
+    let corner = |dx: i32, dy: i32, dz: i32| lattice_value(cx + dx, cy + dy, cz + dz, seed);
+
+    let x00 = corner(0, 0, 0) + (corner(1, 0, 0) - corner(0, 0, 0)) * w.x;
+    let x10 = corner(0, 1, 0) + (corner(1, 1, 0) - corner(0, 1, 0)) * w.x;
+    let x01 = corner(0, 0, 1) + (corner(1, 0, 1) - corner(0, 0, 1)) * w.x;
+    let x11 = corner(0, 1, 1) + (corner(1, 1, 1) - corner(0, 1, 1)) * w.x;
+    let y0 = x00 + (x10 - x00) * w.y;
+    let y1 = x01 + (x11 - x01) * w.y;
+    y0 + (y1 - y0) * w.z
+}
+
+/// Hashes a lattice point to a random value in `[-1, 1]`.
+fn lattice_value(x: i32, y: i32, z: i32, seed: u32) -> f32 {
+    let mut h = seed.wrapping_mul(0x9e37_79b9)
+        ^ (x as u32).wrapping_mul(0x85eb_ca6b)
+        ^ (y as u32).wrapping_mul(0xc2b2_ae35)
+        ^ (z as u32).wrapping_mul(0x27d4_eb2f);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    h ^= h >> 12;
+    h = h.wrapping_mul(0x297a_2d39);
+    h ^= h >> 15;
+    h as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Points on a lattice around the origin, for checks that should hold everywhere.
+    fn samples() -> impl Iterator<Item = Vec3> {
+        (-8..=8).flat_map(|z| {
+            (-8..=8).flat_map(move |y| {
+                (-8..=8).map(move |x| Vec3::new(x as f32, y as f32, z as f32) * 0.45 + 0.05)
+            })
+        })
+    }
+
+    fn assert_distances(sdf: impl Sdf, expected: &[(Vec3, Value)]) {
+        for &(p, distance) in expected {
+            let actual = at(&sdf, p);
+            assert!(
+                (actual - distance).abs() < 1e-5,
+                "expected {distance} at {p}, got {actual}"
+            );
+        }
+    }
+
+    #[test]
+    fn primitives_have_known_distances() {
+        assert_distances(
+            sphere(2.0),
+            &[
+                (Vec3::ZERO, -2.0),
+                (Vec3::new(2.0, 0.0, 0.0), 0.0),
+                (Vec3::new(0.0, -1.2, 1.6), 0.0),
+                (Vec3::new(0.0, 3.0, 4.0), 3.0),
+            ],
+        );
+        assert_distances(
+            cuboid(Vec3::new(1.0, 2.0, 3.0)),
+            &[
+                (Vec3::ZERO, -1.0),
+                (Vec3::new(-1.0, 0.5, 0.5), 0.0),
+                (Vec3::new(0.0, 2.0, 0.0), 0.0),
+                (Vec3::new(0.5, 0.5, -3.0), 0.0),
+                (Vec3::new(3.0, 0.0, 0.0), 2.0),
+                (Vec3::new(2.0, 3.0, 3.0), 2f32.sqrt()),
+            ],
+        );
+        assert_distances(
+            rounded_cuboid(Vec3::splat(1.0), 0.25),
+            &[
+                (Vec3::ZERO, -1.0),
+                (Vec3::new(1.0, 0.0, 0.0), 0.0),
+                (Vec3::splat(0.75 + 0.25 / 3f32.sqrt()), 0.0),
+            ],
+        );
+        assert_distances(
+            torus(2.5, 0.5),
+            &[
+                (Vec3::ZERO, 2.0),
+                (Vec3::new(2.5, 0.0, 0.0), -0.5),
+                (Vec3::new(0.0, 0.0, -3.0), 0.0),
+                (Vec3::new(2.0, 0.0, 0.0), 0.0),
+                (Vec3::new(0.0, 0.5, 2.5), 0.0),
+            ],
+        );
+        assert_distances(
+            cylinder(1.0, 2.0),
+            &[
+                (Vec3::ZERO, -1.0),
+                (Vec3::new(0.6, 0.0, 0.8), 0.0),
+                (Vec3::new(0.0, -2.0, 0.0), 0.0),
+                (Vec3::new(0.0, 5.0, 0.0), 3.0),
+            ],
+        );
+        assert_distances(
+            capsule(Vec3::ZERO, Vec3::new(0.0, 2.0, 0.0), 0.5),
+            &[
+                (Vec3::new(0.0, 1.0, 0.0), -0.5),
+                (Vec3::new(0.5, 1.0, 0.0), 0.0),
+                (Vec3::new(0.0, 3.0, 0.0), 0.5),
+            ],
+        );
+        assert_distances(
+            plane(Vec3::new(0.0, 2.0, 0.0)),
+            &[
+                (Vec3::new(3.0, -1.0, 4.0), -1.0),
+                (Vec3::new(3.0, 2.0, 4.0), 2.0),
+            ],
+        );
+        assert_distances(
+            cone(1.0, 2.0),
+            &[
+                (Vec3::ZERO, 0.0),
+                (Vec3::new(0.0, 2.0, 0.0), 0.0),
+                (Vec3::new(0.5, 1.0, 0.0), 0.0),
+                (Vec3::new(0.0, 3.0, 0.0), 1.0),
+                (Vec3::new(0.0, -1.0, 0.0), 1.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn smooth_operators_blend_towards_the_sharp_ones() {
+        let a = translate(sphere(1.5), Vec3::new(-0.8, 0.0, 0.0));
+        let b = translate(cuboid(Vec3::splat(1.0)), Vec3::new(0.8, 0.3, 0.0));
+        let k = 0.6;
+        for p in samples() {
+            let (x, y, z) = (p.x, p.y, p.z);
+            let sharp = union(&a, &b)(x, y, z);
+            let smooth = smooth_union(&a, &b, k)(x, y, z);
+            assert!(
+                smooth <= sharp && smooth >= sharp - k * 0.25,
+                "union at {p}"
+            );
+
+            let sharp = intersection(&a, &b)(x, y, z);
+            let smooth = smooth_intersection(&a, &b, k)(x, y, z);
+            assert!(
+                smooth >= sharp && smooth <= sharp + k * 0.25,
+                "intersection at {p}"
+            );
+
+            let sharp = difference(&a, &b)(x, y, z);
+            let smooth = smooth_difference(&a, &b, k)(x, y, z);
+            assert!(
+                smooth >= sharp && smooth <= sharp + k * 0.25,
+                "difference at {p}"
+            );
+        }
+        assert_eq!(smooth_min(1.0, 2.0, 0.0), 1.0);
+        assert_eq!(smooth_min(1.0, 3.0, 1.0), 1.0);
+    }
+
+    #[test]
+    fn lipschitz_bounds_contain_the_field() {
+        let shapes: [&(dyn Fn(f32, f32, f32) -> Value + Send + Sync); 4] = [
+            &smooth_union(
+                translate(sphere(2.0), Vec3::new(0.5, 0.0, 0.0)),
+                rotate(torus(2.5, 0.5), Quat::from_rotation_x(0.7)),
+                0.5,
+            ),
+            &difference(
+                cuboid(Vec3::new(2.0, 1.0, 3.0)),
+                capsule(Vec3::ZERO, Vec3::Y, 0.8),
+            ),
+            &scale(
+                smooth_intersection(cylinder(1.0, 2.0), cone(2.0, 3.0), 0.3),
+                1.7,
+            ),
+            &mirror(
+                rounded_cuboid(Vec3::splat(1.5), 0.4),
+                Vec3::new(1.0, 1.0, 0.0),
+            ),
+        ];
+        for shape in shapes {
+            let sdf = |x, y, z| shape(x, y, z);
+            for min in samples().step_by(97) {
+                for size in [0.3, 1.0, 2.5] {
+                    let max = min + Vec3::new(size, size * 0.5, size * 1.5);
+                    let (low, high) = lipschitz_bounds(&sdf, min, max, 1.0);
+                    for i in 0..=4 {
+                        for j in 0..=4 {
+                            for k in 0..=4 {
+                                let t = Vec3::new(i as f32, j as f32, k as f32) / 4.0;
+                                let d = at(&sdf, min + (max - min) * t);
+                                assert!(
+                                    low - 1e-4 <= d && d <= high + 1e-4,
+                                    "{d} outside {low}..{high} in {min}..{max}"
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,636 @@
+use std::collections::HashSet;
+
+#[cfg(feature = "serialize")]
+use bevy::asset::{AssetLoader, LoadContext, io::Reader};
+use bevy::prelude::*;
+
+#[cfg(feature = "serialize")]
+use crate::error::MarchingCubesError;
+use crate::{
+    chunk::Chunk,
+    sdf::{self, Sdf},
+    types::Value,
+};
+
+/// A signed distance field as a tree of data, for shapes authored in files.
+///
+/// Each variant mirrors a function in [`sdf`]. Operators apply to their
+/// `children`: the CSG nodes combine them, and every other operator acts on their union.
+/// A node without children is empty space.
+///
+/// `SdfNode` is a Bevy [`Asset`]. With the `serialize` feature, `.sdf.ron` files load as
+/// `SdfNode`s, and hot reloading works when Bevy's `file_watcher` feature is enabled:
+///
+/// ```ron
+/// SmoothUnion(k: 0.5, children: [
+///     Translate(offset: (4.0, 4.0, 4.0), children: [Sphere(radius: 2.0)]),
+///     Translate(offset: (4.0, 3.0, 4.0), children: [Torus(major_radius: 2.5, minor_radius: 0.5)]),
+/// ])
+/// ```
+///
+/// Add an [`SdfFill`] to a chunk to fill it from a loaded asset, or call
+/// [`distance`](SdfNode::distance) directly.
+#[derive(Asset, Reflect, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum SdfNode {
+    /// See [`sdf::sphere`].
+    Sphere { radius: f32 },
+    /// See [`sdf::cuboid`].
+    Cuboid { half_extents: Vec3 },
+    /// See [`sdf::rounded_cuboid`].
+    RoundedCuboid { half_extents: Vec3, radius: f32 },
+    /// See [`sdf::capsule`].
+    Capsule { a: Vec3, b: Vec3, radius: f32 },
+    /// See [`sdf::cylinder`].
+    Cylinder { radius: f32, half_height: f32 },
+    /// See [`sdf::torus`].
+    Torus {
+        major_radius: f32,
+        minor_radius: f32,
+    },
+    /// See [`sdf::plane`].
+    Plane { normal: Vec3 },
+    /// See [`sdf::cone`].
+    Cone { radius: f32, height: f32 },
+
+    /// Space inside any child.
+    Union { children: Vec<SdfNode> },
+    /// Space inside every child.
+    Intersection { children: Vec<SdfNode> },
+    /// Space inside the first child but none of the others.
+    Difference { children: Vec<SdfNode> },
+    /// [`Union`](SdfNode::Union) blended over a distance of `k`.
+    SmoothUnion { k: f32, children: Vec<SdfNode> },
+    /// [`Intersection`](SdfNode::Intersection) blended over a distance of `k`.
+    SmoothIntersection { k: f32, children: Vec<SdfNode> },
+    /// [`Difference`](SdfNode::Difference) blended over a distance of `k`.
+    SmoothDifference { k: f32, children: Vec<SdfNode> },
+
+    /// See [`sdf::translate`].
+    Translate {
+        offset: Vec3,
+        children: Vec<SdfNode>,
+    },
+    /// See [`sdf::rotate`].
+    Rotate {
+        rotation: Quat,
+        children: Vec<SdfNode>,
+    },
+    /// See [`sdf::scale`].
+    Scale { factor: f32, children: Vec<SdfNode> },
+    /// See [`sdf::mirror`].
+    Mirror {
+        normal: Vec3,
+        children: Vec<SdfNode>,
+    },
+    /// See [`sdf::repeat`].
+    Repeat {
+        period: Vec3,
+        children: Vec<SdfNode>,
+    },
+    /// See [`sdf::twist`].
+    Twist { rate: f32, children: Vec<SdfNode> },
+    /// See [`sdf::bend`].
+    Bend { rate: f32, children: Vec<SdfNode> },
+    /// Displaces the children by [`sdf::noise`].
+    Noise {
+        amplitude: f32,
+        frequency: f32,
+        seed: u32,
+        children: Vec<SdfNode>,
+    },
+}
+
+impl SdfNode {
+    /// Returns the signed distance from `p` to the surface. Negative inside.
+    pub fn distance(&self, p: Vec3) -> Value {
+        let Vec3 { x, y, z } = p;
+        match self {
+            SdfNode::Sphere { radius } => sdf::sphere(*radius)(x, y, z),
+            SdfNode::Cuboid { half_extents } => sdf::cuboid(*half_extents)(x, y, z),
+            SdfNode::RoundedCuboid {
+                half_extents,
+                radius,
+            } => sdf::rounded_cuboid(*half_extents, *radius)(x, y, z),
+            SdfNode::Capsule { a, b, radius } => sdf::capsule(*a, *b, *radius)(x, y, z),
+            SdfNode::Cylinder {
+                radius,
+                half_height,
+            } => sdf::cylinder(*radius, *half_height)(x, y, z),
+            SdfNode::Torus {
+                major_radius,
+                minor_radius,
+            } => sdf::torus(*major_radius, *minor_radius)(x, y, z),
+            SdfNode::Plane { normal } => sdf::plane(*normal)(x, y, z),
+            SdfNode::Cone { radius, height } => sdf::cone(*radius, *height)(x, y, z),
+
+            SdfNode::Union { children } => combine(children, p, |a, b| a.min(b)),
+            SdfNode::Intersection { children } => combine(children, p, |a, b| a.max(b)),
+            SdfNode::Difference { children } => combine(children, p, |a, b| a.max(-b)),
+            SdfNode::SmoothUnion { k, children } => {
+                combine(children, p, |a, b| sdf::smooth_min(a, b, *k))
+            }
+            SdfNode::SmoothIntersection { k, children } => {
+                combine(children, p, |a, b| sdf::smooth_max(a, b, *k))
+            }
+            SdfNode::SmoothDifference { k, children } => {
+                combine(children, p, |a, b| sdf::smooth_max(a, -b, *k))
+            }
+
+            SdfNode::Translate { offset, children } => {
+                sdf::translate(group(children), *offset)(x, y, z)
+            }
+            SdfNode::Rotate { rotation, children } => {
+                sdf::rotate(group(children), *rotation)(x, y, z)
+            }
+            SdfNode::Scale { factor, children } => sdf::scale(group(children), *factor)(x, y, z),
+            SdfNode::Mirror { normal, children } => sdf::mirror(group(children), *normal)(x, y, z),
+            SdfNode::Repeat { period, children } => sdf::repeat(group(children), *period)(x, y, z),
+            SdfNode::Twist { rate, children } => sdf::twist(group(children), *rate)(x, y, z),
+            SdfNode::Bend { rate, children } => sdf::bend(group(children), *rate)(x, y, z),
+            SdfNode::Noise {
+                amplitude,
+                frequency,
+                seed,
+                children,
+            } => sdf::displace(group(children), sdf::noise(*amplitude, *frequency, *seed))(x, y, z),
+        }
+    }
+
+    /// Returns a lower and upper bound of [`distance`](SdfNode::distance) over the box from
+    /// `min` to `max`, for [`Chunk::fill_pruned`].
+    ///
+    /// Primitives are bounded from their distance at the centre of the box, and operators
+    /// combine their children's bounds with interval arithmetic, mapping the box through
+    /// their domain transform. The bounds are conservative, but tighten as the box shrinks.
+    pub fn bounds(&self, min: Vec3, max: Vec3) -> (Value, Value) {
+        match self {
+            SdfNode::Sphere { .. }
+            | SdfNode::Cuboid { .. }
+            | SdfNode::RoundedCuboid { .. }
+            | SdfNode::Capsule { .. }
+            | SdfNode::Cylinder { .. }
+            | SdfNode::Torus { .. }
+            | SdfNode::Plane { .. }
+            | SdfNode::Cone { .. } => {
+                sdf::lipschitz_bounds(&|x, y, z| self.distance(Vec3::new(x, y, z)), min, max, 1.)
+            }
+
+            SdfNode::Union { children } => {
+                combine_bounds(children, min, max, |a, b| (a.0.min(b.0), a.1.min(b.1)))
+            }
+            SdfNode::Intersection { children } => {
+                combine_bounds(children, min, max, |a, b| (a.0.max(b.0), a.1.max(b.1)))
+            }
+            SdfNode::Difference { children } => {
+                combine_bounds(children, min, max, |a, b| (a.0.max(-b.1), a.1.max(-b.0)))
+            }
+            // The smooth operators differ from their sharp versions by at most `k / 4`.
+            SdfNode::SmoothUnion { k, children } => combine_bounds(children, min, max, |a, b| {
+                (a.0.min(b.0) - k.max(0.) * 0.25, a.1.min(b.1))
+            }),
+            SdfNode::SmoothIntersection { k, children } => {
+                combine_bounds(children, min, max, |a, b| {
+                    (a.0.max(b.0), a.1.max(b.1) + k.max(0.) * 0.25)
+                })
+            }
+            SdfNode::SmoothDifference { k, children } => {
+                combine_bounds(children, min, max, |a, b| {
+                    (a.0.max(-b.1), a.1.max(-b.0) + k.max(0.) * 0.25)
+                })
+            }
+
+            SdfNode::Translate { offset, children } => {
+                group_bounds(children, min - *offset, max - *offset)
+            }
+            SdfNode::Rotate { rotation, children } => {
+                let inverse = rotation.inverse();
+                let (min, max) = map_box(min, max, |p| inverse * p);
+                group_bounds(children, min, max)
+            }
+            SdfNode::Scale { factor, children } => {
+                let (min, max) = map_box(min, max, |p| p / *factor);
+                let (low, high) = group_bounds(children, min, max);
+                let (low, high) = (low * factor, high * factor);
+                (low.min(high), low.max(high))
+            }
+            SdfNode::Mirror { normal, children } => {
+                let normal = normal.normalize_or(Vec3::X);
+                let (reflected_min, reflected_max) =
+                    map_box(min, max, |p| p - 2. * p.dot(normal) * normal);
+                let (min, max) = match box_side(min, max, normal) {
+                    Some(true) => (min, max),
+                    Some(false) => (reflected_min, reflected_max),
+                    None => (min.min(reflected_min), max.max(reflected_max)),
+                };
+                group_bounds(children, min, max)
+            }
+            SdfNode::Repeat { period, children } => {
+                let (first, last) = ((min / *period).round(), (max / *period).round());
+                let repeated = period.cmpne(Vec3::ZERO);
+                let shift = Vec3::select(repeated & first.cmpeq(last), *period * first, Vec3::ZERO);
+                // Boxes spanning several periods can reach anywhere within one period.
+                let whole = repeated & first.cmpne(last);
+                let half = period.abs() * 0.5;
+                group_bounds(
+                    children,
+                    Vec3::select(whole, -half, min - shift),
+                    Vec3::select(whole, half, max - shift),
+                )
+            }
+            // Twisting and bending rotate points about an axis, so the box maps into a square
+            // around that axis reaching as far as the box's furthest corner.
+            SdfNode::Twist { children, .. } => {
+                let reach = box_reach(min, max, Vec3::new(1., 0., 1.));
+                let reach = Vec3::new(reach, 0., reach);
+                group_bounds(children, min * Vec3::Y - reach, max * Vec3::Y + reach)
+            }
+            SdfNode::Bend { children, .. } => {
+                let reach = box_reach(min, max, Vec3::new(1., 1., 0.));
+                let reach = Vec3::new(reach, reach, 0.);
+                group_bounds(children, min * Vec3::Z - reach, max * Vec3::Z + reach)
+            }
+            SdfNode::Noise {
+                amplitude,
+                children,
+                ..
+            } => {
+                let (low, high) = group_bounds(children, min, max);
+                (low - amplitude.abs(), high + amplitude.abs())
+            }
+        }
+    }
+
+    /// Turns this tree into an [`Sdf`] closure, for [`Chunk::fill`] or further composition.
+    pub fn into_sdf(self) -> impl Sdf {
+        move |x, y, z| self.distance(Vec3::new(x, y, z))
+    }
+}
+
+/// Distance used for nodes without children: far outside everything.
+///
+/// `Value::MAX` rather than infinity keeps the smooth operators finite.
+const EMPTY: Value = Value::MAX;
+
+/// Folds the children's distances at `p` with `op`, starting from the first child.
+fn combine(children: &[SdfNode], p: Vec3, op: impl Fn(Value, Value) -> Value) -> Value {
+    let mut distances = children.iter().map(|child| child.distance(p));
+    let first = distances.next().unwrap_or(EMPTY);
+    distances.fold(first, op)
+}
+
+/// Folds the children's bounds over a box with `op`, starting from the first child.
+fn combine_bounds(
+    children: &[SdfNode],
+    min: Vec3,
+    max: Vec3,
+    op: impl Fn((Value, Value), (Value, Value)) -> (Value, Value),
+) -> (Value, Value) {
+    let mut bounds = children.iter().map(|child| child.bounds(min, max));
+    let first = bounds.next().unwrap_or((EMPTY, EMPTY));
+    bounds.fold(first, op)
+}
+
+/// Bounds the union of `children` over a box.
+fn group_bounds(children: &[SdfNode], min: Vec3, max: Vec3) -> (Value, Value) {
+    combine_bounds(children, min, max, |a, b| (a.0.min(b.0), a.1.min(b.1)))
+}
+
+/// Returns the eight corners of the box from `min` to `max`.
+fn box_corners(min: Vec3, max: Vec3) -> [Vec3; 8] {
+    std::array::from_fn(|i| Vec3::select(BVec3::new(i & 1 != 0, i & 2 != 0, i & 4 != 0), max, min))
+}
+
+/// Returns the bounding box of the box from `min` to `max` after the linear map `f`.
+fn map_box(min: Vec3, max: Vec3, f: impl Fn(Vec3) -> Vec3) -> (Vec3, Vec3) {
+    box_corners(min, max)
+        .map(f)
+        .iter()
+        .fold((Vec3::MAX, Vec3::MIN), |(lo, hi), &p| {
+            (lo.min(p), hi.max(p))
+        })
+}
+
+/// Returns `Some(true)` if the box lies in front of the plane through the origin with the
+/// given `normal`, `Some(false)` if it lies behind it, and `None` if the plane cuts it.
+fn box_side(min: Vec3, max: Vec3, normal: Vec3) -> Option<bool> {
+    let dots = box_corners(min, max).map(|p| p.dot(normal));
+    if dots.iter().all(|&d| d >= 0.) {
+        Some(true)
+    } else if dots.iter().all(|&d| d <= 0.) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Returns the furthest any point of the box gets from the origin, counting only the axes
+/// set in `mask`.
+fn box_reach(min: Vec3, max: Vec3, mask: Vec3) -> f32 {
+    (min.abs().max(max.abs()) * mask).length()
+}
+
+/// Returns the union of `children` as an [`Sdf`].
+fn group(children: &[SdfNode]) -> impl Sdf + '_ {
+    move |x, y, z| combine(children, Vec3::new(x, y, z), |a, b| a.min(b))
+}
+
+/// Fills the [`Chunk`] on this entity from an [`SdfNode`] asset.
+///
+/// The chunk is filled as soon as the asset has loaded, again whenever the asset is
+/// modified or hot-reloaded, and when the handle changes. Corners are evaluated in world
+/// space, with the chunk's minimum corner at its [`Transform`] translation, so neighbouring
+/// chunks can share one shape. Filling marks the chunk as changed, so it is re-meshed.
+///
+/// Every corner is sampled; add [`PrunedSdfFill`] as well to only sample near the surface.
+///
+/// ```rust,ignore
+/// commands.spawn((
+///     Chunk::new(32, 32, 32),
+///     SdfFill(asset_server.load("shapes/arch.sdf.ron")),
+/// ));
+/// ```
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SdfFill(pub Handle<SdfNode>);
+
+/// Makes the [`SdfFill`] on this entity fill its chunk with [`Chunk::fill_pruned`].
+///
+/// Far fewer corners are sampled, and the mesh is the same, but corners away from the
+/// surface only hold placeholders on the right side of it. Brushes that reach them, such as
+/// a [`Subtract`](crate::BrushOp::Subtract) that uncovers them or a
+/// [`Smooth`](crate::BrushOp::Smooth), act on the placeholders rather than the shape, so
+/// only prune chunks that won't be edited.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrunedSdfFill;
+
+/// Refills chunks whose [`SdfFill`] changed or whose asset was (re)loaded.
+pub(crate) fn fill_from_sdf_assets(
+    mut events: MessageReader<AssetEvent<SdfNode>>,
+    assets: Res<Assets<SdfNode>>,
+    mut chunks: Query<(
+        Ref<SdfFill>,
+        Has<PrunedSdfFill>,
+        &mut Chunk,
+        Option<&Transform>,
+    )>,
+) {
+    let loaded: HashSet<AssetId<SdfNode>> = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    for (fill, pruned, mut chunk, transform) in chunks.iter_mut() {
+        if !fill.is_changed() && !loaded.contains(&fill.0.id()) {
+            continue;
+        }
+        let Some(node) = assets.get(&fill.0) else {
+            continue;
+        };
+        let origin = transform.map_or(Vec3::ZERO, |t| t.translation);
+        let distance = |x, y, z| node.distance(origin + Vec3::new(x, y, z));
+        if pruned {
+            chunk.fill_pruned(distance, |min, max| node.bounds(origin + min, origin + max));
+        } else {
+            chunk.fill(&distance);
+        }
+    }
+}
+
+/// Loads `.sdf.ron` files as [`SdfNode`]s.
+#[cfg(feature = "serialize")]
+#[derive(Default, TypePath)]
+pub struct SdfNodeLoader;
+
+#[cfg(feature = "serialize")]
+impl AssetLoader for SdfNodeLoader {
+    type Asset = SdfNode;
+    type Settings = ();
+    type Error = MarchingCubesError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sdf.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translate(offset: Vec3, child: SdfNode) -> SdfNode {
+        SdfNode::Translate {
+            offset,
+            children: vec![child],
+        }
+    }
+
+    /// Returns a tree using every variant, together with the same shape built from the
+    /// [`sdf`] functions.
+    fn every_variant() -> (SdfNode, impl Sdf) {
+        use SdfNode::*;
+        let rotation = Quat::from_euler(EulerRot::XYZ, 0.4, 0.3, 0.2);
+        let node = Union {
+            children: vec![
+                SmoothUnion {
+                    k: 0.5,
+                    children: vec![
+                        translate(Vec3::new(4.0, 4.0, 4.0), Sphere { radius: 2.0 }),
+                        translate(
+                            Vec3::new(4.0, 3.0, 4.0),
+                            Torus {
+                                major_radius: 2.5,
+                                minor_radius: 0.5,
+                            },
+                        ),
+                    ],
+                },
+                Difference {
+                    children: vec![
+                        Rotate {
+                            rotation,
+                            children: vec![Cuboid {
+                                half_extents: Vec3::new(1.0, 2.0, 3.0),
+                            }],
+                        },
+                        Capsule {
+                            a: Vec3::ZERO,
+                            b: Vec3::new(1.0, 2.0, 0.0),
+                            radius: 0.7,
+                        },
+                        Plane { normal: Vec3::Y },
+                    ],
+                },
+                Intersection {
+                    children: vec![
+                        Scale {
+                            factor: 1.5,
+                            children: vec![RoundedCuboid {
+                                half_extents: Vec3::splat(1.0),
+                                radius: 0.3,
+                            }],
+                        },
+                        Mirror {
+                            normal: Vec3::X,
+                            children: vec![Cone {
+                                radius: 1.0,
+                                height: 2.0,
+                            }],
+                        },
+                    ],
+                },
+                SmoothIntersection {
+                    k: 0.4,
+                    children: vec![
+                        Repeat {
+                            period: Vec3::new(6.0, 0.0, 6.0),
+                            children: vec![Cylinder {
+                                radius: 1.0,
+                                half_height: 2.0,
+                            }],
+                        },
+                        Twist {
+                            rate: 0.3,
+                            children: vec![
+                                Bend {
+                                    rate: 0.2,
+                                    children: vec![Sphere { radius: 3.0 }],
+                                },
+                                Sphere { radius: 1.0 },
+                            ],
+                        },
+                    ],
+                },
+                SmoothDifference {
+                    k: 0.3,
+                    children: vec![
+                        Noise {
+                            amplitude: 0.2,
+                            frequency: 0.7,
+                            seed: 3,
+                            children: vec![Sphere { radius: 2.5 }],
+                        },
+                        Union { children: vec![] },
+                    ],
+                },
+            ],
+        };
+
+        let shape = sdf::union(
+            sdf::union(
+                sdf::union(
+                    sdf::union(
+                        sdf::smooth_union(
+                            sdf::translate(sdf::sphere(2.0), Vec3::new(4.0, 4.0, 4.0)),
+                            sdf::translate(sdf::torus(2.5, 0.5), Vec3::new(4.0, 3.0, 4.0)),
+                            0.5,
+                        ),
+                        sdf::difference(
+                            sdf::difference(
+                                sdf::rotate(sdf::cuboid(Vec3::new(1.0, 2.0, 3.0)), rotation),
+                                sdf::capsule(Vec3::ZERO, Vec3::new(1.0, 2.0, 0.0), 0.7),
+                            ),
+                            sdf::plane(Vec3::Y),
+                        ),
+                    ),
+                    sdf::intersection(
+                        sdf::scale(sdf::rounded_cuboid(Vec3::splat(1.0), 0.3), 1.5),
+                        sdf::mirror(sdf::cone(1.0, 2.0), Vec3::X),
+                    ),
+                ),
+                sdf::smooth_intersection(
+                    sdf::repeat(sdf::cylinder(1.0, 2.0), Vec3::new(6.0, 0.0, 6.0)),
+                    sdf::twist(
+                        sdf::union(sdf::bend(sdf::sphere(3.0), 0.2), sdf::sphere(1.0)),
+                        0.3,
+                    ),
+                    0.4,
+                ),
+            ),
+            sdf::smooth_difference(
+                sdf::displace(sdf::sphere(2.5), sdf::noise(0.2, 0.7, 3)),
+                |_, _, _| EMPTY,
+                0.3,
+            ),
+        );
+        (node, shape)
+    }
+
+    #[test]
+    fn distance_matches_the_sdf_functions() {
+        let (node, shape) = every_variant();
+        for z in -6..=10 {
+            for y in -6..=10 {
+                for x in -6..=10 {
+                    let p = Vec3::new(x as f32, y as f32, z as f32) * 0.75 + 0.1;
+                    assert_eq!(node.distance(p), shape(p.x, p.y, p.z), "at {p}");
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn sdf_ron_files_load_as_nodes() {
+        use std::path::Path;
+
+        use bevy::asset::io::{
+            AssetSourceBuilder,
+            memory::{Dir, MemoryAssetReader},
+        };
+
+        use crate::{MarchingCubesPlugin, test_support::update_until};
+
+        let (node, _) = every_variant();
+        let dir = Dir::default();
+        dir.insert_asset_text(
+            Path::new("shape.sdf.ron"),
+            &ron::ser::to_string_pretty(&node, default()).unwrap(),
+        );
+        dir.insert_asset_text(
+            Path::new("example.sdf.ron"),
+            "SmoothUnion(k: 0.5, children: [
+                Translate(offset: (4.0, 4.0, 4.0), children: [Sphere(radius: 2.0)]),
+                Translate(offset: (4.0, 3.0, 4.0), children: [Torus(major_radius: 2.5, minor_radius: 0.5)]),
+            ])",
+        );
+
+        let mut app = App::new();
+        app.register_asset_source(
+            "memory",
+            AssetSourceBuilder::new(move || Box::new(MemoryAssetReader { root: dir.clone() })),
+        )
+        .add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            MarchingCubesPlugin::default(),
+        ))
+        .init_asset::<Mesh>();
+        let server = app.world().resource::<AssetServer>();
+        let shape: Handle<SdfNode> = server.load("memory://shape.sdf.ron");
+        let example: Handle<SdfNode> = server.load("memory://example.sdf.ron");
+        update_until(&mut app, |world| {
+            let assets = world.resource::<Assets<SdfNode>>();
+            assets.contains(&shape) && assets.contains(&example)
+        });
+
+        let assets = app.world().resource::<Assets<SdfNode>>();
+        assert_eq!(assets.get(&shape), Some(&node));
+        let SdfNode::Union { children } = node else {
+            unreachable!()
+        };
+        assert_eq!(assets.get(&example), Some(&children[0]));
+    }
+}
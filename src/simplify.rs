@@ -0,0 +1,546 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap},
+};
+
+use bevy::math::{DMat3, DVec3, Vec3};
+
+use crate::{
+    mesh::{GeneratedMesh, NormalMode},
+    types::{MaterialId, Value},
+};
+
+/// Collapses that turn any triangle by more than this, as the cosine between its old and
+/// new normals, are rejected so the mesh can't fold over itself.
+const MIN_NORMAL_COSINE: f64 = 0.2;
+
+/// How far [`GeneratedMesh::simplify`] decimates a mesh.
+///
+/// Collapsing stops at whichever limit is reached first:
+///
+/// ```rust,ignore
+/// // Down to a quarter of the triangles, however much the surface moves.
+/// Simplification { target_ratio: 0.25, max_error: f32::INFINITY };
+/// // As far as possible without moving the surface more than 0.05 units.
+/// Simplification { target_ratio: 0.0, max_error: 0.05 };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Simplification {
+    /// Fraction of the mesh's triangles to keep.
+    pub target_ratio: f32,
+    /// Largest distance a collapse may move the surface, in the mesh's units, as measured
+    /// by the quadric error of the merged vertex.
+    pub max_error: Value,
+}
+
+/// The quadric error of a vertex: the sum of squared distances from a point to the planes
+/// of the triangles around it, as `pᵀ A p + 2 bᵀ p + c`.
+#[derive(Clone, Copy)]
+struct Quadric {
+    a: DMat3,
+    b: DVec3,
+    c: f64,
+}
+
+impl Quadric {
+    const ZERO: Self = Self {
+        a: DMat3::ZERO,
+        b: DVec3::ZERO,
+        c: 0.0,
+    };
+
+    /// Returns the quadric of the plane through `point` with unit `normal`.
+    fn plane(normal: DVec3, point: DVec3) -> Self {
+        let d = -normal.dot(point);
+        Self {
+            a: DMat3::from_cols(normal * normal.x, normal * normal.y, normal * normal.z),
+            b: normal * d,
+            c: d * d,
+        }
+    }
+
+    fn error(&self, p: DVec3) -> f64 {
+        (p.dot(self.a * p) + 2.0 * self.b.dot(p) + self.c).max(0.0)
+    }
+
+    /// Returns the point minimising the error, if it is unique.
+    fn minimum(&self) -> Option<DVec3> {
+        let scale = self.a.x_axis.x + self.a.y_axis.y + self.a.z_axis.z;
+        (self.a.determinant().abs() > 1e-6 * scale * scale * scale)
+            .then(|| -(self.a.inverse() * self.b))
+    }
+}
+
+impl std::ops::Add for Quadric {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            a: self.a + other.a,
+            b: self.b + other.b,
+            c: self.c + other.c,
+        }
+    }
+}
+
+/// A candidate collapse in the queue, ordered by cost.
+///
+/// `versions` holds the versions of both vertices when it was queued; it is stale once
+/// either has changed.
+struct Collapse {
+    cost: f64,
+    edge: [u32; 2],
+    versions: [u32; 2],
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cost.total_cmp(&other.cost)
+    }
+}
+
+/// Mesh being decimated, welded by position.
+struct Decimator {
+    positions: Vec<DVec3>,
+    quadrics: Vec<Quadric>,
+    /// Vertices on the mesh's open boundary, which never move.
+    locked: Vec<bool>,
+    versions: Vec<u32>,
+    normals: Vec<[f32; 3]>,
+    colors: Vec<[f32; 4]>,
+    materials: Vec<MaterialId>,
+    triangles: Vec<[u32; 3]>,
+    removed: Vec<bool>,
+    /// Triangles using each vertex; may list removed triangles.
+    vertex_triangles: Vec<Vec<u32>>,
+}
+
+impl GeneratedMesh {
+    /// Decimates the mesh by quadric edge collapse, down to the limits in `settings`.
+    ///
+    /// Vertices are welded by position, then the cheapest edges are collapsed one by one,
+    /// each into the point that stays closest to the planes of the original triangles around
+    /// both its ends. Flat regions collapse to a few large triangles while curved ones keep
+    /// their detail. Collapses that would fold a triangle over, pinch the surface, or merge
+    /// vertices of different materials are skipped.
+    ///
+    /// Vertices on the mesh's open boundary never move, so a chunk's edges along its faces
+    /// still line up with its neighbours' meshes, simplified or not.
+    ///
+    /// Normals are then recomputed with `normal_mode`; gradient normals and colours are
+    /// interpolated along each collapsed edge. Call it before
+    /// [`compute_triplanar_uvs`](GeneratedMesh::compute_triplanar_uvs): UVs and tangents are
+    /// dropped.
+    pub fn simplify(&mut self, settings: &Simplification, normal_mode: NormalMode) {
+        let target = (self.tri_count() as f64 * settings.target_ratio.max(0.0) as f64) as usize;
+        let max_cost = (settings.max_error as f64).powi(2);
+        let mut decimator = Decimator::new(self);
+        decimator.run(target, max_cost);
+        *self = decimator.finish(normal_mode);
+    }
+}
+
+impl Decimator {
+    /// Welds `mesh` by position and builds each vertex's quadric.
+    fn new(mesh: &GeneratedMesh) -> Self {
+        let mut welded: HashMap<[u32; 3], u32> = HashMap::new();
+        let mut remap = Vec::with_capacity(mesh.vertices.len());
+        let mut decimator = Self {
+            positions: Vec::new(),
+            quadrics: Vec::new(),
+            locked: Vec::new(),
+            versions: Vec::new(),
+            normals: Vec::new(),
+            colors: Vec::new(),
+            materials: Vec::new(),
+            triangles: Vec::new(),
+            removed: Vec::new(),
+            vertex_triangles: Vec::new(),
+        };
+        for (i, position) in mesh.vertices.iter().enumerate() {
+            let key = position.map(f32::to_bits);
+            let index = *welded.entry(key).or_insert_with(|| {
+                decimator.positions.push(Vec3::from(*position).as_dvec3());
+                decimator.normals.extend(mesh.normals.get(i));
+                decimator.colors.extend(mesh.colors.get(i));
+                decimator.materials.extend(own_material(mesh, i));
+                (decimator.positions.len() - 1) as u32
+            });
+            remap.push(index);
+        }
+        let vertex_count = decimator.positions.len();
+        decimator.quadrics = vec![Quadric::ZERO; vertex_count];
+        decimator.versions = vec![0; vertex_count];
+        decimator.vertex_triangles = vec![Vec::new(); vertex_count];
+
+        let mut edge_uses: HashMap<[u32; 2], u32> = HashMap::new();
+        for corners in mesh.indices.chunks_exact(3) {
+            let triangle = [0, 1, 2].map(|k| remap[corners[k] as usize]);
+            if triangle[0] == triangle[1]
+                || triangle[1] == triangle[2]
+                || triangle[0] == triangle[2]
+            {
+                continue;
+            }
+            let index = decimator.triangles.len() as u32;
+            decimator.triangles.push(triangle);
+            decimator.removed.push(false);
+            if let Some(normal) = decimator.normal(triangle, None) {
+                let plane = Quadric::plane(normal, decimator.positions[triangle[0] as usize]);
+                for v in triangle {
+                    decimator.quadrics[v as usize] = decimator.quadrics[v as usize] + plane;
+                }
+            }
+            for k in 0..3 {
+                decimator.vertex_triangles[triangle[k] as usize].push(index);
+                *edge_uses
+                    .entry(undirected(triangle[k], triangle[(k + 1) % 3]))
+                    .or_default() += 1;
+            }
+        }
+
+        decimator.locked = vec![false; vertex_count];
+        for (edge, uses) in edge_uses {
+            if uses == 1 {
+                for v in edge {
+                    decimator.locked[v as usize] = true;
+                }
+            }
+        }
+        decimator
+    }
+
+    /// Collapses edges in order of cost until at most `target` triangles remain or the next
+    /// collapse would cost more than `max_cost`.
+    fn run(&mut self, target: usize, max_cost: f64) {
+        let mut queue = BinaryHeap::new();
+        for t in 0..self.triangles.len() {
+            let triangle = self.triangles[t];
+            for k in 0..3 {
+                let (a, b) = (triangle[k], triangle[(k + 1) % 3]);
+                // Each interior edge is queued once, from the triangle where it runs upwards.
+                if a < b || !self.has_edge(b, a) {
+                    self.queue(&mut queue, a, b);
+                }
+            }
+        }
+
+        let mut triangle_count = self.triangles.len();
+        while triangle_count > target {
+            let Some(Reverse(collapse)) = queue.pop() else {
+                break;
+            };
+            if collapse.cost > max_cost {
+                break;
+            }
+            let [a, b] = collapse.edge;
+            if collapse.versions != [self.versions[a as usize], self.versions[b as usize]] {
+                continue;
+            }
+            let Some((position, _)) = self.target(a, b) else {
+                continue;
+            };
+            if !self.can_collapse(a, b, position) {
+                continue;
+            }
+            triangle_count -= self.collapse(a, b, position);
+            let mut neighbours: Vec<u32> = self.neighbours(a).collect();
+            neighbours.sort_unstable();
+            neighbours.dedup();
+            for n in neighbours {
+                self.queue(&mut queue, a, n);
+            }
+        }
+    }
+
+    /// Returns `true` if `b` → `a` is an edge of a live triangle.
+    fn has_edge(&self, a: u32, b: u32) -> bool {
+        self.vertex_triangles[a as usize].iter().any(|&t| {
+            let triangle = self.triangles[t as usize];
+            !self.removed[t as usize]
+                && (0..3).any(|k| triangle[k] == a && triangle[(k + 1) % 3] == b)
+        })
+    }
+
+    /// Queues the collapse of the edge between `a` and `b`, if either may move.
+    fn queue(&self, queue: &mut BinaryHeap<Reverse<Collapse>>, a: u32, b: u32) {
+        if let Some((_, cost)) = self.target(a, b) {
+            queue.push(Reverse(Collapse {
+                cost,
+                edge: [a, b],
+                versions: [self.versions[a as usize], self.versions[b as usize]],
+            }));
+        }
+    }
+
+    /// Returns where collapsing `a` and `b` puts the merged vertex, and the error there.
+    fn target(&self, a: u32, b: u32) -> Option<(DVec3, f64)> {
+        let (ai, bi) = (a as usize, b as usize);
+        if self.locked[ai] && self.locked[bi] || self.materials.get(ai) != self.materials.get(bi) {
+            return None;
+        }
+        let quadric = self.quadrics[ai] + self.quadrics[bi];
+        let (pa, pb) = (self.positions[ai], self.positions[bi]);
+        let candidates = if self.locked[ai] {
+            vec![pa]
+        } else if self.locked[bi] {
+            vec![pb]
+        } else {
+            // The minimum is only trusted near the edge; nearly flat quadrics can put it far
+            // away.
+            let midpoint = (pa + pb) * 0.5;
+            let mut candidates = vec![pa, pb, midpoint];
+            candidates.extend(
+                quadric
+                    .minimum()
+                    .filter(|&p| p.distance(midpoint) <= pa.distance(pb)),
+            );
+            candidates
+        };
+        candidates
+            .into_iter()
+            .map(|p| (p, quadric.error(p)))
+            .min_by(|x, y| x.1.total_cmp(&y.1))
+    }
+
+    /// Returns `true` if merging `a` and `b` at `position` keeps the mesh manifold and
+    /// turns no triangle over.
+    fn can_collapse(&self, a: u32, b: u32, position: DVec3) -> bool {
+        // Link condition: the ends may only share the neighbours across their shared
+        // triangles, otherwise the surface would pinch.
+        let shared_triangles = self
+            .live(a)
+            .filter(|&t| self.triangles[t as usize].contains(&b))
+            .count();
+        let mut around_a: Vec<u32> = self.neighbours(a).collect();
+        around_a.sort_unstable();
+        around_a.dedup();
+        let mut around_b: Vec<u32> = self.neighbours(b).collect();
+        around_b.sort_unstable();
+        around_b.dedup();
+        let shared_neighbours = around_a
+            .iter()
+            .filter(|n| around_b.binary_search(n).is_ok())
+            .count();
+        if shared_neighbours != shared_triangles {
+            return false;
+        }
+
+        self.live(a).chain(self.live(b)).all(|t| {
+            let triangle = self.triangles[t as usize];
+            if triangle.contains(&a) && triangle.contains(&b) {
+                return true;
+            }
+            let moved = if triangle.contains(&a) { a } else { b };
+            let (Some(before), Some(after)) = (
+                self.normal(triangle, None),
+                self.normal(triangle, Some((moved, position))),
+            ) else {
+                return false;
+            };
+            before.dot(after) >= MIN_NORMAL_COSINE
+        })
+    }
+
+    /// Merges `b` into `a` at `position`, returning the number of triangles removed.
+    fn collapse(&mut self, a: u32, b: u32, position: DVec3) -> usize {
+        let (ai, bi) = (a as usize, b as usize);
+        let (pa, pb) = (self.positions[ai], self.positions[bi]);
+        let along = pb - pa;
+        let t = (position - pa).dot(along) / along.length_squared().max(f64::MIN_POSITIVE);
+        let t = t.clamp(0.0, 1.0) as f32;
+        if !self.normals.is_empty() {
+            let (na, nb) = (Vec3::from(self.normals[ai]), Vec3::from(self.normals[bi]));
+            self.normals[ai] = na.lerp(nb, t).normalize_or_zero().to_array();
+        }
+        if !self.colors.is_empty() {
+            let (ca, cb) = (self.colors[ai], self.colors[bi]);
+            self.colors[ai] = [0, 1, 2, 3].map(|k| ca[k] + (cb[k] - ca[k]) * t);
+        }
+        self.positions[ai] = position;
+        self.quadrics[ai] = self.quadrics[ai] + self.quadrics[bi];
+        self.locked[ai] |= self.locked[bi];
+        self.versions[ai] += 1;
+        self.versions[bi] += 1;
+
+        let mut removed = 0;
+        for t in std::mem::take(&mut self.vertex_triangles[bi]) {
+            if self.removed[t as usize] {
+                continue;
+            }
+            let triangle = &mut self.triangles[t as usize];
+            if triangle.contains(&a) {
+                self.removed[t as usize] = true;
+                removed += 1;
+                continue;
+            }
+            for v in triangle.iter_mut().filter(|v| **v == b) {
+                *v = a;
+            }
+            self.vertex_triangles[ai].push(t);
+        }
+        let removed_flags = &self.removed;
+        self.vertex_triangles[ai].retain(|&t| !removed_flags[t as usize]);
+        removed
+    }
+
+    /// Returns the live triangles using `v`.
+    fn live(&self, v: u32) -> impl Iterator<Item = u32> + '_ {
+        self.vertex_triangles[v as usize]
+            .iter()
+            .copied()
+            .filter(|&t| !self.removed[t as usize])
+    }
+
+    /// Returns the vertices sharing a live triangle with `v`, possibly repeated.
+    fn neighbours(&self, v: u32) -> impl Iterator<Item = u32> + '_ {
+        self.live(v)
+            .flat_map(|t| self.triangles[t as usize])
+            .filter(move |&n| n != v)
+    }
+
+    /// Returns the unit normal of `triangle`, optionally with one vertex moved, or `None`
+    /// if it is degenerate.
+    fn normal(&self, triangle: [u32; 3], moved: Option<(u32, DVec3)>) -> Option<DVec3> {
+        let [a, b, c] = triangle.map(|v| match moved {
+            Some((m, p)) if m == v => p,
+            _ => self.positions[v as usize],
+        });
+        (b - a).cross(c - a).try_normalize()
+    }
+
+    /// Compacts the remaining vertices and triangles into a mesh with `normal_mode`.
+    fn finish(self, normal_mode: NormalMode) -> GeneratedMesh {
+        let mut remap = vec![u32::MAX; self.positions.len()];
+        let mut vertices = Vec::new();
+        let mut normals = Vec::new();
+        let mut colors = Vec::new();
+        let mut materials = Vec::new();
+        let mut indices = Vec::new();
+        for (triangle, _) in self
+            .triangles
+            .iter()
+            .zip(&self.removed)
+            .filter(|(_, removed)| !**removed)
+        {
+            for &v in triangle {
+                let v = v as usize;
+                if remap[v] == u32::MAX {
+                    remap[v] = vertices.len() as u32;
+                    vertices.push(self.positions[v].as_vec3().to_array());
+                    normals.extend(self.normals.get(v));
+                    colors.extend(self.colors.get(v));
+                    materials.extend(self.materials.get(v));
+                }
+                indices.push(remap[v]);
+            }
+        }
+        if normal_mode != NormalMode::Gradient {
+            normals.clear();
+        }
+        GeneratedMesh::build(vertices, indices, normals, colors, materials, normal_mode)
+    }
+}
+
+/// Returns the material vertex `i` of `mesh` carries itself: the one with full weight.
+fn own_material(mesh: &GeneratedMesh, i: usize) -> Option<MaterialId> {
+    let (ids, weights) = (mesh.material_ids.get(i)?, mesh.material_weights.get(i)?);
+    let slot = (0..4).find(|&slot| weights[slot] >= 1.0).unwrap_or(0);
+    Some((ids >> (slot * 8)) as MaterialId)
+}
+
+/// Returns the edge between `a` and `b` with its lower vertex first.
+fn undirected(a: u32, b: u32) -> [u32; 2] {
+    [a.min(b), a.max(b)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chunk::Chunk,
+        mesh::MeshingAlgorithm,
+        sdf,
+        test_support::{is_closed, mesh_standalone},
+    };
+
+    /// Fills a 16³ chunk at `offset` with `shape` and meshes it with marching cubes.
+    fn mesh(shape: &impl sdf::Sdf, offset: Vec3) -> GeneratedMesh {
+        let mut chunk = Chunk::new(16, 16, 16);
+        chunk.fill(&|x, y, z| shape(x + offset.x, y + offset.y, z + offset.z));
+        mesh_standalone(
+            &chunk,
+            MeshingAlgorithm::MarchingCubes,
+            NormalMode::AreaWeighted,
+        )
+    }
+
+    /// Returns the sorted positions of the vertices with the given `x`.
+    fn face_vertices(mesh: &GeneratedMesh, x: f32) -> Vec<[u32; 3]> {
+        let mut vertices: Vec<[u32; 3]> = mesh
+            .vertices
+            .iter()
+            .filter(|v| v[0] == x)
+            .map(|v| [v[0] - x, v[1], v[2]].map(f32::to_bits))
+            .collect();
+        vertices.sort_unstable();
+        vertices.dedup();
+        vertices
+    }
+
+    #[test]
+    fn simplify_keeps_shared_face_vertices() {
+        let shape = sdf::translate(sdf::sphere(9.3), Vec3::new(16.2, 7.9, 8.1));
+        let mut left = mesh(&shape, Vec3::ZERO);
+        let mut right = mesh(&shape, Vec3::new(16.0, 0.0, 0.0));
+        let seam = face_vertices(&left, 16.0);
+        assert!(!seam.is_empty());
+        assert_eq!(face_vertices(&right, 0.0), seam);
+
+        let settings = Simplification {
+            target_ratio: 0.1,
+            max_error: f32::INFINITY,
+        };
+        let before = left.tri_count() + right.tri_count();
+        left.simplify(&settings, NormalMode::AreaWeighted);
+        right.simplify(&settings, NormalMode::AreaWeighted);
+        assert!(left.tri_count() + right.tri_count() < before / 2);
+        assert_eq!(face_vertices(&left, 16.0), seam);
+        assert_eq!(face_vertices(&right, 0.0), seam);
+    }
+
+    #[test]
+    fn simplify_keeps_closed_meshes_closed() {
+        let shape = sdf::translate(sdf::torus(5.0, 2.1), Vec3::new(8.1, 7.9, 8.2));
+        for target_ratio in [0.5, 0.25, 0.1, 0.0] {
+            let mut torus = mesh(&shape, Vec3::ZERO);
+            assert!(is_closed(&[(&torus, Vec3::ZERO)], true));
+            torus.simplify(
+                &Simplification {
+                    target_ratio,
+                    max_error: f32::INFINITY,
+                },
+                NormalMode::AreaWeighted,
+            );
+            assert!(torus.tri_count() > 0);
+            assert!(
+                is_closed(&[(&torus, Vec3::ZERO)], true),
+                "open at ratio {target_ratio}"
+            );
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+
+use crate::{
+    generator::ChunkRequest,
+    lod::ChunkLods,
+    neighbours::{ChunkCoord, ChunkMap},
+    plugin::MarchingCubesSet,
+    types::Value,
+};
+
+/// Loads chunks around the entity it is attached to, usually the camera or the player.
+///
+/// Radii are measured in chunks from the entity's [`GlobalTransform`] to each chunk's
+/// centre. Chunks load once they come within `load_radius` of any streamer and unload once
+/// they are further than `unload_radius` from all of them. Keeping `unload_radius` above
+/// `load_radius` stops chunks on the boundary from loading and unloading every frame as the
+/// streamer moves back and forth.
+#[derive(Component, Debug, Clone, Copy)]
+#[require(Transform)]
+pub struct ChunkStreamer {
+    /// Distance within which chunks are loaded. Default: `4.0`.
+    pub load_radius: f32,
+    /// Distance beyond which chunks are unloaded. Values below `load_radius` are treated as
+    /// `load_radius`. Default: `5.0`.
+    pub unload_radius: f32,
+}
+
+impl Default for ChunkStreamer {
+    fn default() -> Self {
+        Self {
+            load_radius: 4.,
+            unload_radius: 5.,
+        }
+    }
+}
+
+impl ChunkStreamer {
+    /// Creates a streamer with the given radii, in chunks.
+    pub fn new(load_radius: f32, unload_radius: f32) -> Self {
+        Self {
+            load_radius,
+            unload_radius,
+        }
+    }
+}
+
+/// Marker for chunks spawned by [`ChunkStreamingPlugin`].
+///
+/// Only chunks with this marker are unloaded; chunks you spawn yourself are left alone even
+/// if they carry a [`ChunkCoord`]. Streamed chunks don't get a material, so add one when
+/// they appear:
+///
+/// ```rust,ignore
+/// fn add_material(
+///     mut commands: Commands,
+///     terrain: Res<TerrainMaterial>,
+///     added: Query<Entity, Added<StreamedChunk>>,
+/// ) {
+///     for entity in &added {
+///         commands.entity(entity).insert(MeshMaterial3d(terrain.0.clone()));
+///     }
+/// }
+/// ```
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct StreamedChunk;
+
+/// Runtime configuration for chunk streaming.
+///
+/// Inserted as a resource by [`ChunkStreamingPlugin`]. Changing the chunk layout only
+/// affects chunks loaded afterwards.
+#[derive(Resource, Clone)]
+pub struct ChunkStreamingConfig {
+    /// Number of voxels along each axis of a chunk. Default: `32 × 32 × 32`.
+    pub chunk_size: UVec3,
+    /// World-space size of each voxel edge. Default: `1.0`.
+    pub scale: Value,
+    /// Iso-surface threshold of streamed chunks. Default: `0.0`.
+    pub threshold: Value,
+    /// Chunk Y coordinates that may be loaded. Use `0..=0` for a single layer of terrain
+    /// columns. Default: unbounded.
+    pub y_range: RangeInclusive<i32>,
+    /// Maximum number of [`ChunkRequest`]s spawned per frame, nearest first. Generation is
+    /// further throttled by
+    /// [`MarchingCubesConfig::max_tasks_per_frame`](crate::MarchingCubesConfig::max_tasks_per_frame).
+    /// Default: `8`.
+    pub max_loads_per_frame: usize,
+}
+
+impl ChunkStreamingConfig {
+    /// Returns the world-space size of one chunk.
+    pub fn chunk_extent(&self) -> Vec3 {
+        self.chunk_size.as_vec3() * self.scale
+    }
+}
+
+/// Bevy plugin that streams chunks in and out around every [`ChunkStreamer`].
+///
+/// Add it alongside a [`MarchingCubesPlugin`](crate::MarchingCubesPlugin) that has a
+/// [`ChunkGenerator`](crate::ChunkGenerator), which fills the streamed chunks:
+///
+/// ```rust,ignore
+/// app.add_plugins((
+///     MarchingCubesPlugin::default().with_generator(MyTerrain::default()),
+///     ChunkStreamingPlugin::default().with_y_range(0..=0),
+/// ));
+/// commands.spawn((Camera3d::default(), ChunkStreamer::default()));
+/// ```
+///
+/// ```text
+/// chunk comes within load_radius
+///   → entity spawned with ChunkRequest + ChunkCoord + StreamedChunk
+///   → [generated and meshed by MarchingCubesPlugin]
+/// chunk moves past unload_radius
+///   → entity despawned, mesh asset removed
+/// ```
+///
+/// Streaming only runs while at least one [`ChunkStreamer`] exists; removing all of them
+/// leaves the loaded chunks in place.
+pub struct ChunkStreamingPlugin {
+    /// Initial value for [`ChunkStreamingConfig::chunk_size`].
+    pub chunk_size: UVec3,
+    /// Initial value for [`ChunkStreamingConfig::scale`].
+    pub scale: Value,
+    /// Initial value for [`ChunkStreamingConfig::threshold`].
+    pub threshold: Value,
+    /// Initial value for [`ChunkStreamingConfig::y_range`].
+    pub y_range: RangeInclusive<i32>,
+    /// Initial value for [`ChunkStreamingConfig::max_loads_per_frame`].
+    pub max_loads_per_frame: usize,
+}
+
+impl Default for ChunkStreamingPlugin {
+    fn default() -> Self {
+        Self {
+            chunk_size: UVec3::splat(32),
+            scale: 1.,
+            threshold: 0.,
+            y_range: i32::MIN..=i32::MAX,
+            max_loads_per_frame: 8,
+        }
+    }
+}
+
+impl ChunkStreamingPlugin {
+    /// Sets the number of voxels along each axis of a chunk.
+    pub fn with_chunk_size(mut self, chunk_size: UVec3) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Sets the world-space size of each voxel edge.
+    pub fn with_scale(mut self, scale: Value) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the iso-surface threshold of streamed chunks.
+    pub fn with_threshold(mut self, threshold: Value) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Limits which chunk Y coordinates may be loaded.
+    pub fn with_y_range(mut self, y_range: RangeInclusive<i32>) -> Self {
+        self.y_range = y_range;
+        self
+    }
+}
+
+impl Plugin for ChunkStreamingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ChunkStreamingConfig {
+            chunk_size: self.chunk_size,
+            scale: self.scale,
+            threshold: self.threshold,
+            y_range: self.y_range.clone(),
+            max_loads_per_frame: self.max_loads_per_frame,
+        })
+        .init_resource::<ChunkMap>()
+        .add_systems(
+            Update,
+            (unload_chunks, load_chunks)
+                .chain()
+                .before(MarchingCubesSet::Spawn),
+        );
+    }
+}
+
+/// Returns each streamer's position in chunk units.
+fn streamer_positions(
+    streamers: &Query<(&GlobalTransform, &ChunkStreamer)>,
+    extent: Vec3,
+) -> Vec<(Vec3, ChunkStreamer)> {
+    streamers
+        .iter()
+        .map(|(transform, streamer)| (transform.translation() / extent, *streamer))
+        .collect()
+}
+
+/// Returns the distance, in chunks, from `position` to the centre of the chunk at `coord`.
+#[inline]
+fn chunk_distance(position: Vec3, coord: IVec3) -> f32 {
+    position.distance(coord.as_vec3() + Vec3::splat(0.5))
+}
+
+/// Despawns streamed chunks that are beyond every streamer's unload radius, together with
+/// their mesh assets, including every level of detail.
+fn unload_chunks(
+    mut commands: Commands,
+    config: Res<ChunkStreamingConfig>,
+    streamers: Query<(&GlobalTransform, &ChunkStreamer)>,
+    chunks: Query<(Entity, &ChunkCoord, Option<&Mesh3d>, Option<&ChunkLods>), With<StreamedChunk>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let positions = streamer_positions(&streamers, config.chunk_extent());
+    if positions.is_empty() {
+        return;
+    }
+
+    for (entity, coord, mesh, lods) in chunks.iter() {
+        let in_range = positions.iter().any(|(position, streamer)| {
+            chunk_distance(*position, **coord) <= streamer.unload_radius.max(streamer.load_radius)
+        });
+        if in_range {
+            continue;
+        }
+        if let Some(mesh) = mesh {
+            meshes.remove(&mesh.0);
+        }
+        for mesh in lods.iter().flat_map(|lods| &lods.meshes) {
+            meshes.remove(mesh);
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Spawns a [`ChunkRequest`] for each missing chunk within a streamer's load radius,
+/// nearest first.
+fn load_chunks(
+    mut commands: Commands,
+    config: Res<ChunkStreamingConfig>,
+    map: Res<ChunkMap>,
+    streamers: Query<(&GlobalTransform, &ChunkStreamer)>,
+) {
+    let extent = config.chunk_extent();
+    let mut missing: Vec<(f32, IVec3)> = Vec::new();
+    for (position, streamer) in streamer_positions(&streamers, extent) {
+        let reach = streamer.load_radius.ceil() as i32;
+        let centre = position.floor().as_ivec3();
+        for z in -reach..=reach {
+            for y in -reach..=reach {
+                for x in -reach..=reach {
+                    let coord = centre + IVec3::new(x, y, z);
+                    if !config.y_range.contains(&coord.y) || map.contains(coord) {
+                        continue;
+                    }
+                    let distance = chunk_distance(position, coord);
+                    if distance <= streamer.load_radius {
+                        missing.push((distance, coord));
+                    }
+                }
+            }
+        }
+    }
+
+    // Several streamers can want the same chunk; keep its nearest distance.
+    missing.sort_by(|a, b| (a.1.to_array().cmp(&b.1.to_array())).then_with(|| a.0.total_cmp(&b.0)));
+    missing.dedup_by_key(|(_, coord)| *coord);
+    missing.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    for (_, coord) in missing.into_iter().take(config.max_loads_per_frame) {
+        let request = ChunkRequest::new(coord, config.chunk_size, config.scale)
+            .with_threshold(config.threshold);
+        commands.spawn((StreamedChunk, request.bundle()));
+    }
+}
@@ -0,0 +1,425 @@
+use bevy::math::Vec3;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{
+    dual_contouring::Qef,
+    grid::VoxelGrid,
+    interp::{find_t, interpolate_colors, interpolate_points},
+    mesh::{GeneratedMesh, NormalMode},
+    neighbours::ChunkPadding,
+    types::{MaterialId, Value},
+    utils::{edge_t, normalize_or_zero},
+};
+
+/// Marks cells without a vertex in the cell → vertex lookup.
+const NO_VERTEX: u32 = u32::MAX;
+
+/// A cell's vertex, before vertices are numbered.
+struct CellVertex {
+    position: [f32; 3],
+    /// Gradient normal; only set for [`NormalMode::Gradient`].
+    normal: Option<[f32; 3]>,
+    /// Averaged colour; only set if the chunk has colours.
+    color: Option<[f32; 4]>,
+    /// Material; only set if the chunk has materials.
+    material: Option<MaterialId>,
+}
+
+/// Runs surface nets over the given voxel grid.
+///
+/// ```text
+/// 1. Every cell whose corners aren't all on one side of the threshold gets one vertex, at
+///    the average of the points where the surface crosses its edges.
+/// 2. Every grid edge crossed by the surface gets a quad joining the vertices of the four
+///    cells around it, split along its shorter diagonal.
+/// ```
+///
+/// Cells are indexed by their minimum corner. Quads around edges on the chunk's negative
+/// faces belong to the neighbour on that side; those around edges on the positive faces
+/// use cells past the chunk, sampled from `padding`. Cells without padding get no vertex,
+/// so their quads are skipped.
+///
+/// Vertices take the colours interpolated at each edge crossing, averaged, and the
+/// material of the cell's deepest inside corner. Gradient normals come from the trilinear
+/// interpolation of the cell's corners, so the cells shared with a neighbour get the same
+/// normal in both chunks.
+pub(crate) fn run_surface_nets(
+    size_x: usize,
+    size_y: usize,
+    size_z: usize,
+    scale: Value,
+    threshold: Value,
+    normal_mode: NormalMode,
+    values: &VoxelGrid,
+    materials: Option<&VoxelGrid<MaterialId>>,
+    colors: Option<&VoxelGrid<[f32; 4]>>,
+    padding: &ChunkPadding,
+) -> GeneratedMesh {
+    run_dual(
+        [size_x, size_y, size_z],
+        scale,
+        threshold,
+        normal_mode,
+        values,
+        materials,
+        colors,
+        padding,
+        VertexPlacement::MassPoint,
+    )
+}
+
+/// Where a dual mesher puts the vertex of each cell.
+#[derive(Clone, Copy)]
+pub(crate) enum VertexPlacement<'a> {
+    /// The average of the cell's edge crossings.
+    MassPoint,
+    /// The minimiser of the [`Qef`] built from the cell's Hermite data, using `normals` where
+    /// given and the trilinear gradient of the cell otherwise.
+    Qef {
+        normals: Option<&'a VoxelGrid<[f32; 3]>>,
+    },
+}
+
+/// Meshes a voxel grid with one vertex per crossed cell, placed by `placement`.
+///
+/// Shared by [`run_surface_nets`] and
+/// [`run_dual_contouring`](crate::dual_contouring::run_dual_contouring); see the former for
+/// how cells and quads are laid out.
+pub(crate) fn run_dual(
+    size: [usize; 3],
+    scale: Value,
+    threshold: Value,
+    normal_mode: NormalMode,
+    values: &VoxelGrid,
+    materials: Option<&VoxelGrid<MaterialId>>,
+    colors: Option<&VoxelGrid<[f32; 4]>>,
+    padding: &ChunkPadding,
+    placement: VertexPlacement,
+) -> GeneratedMesh {
+    let [size_x, size_y, size_z] = size;
+    let cells = size.map(|s| s + 1);
+    let cell_index = |[x, y, z]: [usize; 3]| (z * cells[1] + y) * cells[0] + x;
+
+    let per_z: Vec<Vec<(usize, CellVertex)>> = (0..cells[2])
+        .into_par_iter()
+        .map(|z| {
+            let mut slab = Vec::new();
+            for y in 0..cells[1] {
+                for x in 0..cells[0] {
+                    let cell = [x, y, z];
+                    let vertex = cell_vertex(
+                        cell,
+                        scale,
+                        threshold,
+                        normal_mode,
+                        values,
+                        materials,
+                        colors,
+                        padding,
+                        placement,
+                    );
+                    slab.extend(vertex.map(|vertex| (cell_index(cell), vertex)));
+                }
+            }
+            slab
+        })
+        .collect();
+
+    let mut cell_vertices = vec![NO_VERTEX; cells.iter().product()];
+    let mut vertices: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut vertex_colors: Vec<[f32; 4]> = Vec::new();
+    let mut vertex_materials: Vec<MaterialId> = Vec::new();
+    for (cell, vertex) in per_z.into_iter().flatten() {
+        cell_vertices[cell] = vertices.len() as u32;
+        vertices.push(vertex.position);
+        normals.extend(vertex.normal);
+        vertex_colors.extend(vertex.color);
+        vertex_materials.extend(vertex.material);
+    }
+
+    let per_z: Vec<Vec<u32>> = (0..=size_z)
+        .into_par_iter()
+        .map(|z| {
+            let mut indices = Vec::new();
+            for y in 0..=size_y {
+                for x in 0..=size_x {
+                    let p = [x, y, z];
+                    let inside = values.get(x, y, z) <= threshold;
+                    for axis in 0..3 {
+                        let (b, c) = ((axis + 1) % 3, (axis + 2) % 3);
+                        if p[axis] == size[axis] || p[b] == 0 || p[c] == 0 {
+                            continue;
+                        }
+                        let mut q = p;
+                        q[axis] += 1;
+                        if (values.get(q[0], q[1], q[2]) <= threshold) == inside {
+                            continue;
+                        }
+
+                        // Cells around the edge, counter-clockwise seen from +axis.
+                        let around = |db: usize, dc: usize| {
+                            let mut cell = p;
+                            cell[b] = cell[b] + db - 1;
+                            cell[c] = cell[c] + dc - 1;
+                            cell_vertices[cell_index(cell)]
+                        };
+                        let mut quad = [around(0, 0), around(1, 0), around(1, 1), around(0, 1)];
+                        if quad.contains(&NO_VERTEX) {
+                            continue;
+                        }
+                        // The surface faces away from the inside end of the edge.
+                        if !inside {
+                            quad.reverse();
+                        }
+                        indices.extend(split_quad(quad, &vertices));
+                    }
+                }
+            }
+            indices
+        })
+        .collect();
+
+    GeneratedMesh::build(
+        vertices,
+        per_z.concat(),
+        normals,
+        vertex_colors,
+        vertex_materials,
+        normal_mode,
+    )
+}
+
+/// Builds the vertex of `cell`, or returns `None` if the surface doesn't pass through it or
+/// one of its corners isn't available.
+///
+/// Corners are numbered `x | y << 1 | z << 2` from the cell's minimum corner.
+fn cell_vertex(
+    cell: [usize; 3],
+    scale: Value,
+    threshold: Value,
+    normal_mode: NormalMode,
+    values: &VoxelGrid,
+    materials: Option<&VoxelGrid<MaterialId>>,
+    colors: Option<&VoxelGrid<[f32; 4]>>,
+    padding: &ChunkPadding,
+    placement: VertexPlacement,
+) -> Option<CellVertex> {
+    let corner = |i: usize| [0, 1, 2].map(|axis| cell[axis] + (i >> axis & 1));
+    let mut corner_values = [0.0; 8];
+    for (i, value) in corner_values.iter_mut().enumerate() {
+        let [x, y, z] = corner(i);
+        *value = padding.value(values, x, y, z)?;
+    }
+    let inside = corner_values.map(|v| v <= threshold);
+    if inside.iter().all(|&i| i == inside[0]) {
+        return None;
+    }
+
+    // Positions are in cell units from the cell's minimum corner until the end.
+    let offset_of = |i: usize| [0, 1, 2].map(|axis| (i >> axis & 1) as f32);
+    let color_of = |colors: &VoxelGrid<[f32; 4]>, i: usize| {
+        let [x, y, z] = corner(i);
+        padding.color(colors, x, y, z)
+    };
+    let mut mass_point = [0.0; 3];
+    let mut color = [0.0; 4];
+    let mut crossings = 0;
+    let mut qef = Qef::default();
+    let mut normal_sum = Vec3::ZERO;
+    for a in 0..8 {
+        for axis in 0..3 {
+            let b = a | 1 << axis;
+            if a == b || inside[a] == inside[b] {
+                continue;
+            }
+            let t = match placement {
+                VertexPlacement::MassPoint => edge_t(corner_values[a], corner_values[b], threshold),
+                // Hermite data needs the actual crossing, whatever the vertex interpolation.
+                VertexPlacement::Qef { .. } => {
+                    find_t(corner_values[a], corner_values[b], threshold)
+                }
+            };
+            let crossing = interpolate_points(offset_of(a), offset_of(b), t);
+            for (sum, p) in mass_point.iter_mut().zip(crossing) {
+                *sum += p;
+            }
+            if let Some(colors) = colors {
+                let crossing = interpolate_colors(color_of(colors, a), color_of(colors, b), t);
+                for (sum, c) in color.iter_mut().zip(crossing) {
+                    *sum += c;
+                }
+            }
+            if let VertexPlacement::Qef { normals } = placement {
+                let supplied = normals.map_or([0.0; 3], |normals| {
+                    let normal_of = |i: usize| {
+                        let [x, y, z] = corner(i);
+                        padding.normal(normals, x, y, z)
+                    };
+                    normalize_or_zero(interpolate_points(normal_of(a), normal_of(b), t))
+                });
+                let normal = if supplied == [0.0; 3] {
+                    normalize_or_zero(trilinear_gradient(&corner_values, crossing))
+                } else {
+                    supplied
+                };
+                qef.add(Vec3::from(crossing), Vec3::from(normal));
+                normal_sum += Vec3::from(normal);
+            }
+            crossings += 1;
+        }
+    }
+    let mass_point = mass_point.map(|p| p / crossings as f32);
+    let local = match placement {
+        VertexPlacement::MassPoint => mass_point,
+        // Clamped to the cell so vertices can't fold the mesh over its neighbours.
+        VertexPlacement::Qef { .. } => qef.solve().clamp(Vec3::ZERO, Vec3::ONE).to_array(),
+    };
+    let position = [0, 1, 2].map(|axis| (cell[axis] as f32 + local[axis]) * scale);
+
+    let normal = (normal_mode == NormalMode::Gradient).then(|| match placement {
+        VertexPlacement::MassPoint => normalize_or_zero(trilinear_gradient(&corner_values, local)),
+        VertexPlacement::Qef { .. } => normalize_or_zero(normal_sum.to_array()),
+    });
+    let material = materials.map(|materials| {
+        let deepest = (0..8)
+            .filter(|&i| inside[i])
+            .min_by(|&a, &b| corner_values[a].total_cmp(&corner_values[b]))
+            .expect("crossed cell has an inside corner");
+        let [x, y, z] = corner(deepest);
+        padding.material(materials, x, y, z)
+    });
+
+    Some(CellVertex {
+        position,
+        normal,
+        color: colors.map(|_| color.map(|c| c / crossings as f32)),
+        material,
+    })
+}
+
+/// Returns the gradient of the trilinear interpolation of `corners` at `f`, in cell units.
+///
+/// Corners are numbered `x | y << 1 | z << 2`, and `f` is the position within the cell,
+/// from `0` to `1` along each axis. Only the direction matters for normals.
+fn trilinear_gradient(corners: &[Value; 8], f: [f32; 3]) -> [f32; 3] {
+    let mut gradient = [0.0; 3];
+    for (axis, g) in gradient.iter_mut().enumerate() {
+        for (i, &value) in corners.iter().enumerate() {
+            if i >> axis & 1 == 0 {
+                continue;
+            }
+            let weight: f32 = (0..3)
+                .filter(|&other| other != axis)
+                .map(|other| {
+                    if i >> other & 1 == 1 {
+                        f[other]
+                    } else {
+                        1.0 - f[other]
+                    }
+                })
+                .product();
+            *g += (value - corners[i ^ 1 << axis]) * weight;
+        }
+    }
+    gradient
+}
+
+/// Splits a quad into two triangles along its shorter diagonal, keeping its winding.
+pub(crate) fn split_quad([a, b, c, d]: [u32; 4], vertices: &[[f32; 3]]) -> [u32; 6] {
+    let length_squared = |i: u32, j: u32| {
+        let (p, q) = (vertices[i as usize], vertices[j as usize]);
+        (0..3).map(|axis| (p[axis] - q[axis]).powi(2)).sum::<f32>()
+    };
+    if length_squared(a, c) <= length_squared(b, d) {
+        [a, b, c, a, c, d]
+    } else {
+        [a, b, d, b, c, d]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+    use crate::{
+        chunk::Chunk,
+        mesh::MeshingAlgorithm,
+        neighbours::{ChunkCoord, ChunkMap},
+        sdf,
+        test_support::{is_closed, mesh_standalone},
+        types::CompiledFunction,
+    };
+
+    #[test]
+    fn dual_meshes_of_closed_fields_are_closed() {
+        let torus = sdf::translate(sdf::torus(4.5, 1.7), Vec3::new(8.1, 7.9, 8.2));
+        let sphere = sdf::translate(sdf::sphere(5.3), Vec3::new(7.6, 8.3, 8.1));
+        for shape in [&torus as &CompiledFunction, &sphere] {
+            let mut chunk = Chunk::new(16, 16, 16);
+            chunk.fill(shape);
+            for algorithm in [
+                MeshingAlgorithm::SurfaceNets,
+                MeshingAlgorithm::DualContouring,
+            ] {
+                let mesh = mesh_standalone(&chunk, algorithm, NormalMode::AreaWeighted);
+                assert!(mesh.tri_count() > 0);
+                assert!(is_closed(&[(&mesh, Vec3::ZERO)], false), "{algorithm:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn padding_closes_seams_between_chunks() {
+        let sphere = sdf::translate(sdf::sphere(2.7), Vec3::new(8.0, 4.1, 4.2));
+        let mut world = World::new();
+        world.init_resource::<ChunkMap>();
+        let mut left = Chunk::new(8, 8, 8);
+        left.fill(&sphere);
+        let mut right = Chunk::new(8, 8, 8);
+        right.fill(&|x, y, z| sphere(x + 8.0, y, z));
+        let left = world.spawn((left, ChunkCoord(IVec3::ZERO))).id();
+        let right = world.spawn((right, ChunkCoord(IVec3::X))).id();
+
+        let (left, right) = (
+            world.get::<Chunk>(left).unwrap(),
+            world.get::<Chunk>(right).unwrap(),
+        );
+        let padding = ChunkPadding::gather(left, IVec3::ZERO, world.resource::<ChunkMap>(), |e| {
+            world.get::<Chunk>(e)
+        });
+        let offset = Vec3::new(8.0, 0.0, 0.0);
+        for (algorithm, placement) in [
+            (MeshingAlgorithm::SurfaceNets, VertexPlacement::MassPoint),
+            (
+                MeshingAlgorithm::DualContouring,
+                VertexPlacement::Qef { normals: None },
+            ),
+        ] {
+            let mesh = |padding: &ChunkPadding| {
+                run_dual(
+                    [8, 8, 8],
+                    1.0,
+                    0.0,
+                    NormalMode::AreaWeighted,
+                    left.values(),
+                    None,
+                    None,
+                    padding,
+                    placement,
+                )
+            };
+            let right = mesh_standalone(right, algorithm, NormalMode::AreaWeighted);
+            assert!(
+                is_closed(&[(&mesh(&padding), Vec3::ZERO), (&right, offset)], true),
+                "{algorithm:?}"
+            );
+            let unpadded = mesh(&ChunkPadding::default());
+            assert!(!is_closed(
+                &[(&unpadded, Vec3::ZERO), (&right, offset)],
+                true
+            ));
+        }
+    }
+}
@@ -14,6 +14,50 @@ pub const CORNER_POINT_INDICES: [[i8; 2]; 12] = [
     [3, 7],
 ];
 
+/// Maps each of the 12 edges to the grid corner it starts from and the axis it runs along.
+///
+/// Entries are `[dx, dy, dz, axis]`: the edge begins at voxel corner `(x + dx, y + dy, z + dz)`
+/// and extends one cell along `axis` (`0` = X, `1` = Y, `2` = Z). Neighbouring voxels that
+/// share an edge resolve to the same origin and axis, which is what lets them share a vertex.
+/// See [`edge_key`](crate::utils::edge_key).
+pub const EDGE_ORIGINS: [[usize; 4]; 12] = [
+    [0, 0, 0, 0],
+    [1, 0, 0, 1],
+    [0, 1, 0, 0],
+    [0, 0, 0, 1],
+    [0, 0, 1, 0],
+    [1, 0, 1, 1],
+    [0, 1, 1, 0],
+    [0, 0, 1, 1],
+    [0, 0, 0, 2],
+    [1, 0, 0, 2],
+    [1, 1, 0, 2],
+    [0, 1, 0, 2],
+];
+
+/// Like [`CORNER_POINT_INDICES`], but with each edge's endpoints listed from its
+/// [`EDGE_ORIGINS`] corner, the one nearest the grid origin.
+///
+/// Every voxel (and every neighbouring chunk) that shares an edge then interpolates along it
+/// in the same direction and computes a bit-identical vertex.
+pub(crate) const EDGE_ENDPOINTS: [[i8; 2]; 12] = {
+    // Corner number of each `[dx, dy]` offset on the bottom face; the top face adds 4.
+    const FACE_CORNERS: [[i8; 2]; 2] = [[0, 3], [1, 2]];
+    let mut endpoints = [[0; 2]; 12];
+    let mut edge = 0;
+    while edge < 12 {
+        let [dx, dy, dz, axis] = EDGE_ORIGINS[edge];
+        let mut end = [dx, dy, dz];
+        end[axis] += 1;
+        endpoints[edge] = [
+            FACE_CORNERS[dx][dy] + 4 * dz as i8,
+            FACE_CORNERS[end[0]][end[1]] + 4 * end[2] as i8,
+        ];
+        edge += 1;
+    }
+    endpoints
+};
+
 /// Maps a voxel state (0–255) to a 12-bit bitmask of intersected edges.
 ///
 /// Each bit corresponds to one edge (bits 0–11). A set bit means the iso-surface
@@ -0,0 +1,143 @@
+//! Helpers shared by the unit tests of several modules.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    chunk::Chunk,
+    mesh::{GeneratedMesh, MeshingAlgorithm, NormalMode},
+    neighbours::{ChunkHalo, ChunkPadding},
+    plugin::run_meshing,
+    transvoxel::TransitionSamples,
+    types::Value,
+};
+
+/// Meshes `chunk` on its own, without neighbours.
+pub(crate) fn mesh_standalone(
+    chunk: &Chunk,
+    algorithm: MeshingAlgorithm,
+    normal_mode: NormalMode,
+) -> GeneratedMesh {
+    mesh_with_transitions(chunk, algorithm, normal_mode, &TransitionSamples::default())
+}
+
+/// Meshes `chunk` like [`mesh_standalone`], stitching its transition faces
+/// to the finer `transitions`.
+pub(crate) fn mesh_with_transitions(
+    chunk: &Chunk,
+    algorithm: MeshingAlgorithm,
+    normal_mode: NormalMode,
+    transitions: &TransitionSamples,
+) -> GeneratedMesh {
+    run_meshing(
+        algorithm,
+        chunk.size_x,
+        chunk.size_y,
+        chunk.size_z,
+        chunk.scale,
+        chunk.threshold,
+        normal_mode,
+        chunk.values(),
+        chunk.materials.as_deref(),
+        chunk.colors.as_deref(),
+        chunk.normals.as_deref(),
+        &ChunkHalo::default(),
+        transitions,
+        &ChunkPadding::default(),
+    )
+}
+
+/// Returns `true` if every directed edge of the meshes, each moved by its offset, is used
+/// once and matched by exactly one reversed twin.
+///
+/// With `weld`, vertices at the same position are treated as one, so separate meshes and
+/// vertices split for their attributes can close each other; without it, edges are only
+/// matched by index within each mesh.
+pub(crate) fn is_closed(meshes: &[(&GeneratedMesh, Vec3)], weld: bool) -> bool {
+    let mut ids: HashMap<[i64; 3], u32> = HashMap::new();
+    let mut edges: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut base = 0;
+    for (mesh, offset) in meshes {
+        let welded: Vec<u32> = if weld {
+            mesh.vertices
+                .iter()
+                .map(|&v| {
+                    let key = (Vec3::from(v) + *offset)
+                        .to_array()
+                        .map(|c| (c * 1e4).round() as i64);
+                    let next = ids.len() as u32;
+                    *ids.entry(key).or_insert(next)
+                })
+                .collect()
+        } else {
+            (base..base + mesh.vertices.len() as u32).collect()
+        };
+        base += mesh.vertices.len() as u32;
+        for triangle in mesh.indices.chunks_exact(3) {
+            for k in 0..3 {
+                let edge = (
+                    welded[triangle[k] as usize],
+                    welded[triangle[(k + 1) % 3] as usize],
+                );
+                *edges.entry(edge).or_default() += 1;
+            }
+        }
+    }
+    edges
+        .iter()
+        .all(|(&(a, b), &uses)| uses == 1 && edges.get(&(b, a)) == Some(&1))
+}
+
+/// Meshes `count` random fields of `size³` voxels whose border is outside, and returns
+/// the number of meshes where some directed edge isn't matched by exactly one reversed
+/// twin.
+pub(crate) fn count_non_manifold(algorithm: MeshingAlgorithm, size: usize, count: usize) -> usize {
+    let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+    let mut random = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed % 10_000) as Value / 5_000.0 - 1.0
+    };
+    let mut failures = 0;
+    for _ in 0..count {
+        let mut chunk = Chunk::new(size, size, size);
+        for z in 0..=size {
+            for y in 0..=size {
+                for x in 0..=size {
+                    let border = [x, y, z].iter().any(|&c| c == 0 || c == size);
+                    let value = random();
+                    chunk.set(x, y, z, if border { 1.0 } else { value });
+                }
+            }
+        }
+        let mesh = mesh_standalone(&chunk, algorithm, NormalMode::AreaWeighted);
+        if !is_closed(&[(&mesh, Vec3::ZERO)], false) {
+            failures += 1;
+        }
+    }
+    failures
+}
+
+/// Returns a headless app running `plugin`, with just enough of Bevy to mesh chunks.
+#[cfg(feature = "auto_queue")]
+pub(crate) fn test_app(plugin: crate::MarchingCubesPlugin) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), plugin))
+        .init_asset::<Mesh>();
+    app
+}
+
+/// Updates `app` until `done` holds, panicking if it still doesn't after a few seconds.
+#[cfg(any(feature = "auto_queue", feature = "serialize"))]
+pub(crate) fn update_until(app: &mut App, mut done: impl FnMut(&mut World) -> bool) {
+    for _ in 0..2000 {
+        app.update();
+        if done(app.world_mut()) {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    panic!("app never reached the expected state");
+}
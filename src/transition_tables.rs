@@ -0,0 +1,50 @@
+/// Corner indices of each cube face, in [`Face::ALL`](crate::neighbours::Face::ALL) order.
+///
+/// Corners are listed counter-clockwise as seen from outside the cube, using the corner
+/// numbering of [`CUBE_CORNER_OFFSETS`] (the same numbering `EDGE_TABLE` and `TRI_TABLE` use).
+/// Transition cells are traced face by face, so this winding is what makes the resulting
+/// triangles face out of the surface.
+pub const CUBE_FACE_CORNERS: [[usize; 4]; 6] = [
+    // -X
+    [0, 4, 7, 3],
+    // +X
+    [1, 2, 6, 5],
+    // -Y
+    [0, 1, 5, 4],
+    // +Y
+    [3, 7, 6, 2],
+    // -Z
+    [0, 3, 2, 1],
+    // +Z
+    [4, 5, 6, 7],
+];
+
+/// Offset of each cube corner from the voxel's minimum corner, in corner order.
+pub const CUBE_CORNER_OFFSETS: [[usize; 3]; 8] = [
+    [0, 0, 0],
+    [1, 0, 0],
+    [1, 1, 0],
+    [0, 1, 0],
+    [0, 0, 1],
+    [1, 0, 1],
+    [1, 1, 1],
+    [0, 1, 1],
+];
+
+/// Splits a face that borders a finer neighbour into four quads.
+///
+/// A subdivided face has 9 samples, numbered around its boundary starting at its first
+/// corner, with the face centre last:
+///
+/// ```text
+///   c3 --- m23 --- c2          6 --- 5 --- 4
+///    |      |      |           |     |     |
+///   m30 --- C --- m12    →     7 --- 8 --- 3
+///    |      |      |           |     |     |
+///   c0 --- m01 --- c1          0 --- 1 --- 2
+/// ```
+///
+/// where `c0..c3` follow [`CUBE_FACE_CORNERS`]. Each quad keeps the parent's
+/// counter-clockwise winding.
+pub const SUBDIVIDED_FACE_QUADS: [[usize; 4]; 4] =
+    [[0, 1, 8, 7], [1, 2, 3, 8], [8, 3, 4, 5], [7, 8, 5, 6]];
@@ -0,0 +1,577 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    ambiguity::{corner_index, face_joins_inside, tunnel},
+    chunk::Chunk,
+    grid::VoxelGrid,
+    interp::{interpolate_colors, interpolate_points},
+    neighbours::{ChunkMap, Face},
+    transition_tables::{CUBE_CORNER_OFFSETS, CUBE_FACE_CORNERS, SUBDIVIDED_FACE_QUADS},
+    types::Value,
+    utils::edge_t,
+};
+
+/// Set of [`Face`]s of a [`Chunk`] that border a neighbour sampled at twice its resolution.
+///
+/// Mark the faces of a coarse chunk that touch finer chunks so the mesher replaces the cells
+/// along those faces with transition cells:
+///
+/// ```rust,ignore
+/// // 16 voxels at scale 2.0 next to a 32-voxel chunk at scale 1.0 on its +X side:
+/// let coarse = Chunk::new(16, 16, 16)
+///     .with_scale(2.0)
+///     .with_transition_faces(TransitionFaces::NONE.with(Face::PosX));
+/// ```
+///
+/// The finer chunk needs nothing special; it meshes with regular cells as usual. Both chunks
+/// need a [`ChunkCoord`](crate::neighbours::ChunkCoord) and must cover the same extent.
+///
+/// Only neighbours at exactly twice the resolution are stitched. A transition face whose
+/// neighbour has any other resolution is skipped by [`TransitionSamples::gather`] and meshed
+/// with regular cells, which leaves cracks along it, so put a ring of chunks at every level
+/// between 1x and 8x.
+///
+/// Limitations:
+/// - Faces where the two sides disagree on an ambiguous marching cubes case can still leave
+///   a small hole, as between any two classic marching cubes cells, unless the chunk uses
+///   [`MeshingAlgorithm::MarchingCubes33`](crate::mesh::MeshingAlgorithm::MarchingCubes33).
+/// - Chunks that only touch along an edge or corner should agree on which faces transition,
+///   otherwise the cells along that edge don't line up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TransitionFaces(u8);
+
+impl TransitionFaces {
+    /// No transition faces.
+    pub const NONE: Self = Self(0);
+    /// All six faces.
+    pub const ALL: Self = Self(0b11_1111);
+
+    /// Returns a copy with `face` added.
+    pub const fn with(self, face: Face) -> Self {
+        Self(self.0 | 1 << face as u8)
+    }
+
+    /// Returns a copy with `face` removed.
+    pub const fn without(self, face: Face) -> Self {
+        Self(self.0 & !(1 << face as u8))
+    }
+
+    /// Returns `true` if `face` is a transition face.
+    pub const fn contains(self, face: Face) -> bool {
+        self.0 & (1 << face as u8) != 0
+    }
+
+    /// Returns `true` if no face is marked.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Iterates over the marked faces.
+    pub fn iter(self) -> impl Iterator<Item = Face> {
+        Face::ALL
+            .into_iter()
+            .filter(move |&face| self.contains(face))
+    }
+}
+
+/// Full-resolution samples across each transition face of a chunk.
+///
+/// Each face holds the finer neighbour's samples on the shared plane: a [`VoxelGrid`] with
+/// one corner along the face axis and `2 * size + 1` corners along the other two.
+///
+/// ```text
+/// coarse face corners:  o-------o-------o
+/// fine face samples:    o---o---o---o---o
+/// ```
+///
+/// Gathered from the [`ChunkMap`] when a chunk with [`TransitionFaces`] is queued for
+/// meshing, or filled by hand with [`TransitionSamples::set`].
+#[derive(Debug, Clone, Default)]
+pub struct TransitionSamples {
+    faces: [Option<VoxelGrid>; 6],
+}
+
+impl TransitionSamples {
+    /// Collects the fine samples for every transition face of `chunk` from its neighbours.
+    ///
+    /// `get_chunk` resolves a neighbour entity to its [`Chunk`]. Faces whose neighbour is
+    /// missing or isn't exactly twice as fine are skipped and mesh as regular cells.
+    pub fn gather<'a>(
+        chunk: &Chunk,
+        coord: IVec3,
+        map: &ChunkMap,
+        get_chunk: impl Fn(Entity) -> Option<&'a Chunk>,
+    ) -> Self {
+        let dims = chunk.values().dims();
+        let mut samples = Self::default();
+        for face in chunk.transition_faces.iter() {
+            let Some(other) = map.neighbour(coord, face).and_then(&get_chunk) else {
+                continue;
+            };
+            let axis = face.axis();
+            let other_dims = other.values().dims();
+            if (0..3).any(|a| a != axis && other_dims[a] != fine_count(dims[a])) {
+                continue;
+            }
+            let index = if face.is_positive() {
+                0
+            } else {
+                other_dims[axis] - 1
+            };
+            samples.faces[face.index()] = Some(other.values().layer(axis, index));
+        }
+        samples
+    }
+
+    /// Sets the fine samples for `face` of a chunk whose corner counts are `dims`.
+    ///
+    /// # Panics
+    /// Panics if `layer` isn't one corner thick along the face axis with `2 * size + 1`
+    /// corners along the other two.
+    pub fn set(&mut self, dims: [usize; 3], face: Face, layer: VoxelGrid) {
+        let mut expected = dims.map(fine_count);
+        expected[face.axis()] = 1;
+        assert_eq!(
+            layer.dims(),
+            expected,
+            "transition samples have the wrong size"
+        );
+        self.faces[face.index()] = Some(layer);
+    }
+
+    /// Returns `true` if no face has samples.
+    pub fn is_empty(&self) -> bool {
+        self.faces.iter().all(Option::is_none)
+    }
+}
+
+/// Number of fine samples spanning `corners` coarse corners.
+#[inline]
+pub(crate) fn fine_count(corners: usize) -> usize {
+    corners.saturating_sub(1) * 2 + 1
+}
+
+/// Flag distinguishing half-edge vertex keys from the regular keys produced by
+/// [`edge_key`](crate::utils::edge_key).
+const HALF_EDGE_KEY: usize = 1 << (usize::BITS - 1);
+
+/// Flag marking the keys of vertices added at the centre of a transition cell loop.
+const LOOP_CENTRE_KEY: usize = 1 << (usize::BITS - 2);
+
+/// One vertex of a transition cell triangle: an edge crossing between two samples.
+///
+/// Samples are addressed on the fine lattice, where coarse corner `(x, y, z)` sits at
+/// `(2x, 2y, 2z)`. `lo` is always the sample nearer the origin.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TransitionCrossing {
+    /// Key shared by every cell that contains this edge.
+    pub key: usize,
+    /// Fine lattice position of the lower endpoint.
+    pub lo: [usize; 3],
+    /// Fine lattice position of the upper endpoint.
+    pub hi: [usize; 3],
+    /// Where along `lo → hi` the surface crosses.
+    pub t: Value,
+    /// Coarse corner at or just below the inside endpoint, whose material the vertex takes.
+    pub inside_corner: [usize; 3],
+}
+
+impl TransitionCrossing {
+    /// Returns the chunk-space position of the crossing for a chunk of voxel size `scale`.
+    pub fn position(&self, scale: Value) -> [f32; 3] {
+        let half = scale * 0.5;
+        interpolate_points(
+            self.lo.map(|c| c as Value * half),
+            self.hi.map(|c| c as Value * half),
+            self.t,
+        )
+    }
+
+    /// Returns the field gradient at the crossing.
+    ///
+    /// `gradient` gives the gradient at a coarse corner. Fine samples between coarse
+    /// corners take the average of the corners around them.
+    pub fn gradient(&self, gradient: impl Fn([usize; 3]) -> [f32; 3]) -> [f32; 3] {
+        interpolate_points(
+            coarse_average(self.lo, &gradient),
+            coarse_average(self.hi, &gradient),
+            self.t,
+        )
+    }
+
+    /// Returns the colour at the crossing.
+    ///
+    /// `color` gives the colour at a coarse corner, averaged like
+    /// [`gradient`](TransitionCrossing::gradient) for fine samples between corners.
+    pub fn color(&self, color: impl Fn([usize; 3]) -> [f32; 4]) -> [f32; 4] {
+        interpolate_colors(
+            coarse_average(self.lo, &color),
+            coarse_average(self.hi, &color),
+            self.t,
+        )
+    }
+}
+
+/// Averages `at` over the coarse corners around fine lattice position `p`: one per axis
+/// where it sits on a corner, two otherwise.
+fn coarse_average<const N: usize>(p: [usize; 3], at: impl Fn([usize; 3]) -> [f32; N]) -> [f32; N] {
+    let span = p.map(|c| (c / 2, c.div_ceil(2)));
+    let mut sum = [0.0; N];
+    let mut count = 0.0;
+    for z in [span[2].0, span[2].1] {
+        for y in [span[1].0, span[1].1] {
+            for x in [span[0].0, span[0].1] {
+                for (s, v) in sum.iter_mut().zip(at([x, y, z])) {
+                    *s += v;
+                }
+                count += 1.0;
+            }
+        }
+    }
+    sum.map(|s| s / count)
+}
+
+/// Builds transition cells for a chunk from its own values and its [`TransitionSamples`].
+///
+/// A transition cell is a voxel touching one or more transition faces. Each such face of
+/// the voxel is split into four quads using the fine samples, and every voxel edge lying on
+/// a split face gains a midpoint sample. The cell is then a closed polyhedron of quads and
+/// pentagons (or larger polygons at chunk edges) whose outer faces match the finer
+/// neighbour's voxels exactly and whose inner faces match the regular voxels behind it.
+///
+/// ```text
+///   fine neighbour | transition cell | regular cell
+///                  |                 |
+///      o---o---o---o-------o         o-------o
+///      |   |   |   m       |         |       |
+///      o---o---o---o-------o         o-------o
+/// ```
+///
+/// The surface is extracted by tracing the iso-line across every face polygon and joining
+/// the segments into closed loops, which are then fanned into triangles around their centre.
+/// On faces with more than one inside run, each run is cut off by its own segment, and both
+/// cells sharing a face apply the same rule, so the result is watertight.
+///
+/// With `resolve_ambiguities`, unsplit faces with inside corners on one diagonal follow
+/// [`face_joins_inside`] instead, and the same tracing meshes ambiguous regular voxels; see
+/// [`MeshingAlgorithm::MarchingCubes33`](crate::mesh::MeshingAlgorithm::MarchingCubes33).
+pub(crate) struct TransitionCells<'a> {
+    values: &'a VoxelGrid,
+    samples: &'a TransitionSamples,
+    size: [usize; 3],
+    fine_dims: [usize; 3],
+    threshold: Value,
+    resolve_ambiguities: bool,
+}
+
+impl<'a> TransitionCells<'a> {
+    pub(crate) fn new(
+        values: &'a VoxelGrid,
+        samples: &'a TransitionSamples,
+        threshold: Value,
+        resolve_ambiguities: bool,
+    ) -> Self {
+        let dims = values.dims();
+        Self {
+            values,
+            samples,
+            size: dims.map(|d| d.saturating_sub(1)),
+            fine_dims: dims.map(fine_count),
+            threshold,
+            resolve_ambiguities,
+        }
+    }
+
+    /// Returns a bitmask of the cube faces of voxel `(x, y, z)` that are split, indexed by
+    /// [`Face::index`]. `0` means the voxel is a regular cell.
+    #[inline]
+    pub(crate) fn cell_mask(&self, x: usize, y: usize, z: usize) -> u8 {
+        let p = [x, y, z];
+        let mut mask = 0;
+        for face in Face::ALL {
+            if self.samples.faces[face.index()].is_none() {
+                continue;
+            }
+            let axis = face.axis();
+            let on_face = if face.is_positive() {
+                p[axis] + 1 == self.size[axis]
+            } else {
+                p[axis] == 0
+            };
+            if on_face {
+                mask |= 1 << face.index();
+            }
+        }
+        mask
+    }
+
+    /// Returns the value at fine lattice point `p`.
+    ///
+    /// Coarse corners read the chunk's own values. Other points read the fine samples of the
+    /// first split face (in [`Face::ALL`] order) that contains them.
+    pub(crate) fn fine_value(&self, p: [usize; 3]) -> Value {
+        if p.iter().all(|c| c % 2 == 0) {
+            return self.values.get(p[0] / 2, p[1] / 2, p[2] / 2);
+        }
+        for face in Face::ALL {
+            let Some(layer) = &self.samples.faces[face.index()] else {
+                continue;
+            };
+            let axis = face.axis();
+            let plane = if face.is_positive() {
+                self.fine_dims[axis] - 1
+            } else {
+                0
+            };
+            if p[axis] == plane {
+                let mut q = p;
+                q[axis] = 0;
+                return layer.get(q[0], q[1], q[2]);
+            }
+        }
+        panic!("fine sample {p:?} is not on a transition face");
+    }
+
+    /// Extracts the surface loops of the transition cell at voxel `(x, y, z)`.
+    ///
+    /// `mask` is the voxel's [`cell_mask`](Self::cell_mask), or `0` to trace a regular
+    /// voxel. Each loop is wound counter-clockwise as seen from outside the surface.
+    pub(crate) fn loops(
+        &self,
+        x: usize,
+        y: usize,
+        z: usize,
+        mask: u8,
+    ) -> Vec<Vec<TransitionCrossing>> {
+        let base = [x * 2, y * 2, z * 2];
+        let corner = |c: usize| {
+            let o = CUBE_CORNER_OFFSETS[c];
+            [base[0] + o[0] * 2, base[1] + o[1] * 2, base[2] + o[2] * 2]
+        };
+        let midpoint = |a: [usize; 3], b: [usize; 3]| {
+            [(a[0] + b[0]) / 2, (a[1] + b[1]) / 2, (a[2] + b[2]) / 2]
+        };
+        let split = |face: usize| mask & (1 << face) != 0;
+        // A voxel edge gains a midpoint if it lies on any split face.
+        let edge_split = |a: usize, b: usize| {
+            (0..6).any(|f| {
+                split(f) && CUBE_FACE_CORNERS[f].contains(&a) && CUBE_FACE_CORNERS[f].contains(&b)
+            })
+        };
+
+        let mut polygons: Vec<Vec<[usize; 3]>> = Vec::with_capacity(12);
+        for (f, corners) in CUBE_FACE_CORNERS.iter().enumerate() {
+            if split(f) {
+                let [c0, c1, c2, c3] = corners.map(corner);
+                let ring = [
+                    c0,
+                    midpoint(c0, c1),
+                    c1,
+                    midpoint(c1, c2),
+                    c2,
+                    midpoint(c2, c3),
+                    c3,
+                    midpoint(c3, c0),
+                    midpoint(c0, c2),
+                ];
+                for quad in SUBDIVIDED_FACE_QUADS {
+                    polygons.push(quad.iter().map(|&i| ring[i]).collect());
+                }
+            } else {
+                let mut polygon = Vec::with_capacity(8);
+                for i in 0..4 {
+                    let (a, b) = (corners[i], corners[(i + 1) % 4]);
+                    polygon.push(corner(a));
+                    if edge_split(a, b) {
+                        polygon.push(midpoint(corner(a), corner(b)));
+                    }
+                }
+                polygons.push(polygon);
+            }
+        }
+
+        let inside = |p: [usize; 3]| self.fine_value(p) <= self.threshold;
+
+        // Each inside run along a polygon's boundary contributes one segment from the edge
+        // where the walk enters it to the edge where it leaves. Where the inside corners of
+        // an ambiguous face are joined, each outside run is cut off instead, walking back.
+        let mut next: BTreeMap<([usize; 3], [usize; 3]), ([usize; 3], [usize; 3])> =
+            BTreeMap::new();
+        for polygon in &polygons {
+            let n = polygon.len();
+            let states: Vec<bool> = polygon.iter().map(|&p| inside(p)).collect();
+            let cut_outside = self.joins_inside(polygon, &states);
+            for i in 0..n {
+                let prev = (i + n - 1) % n;
+                if states[i] == cut_outside || states[prev] != cut_outside {
+                    continue;
+                }
+                let entry = (polygon[prev], polygon[i]);
+                let mut j = i;
+                while states[(j + 1) % n] != cut_outside {
+                    j = (j + 1) % n;
+                }
+                let exit = (polygon[j], polygon[(j + 1) % n]);
+                if cut_outside {
+                    next.insert(undirected(exit), undirected(entry));
+                } else {
+                    next.insert(undirected(entry), undirected(exit));
+                }
+            }
+        }
+
+        let mut loops = Vec::new();
+        while let Some(&start) = next.keys().next() {
+            let mut ring = Vec::new();
+            let mut edge = start;
+            while let Some(following) = next.remove(&edge) {
+                ring.push(self.crossing(edge));
+                edge = following;
+            }
+            loops.push(ring);
+        }
+        loops
+    }
+
+    /// Returns `true` if `polygon` is an unsplit face whose inside corners lie on one diagonal
+    /// and [`face_joins_inside`] joins them. Always `false` without `resolve_ambiguities`.
+    fn joins_inside(&self, polygon: &[[usize; 3]], states: &[bool]) -> bool {
+        let [a, b, c, d] = match polygon {
+            &[a, b, c, d] if self.resolve_ambiguities => [a, b, c, d],
+            _ => return false,
+        };
+        if states[0] != states[2] || states[1] != states[3] || states[0] == states[1] {
+            return false;
+        }
+        let (ins, outs) = if states[0] {
+            ([a, c], [b, d])
+        } else {
+            ([b, d], [a, c])
+        };
+        face_joins_inside(
+            ins.map(|p| self.fine_value(p)),
+            outs.map(|p| self.fine_value(p)),
+            self.threshold,
+        )
+    }
+
+    /// Returns the indices of two `loops` of the regular voxel at `(x, y, z)` to join with a
+    /// tube through its interior instead of capping each; see
+    /// [`MeshingAlgorithm::MarchingCubes33`](crate::mesh::MeshingAlgorithm::MarchingCubes33).
+    pub(crate) fn tunnel(
+        &self,
+        x: usize,
+        y: usize,
+        z: usize,
+        loops: &[Vec<TransitionCrossing>],
+    ) -> Option<(usize, usize)> {
+        let base = [x, y, z];
+        let corners = CUBE_CORNER_OFFSETS.map(|o| {
+            self.values
+                .get(base[0] + o[0], base[1] + o[1], base[2] + o[2])
+        });
+        let corner_of = |p: [usize; 3]| corner_index([0, 1, 2].map(|a| p[a] / 2 - base[a]));
+        let sides: Vec<(usize, usize)> = loops
+            .iter()
+            .map(|ring| {
+                let crossing = &ring[0];
+                let (lo, hi) = (corner_of(crossing.lo), corner_of(crossing.hi));
+                if corners[lo] <= self.threshold {
+                    (lo, hi)
+                } else {
+                    (hi, lo)
+                }
+            })
+            .collect();
+        tunnel(&corners, self.threshold, &sides)
+    }
+
+    /// Returns the vertex key for the centre of loop `n` of the transition cell at voxel
+    /// `(x, y, z)`. Loop centres are never shared with other cells.
+    ///
+    /// Keys numbered past the last loop are free for other vertices inside the cell, such as
+    /// the ring of a [tunnel](Self::tunnel). Up to 16 keys are available per cell.
+    pub(crate) fn centre_key(&self, x: usize, y: usize, z: usize, n: usize) -> usize {
+        debug_assert!(n < 16, "too many vertices inside one transition cell");
+        LOOP_CENTRE_KEY | (self.values.index(x, y, z) << 4 | n)
+    }
+
+    /// Builds the crossing on the edge between fine lattice points `lo` and `hi`.
+    fn crossing(&self, (lo, hi): ([usize; 3], [usize; 3])) -> TransitionCrossing {
+        let axis = (0..3).find(|&a| lo[a] != hi[a]).expect("degenerate edge");
+        let key = if hi[axis] - lo[axis] == 2 {
+            // Full voxel edge: shared with regular cells, so use the regular key.
+            let [cx, cy, cz] = lo.map(|c| c / 2);
+            self.values.index(cx, cy, cz) * 3 + axis
+        } else {
+            let [fx, fy, _] = self.fine_dims;
+            HALF_EDGE_KEY | (((lo[2] * fy + lo[1]) * fx + lo[0]) * 3 + axis)
+        };
+        let (v_lo, v_hi) = (self.fine_value(lo), self.fine_value(hi));
+        let inside = if v_lo <= self.threshold { lo } else { hi };
+        TransitionCrossing {
+            key,
+            lo,
+            hi,
+            t: edge_t(v_lo, v_hi, self.threshold),
+            inside_corner: inside.map(|c| c / 2),
+        }
+    }
+}
+
+/// Orders an edge's endpoints so the one nearer the origin comes first.
+#[inline]
+fn undirected((a, b): ([usize; 3], [usize; 3])) -> ([usize; 3], [usize; 3]) {
+    if a <= b { (a, b) } else { (b, a) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mesh::{MeshingAlgorithm, NormalMode},
+        test_support::{is_closed, mesh_standalone, mesh_with_transitions},
+    };
+
+    #[test]
+    fn transition_seam_is_closed() {
+        for centre in [
+            Vec3::new(16.0, 8.0, 8.0),
+            Vec3::new(16.3, 8.1, 7.7),
+            Vec3::new(15.5, 7.9, 8.2),
+            Vec3::new(17.1, 8.4, 7.6),
+        ] {
+            let sphere = move |x: f32, y: f32, z: f32| Vec3::new(x, y, z).distance(centre) - 5.3;
+            let mut coarse = Chunk::new(8, 8, 8)
+                .with_scale(2.0)
+                .with_transition_faces(TransitionFaces::NONE.with(Face::PosX));
+            coarse.fill(&sphere);
+            let mut fine = Chunk::new(16, 16, 16);
+            fine.fill(&move |x, y, z| sphere(x + 16.0, y, z));
+            let mut samples = TransitionSamples::default();
+            samples.set(
+                coarse.values().dims(),
+                Face::PosX,
+                fine.values().layer(0, 0),
+            );
+
+            for algorithm in [
+                MeshingAlgorithm::MarchingCubes,
+                MeshingAlgorithm::MarchingCubes33,
+            ] {
+                let fine = mesh_standalone(&fine, algorithm, NormalMode::AreaWeighted);
+                let stitched =
+                    mesh_with_transitions(&coarse, algorithm, NormalMode::AreaWeighted, &samples);
+                let cracked = mesh_standalone(&coarse, algorithm, NormalMode::AreaWeighted);
+                let offset = Vec3::new(16.0, 0.0, 0.0);
+                assert!(
+                    is_closed(&[(&stitched, Vec3::ZERO), (&fine, offset)], true),
+                    "{algorithm:?} at {centre}"
+                );
+                assert!(!is_closed(&[(&cracked, Vec3::ZERO), (&fine, offset)], true));
+            }
+        }
+    }
+}
@@ -1,6 +1,11 @@
 /// Scalar field value at a point in space.
 pub type Value = f32;
 
+/// Material of a voxel corner, used to texture the surface next to it.
+///
+/// `0` is the default material of chunks that never had one set.
+pub type MaterialId = u8;
+
 /// A scalar field function: maps `(x, y, z)` coordinates to a [`Value`].
 ///
 /// Return values **below or equal to** the chunk's threshold are considered "inside" the surface.
@@ -1,8 +1,9 @@
 #[cfg(feature = "interpolate_midpoints")]
-use crate::interp::{find_t, interpolate_points};
+use crate::interp::find_t;
 use crate::{
     error::{MarchingCubesError, Result},
-    tables::TRI_TABLE,
+    interp::interpolate_points,
+    tables::{EDGE_ORIGINS, TRI_TABLE},
     types::Value,
 };
 
@@ -27,6 +28,39 @@ pub fn triangle_verts_from_state(
         .collect()
 }
 
+/// Returns the edge indices of every triangle vertex for a given marching cubes `state`.
+///
+/// Same walk over `TRI_TABLE[state]` as [`triangle_verts_from_state`], but yields the edge
+/// index instead of its position so callers can share vertices between triangles.
+#[inline]
+pub fn triangle_edges_from_state(state: usize) -> impl Iterator<Item = usize> {
+    TRI_TABLE[state]
+        .iter()
+        .take_while(|&&v| v != -1)
+        .map(|&t| t as usize)
+}
+
+/// Returns a key that uniquely identifies `edge` of the voxel at `(x, y, z)` within the grid.
+///
+/// `corners_x` and `corners_y` are the number of grid corners along X and Y (`size + 1`).
+/// Adjacent voxels that share an edge produce the same key:
+///
+/// ```text
+/// key = corner_index(origin) * 3 + axis
+/// ```
+#[inline]
+pub fn edge_key(
+    x: usize,
+    y: usize,
+    z: usize,
+    edge: usize,
+    corners_x: usize,
+    corners_y: usize,
+) -> usize {
+    let [dx, dy, dz, axis] = EDGE_ORIGINS[edge];
+    (((z + dz) * corners_y + (y + dy)) * corners_x + (x + dx)) * 3 + axis
+}
+
 /// Returns the 8 world-space corner positions of the voxel at grid index `(x, y, z)`.
 ///
 /// Corners are ordered to match the standard marching cubes convention:
@@ -129,24 +163,77 @@ pub fn get_edge_midpoints(
         let pair = point_indices[i];
         let pi = corner_positions[pair[0] as usize];
         let pf = corner_positions[pair[1] as usize];
+        let vi = corner_values[pair[0] as usize];
+        let vf = corner_values[pair[1] as usize];
 
-        #[cfg(feature = "interpolate_midpoints")]
-        {
-            let vi = corner_values[pair[0] as usize];
-            let vf = corner_values[pair[1] as usize];
-            let t = find_t(vi, vf, threshold);
-            edge_points[i] = Some(interpolate_points(pi, pf, t));
-        }
-        #[cfg(not(feature = "interpolate_midpoints"))]
-        {
-            let _ = (corner_values, threshold);
-            edge_points[i] = Some([
-                (pi[0] + pf[0]) * 0.5,
-                (pi[1] + pf[1]) * 0.5,
-                (pi[2] + pf[2]) * 0.5,
-            ]);
-        }
+        edge_points[i] = Some(interpolate_points(pi, pf, edge_t(vi, vf, threshold)));
     }
 
     edge_points
 }
+
+/// Returns the factor `t ∈ [0, 1]` at which a vertex is placed along an edge whose
+/// endpoints hold `v0` and `v1`.
+///
+/// With the `interpolate_midpoints` feature this is the exact iso-crossing from
+/// [`find_t`](crate::interp::find_t); otherwise vertices sit at the edge midpoint (`0.5`).
+#[inline]
+pub fn edge_t(v0: Value, v1: Value, threshold: Value) -> Value {
+    #[cfg(feature = "interpolate_midpoints")]
+    {
+        find_t(v0, v1, threshold)
+    }
+    #[cfg(not(feature = "interpolate_midpoints"))]
+    {
+        let _ = (v0, v1, threshold);
+        0.5
+    }
+}
+
+/// Estimates the field gradient at grid corner `(x, y, z)` using central differences.
+///
+/// `sample(x, y, z)` returns the value at a corner, or `None` if it lies outside the
+/// available data; on such sides the estimate falls back to a one-sided difference.
+/// Samplers that can reach one step past the grid (see
+/// [`ChunkHalo`](crate::neighbours::ChunkHalo)) get true central differences on the
+/// boundary too. Because corners inside the surface are `≤ threshold`, the gradient
+/// points out of the surface.
+///
+/// ```text
+/// ∂f/∂x ≈ (f(x+1) - f(x-1)) / (2 · scale)
+/// ```
+#[inline]
+pub fn corner_gradient(
+    x: usize,
+    y: usize,
+    z: usize,
+    scale: Value,
+    sample: impl Fn(isize, isize, isize) -> Option<Value>,
+) -> [f32; 3] {
+    let p = [x as isize, y as isize, z as isize];
+    let centre = sample(p[0], p[1], p[2]).expect("gradient sampled outside the grid");
+    let mut gradient = [0.0; 3];
+    for (axis, g) in gradient.iter_mut().enumerate() {
+        let (mut lo, mut hi) = (p, p);
+        lo[axis] -= 1;
+        hi[axis] += 1;
+        *g = match (sample(lo[0], lo[1], lo[2]), sample(hi[0], hi[1], hi[2])) {
+            (Some(l), Some(h)) => (h - l) / (2.0 * scale),
+            (None, Some(h)) => (h - centre) / scale,
+            (Some(l), None) => (centre - l) / scale,
+            (None, None) => 0.0,
+        };
+    }
+    gradient
+}
+
+/// Normalises `v`, returning the zero vector if it has zero length.
+#[inline]
+pub fn normalize_or_zero(v: [f32; 3]) -> [f32; 3] {
+    let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if len == 0.0 {
+        [0.0, 0.0, 0.0]
+    } else {
+        [v[0] / len, v[1] / len, v[2] / len]
+    }
+}